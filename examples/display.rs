@@ -30,6 +30,7 @@ fn main() {
         },
         &DrawOptions::new(),
     );
+    ctlr.commit_display(0).unwrap();
 
     loop {
         // Allow controller to do work and update any events
@@ -0,0 +1,44 @@
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle, Rectangle},
+    text::{Alignment, Text},
+};
+use maschine::{get_device, EventContext};
+
+fn main() {
+    let mut ctlr = get_device().unwrap();
+
+    let fb = ctlr.get_framebuffer(0).unwrap();
+    let size = fb.size();
+
+    Rectangle::new(Point::zero(), size)
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(fb)
+        .unwrap();
+    Circle::with_center(Point::new(size.width as i32 / 2, 28), 30)
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
+        .draw(fb)
+        .unwrap();
+    Text::with_alignment(
+        "embedded-graphics",
+        Point::new(size.width as i32 / 2, 58),
+        MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+        Alignment::Center,
+    )
+    .draw(fb)
+    .unwrap();
+    ctlr.commit_display(0).unwrap();
+
+    loop {
+        // Allow controller to do work and update any events
+        let mut context = EventContext::new();
+        ctlr.tick(&mut context).unwrap();
+
+        // Handle any generated events
+        while let Some(event) = context.events.pop_front() {
+            println!("{event:?}");
+        }
+    }
+}
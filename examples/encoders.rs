@@ -1,19 +1,19 @@
-use font_kit::{font::Font, handle::Handle};
+use maschine::display::{font::FONT_8X13, Alignment, DrawText, TextStyle};
 use maschine::{get_device, Event, EventContext};
-use raqote::{DrawOptions, DrawTarget, LineCap, PathBuilder, SolidSource, Source, StrokeStyle, Point, AntialiasMode};
-use std::sync::Arc;
-
-const FONT_BYTES: &[u8] = include_bytes!("fonts/Andale_Mono.ttf");
+use raqote::{
+    AntialiasMode, DrawOptions, DrawTarget, IntRect, LineCap, PathBuilder, SolidSource, Source,
+    StrokeStyle,
+};
 
 fn main() {
-    let font = Handle::from_memory(Arc::new(FONT_BYTES.into()), 0)
-        .load()
-        .unwrap();
-
     let mut ctlr = get_device().unwrap();
+    let capabilities = ctlr.capabilities();
+    for i in 0..capabilities.displays.len() {
+        ctlr.set_max_fps(i as u8, Some(30)).unwrap();
+    }
 
-    let mut encoder_states: [f32; 9] = [0.5; 9];
-    let mut should_draw: [bool; 2] = [true; 2];
+    let mut encoder_states = vec![0.5; capabilities.encoders.len()];
+    let mut should_draw = vec![true; capabilities.displays.len()];
     loop {
         // Allow controller to do work and update any events
         let mut context = EventContext::new();
@@ -22,36 +22,33 @@ fn main() {
         // Handle any generated events
         while !context.events.is_empty() {
             let event = context.events.pop_front().unwrap();
-            match event {
-                Event::Encoder(idx, _value, delta, _shift) => {
-                    let range = if idx == 0 { 16. } else { 1024. };
-                    encoder_states[idx as usize] =
-                        (encoder_states[idx as usize] + delta as f32 / range).clamp(0., 1.);
-                    if (1..=4).contains(&idx) {
-                        should_draw[0] = true;
-                    } else if (5..=8).contains(&idx) {
-                        should_draw[1] = true;
+            if let Event::Encoder(idx, _value, delta, _shift) = event {
+                let range = capabilities.encoders[idx as usize].range as f32;
+                encoder_states[idx as usize] =
+                    (encoder_states[idx as usize] + delta as f32 / range).clamp(0., 1.);
+                // Encoders 1-4 are shown on the first display, 5-8 on the second
+                if idx > 0 {
+                    if let Some(d) = should_draw.get_mut((idx as usize - 1) / 4) {
+                        *d = true;
                     }
                 }
-                _ => {}
             }
         }
         for (i, d) in should_draw.iter().enumerate() {
             if *d {
+                let end = encoder_states.len().min(5 + 4 * i);
                 draw(
-                    &mut ctlr.get_display(i as u8).unwrap(),
-                    &encoder_states[(1 + 4 * i)..=(4 + 4 * i)]
-                        .try_into()
-                        .unwrap(),
-                    &font,
+                    ctlr.get_display(i as u8).unwrap(),
+                    encoder_states.get((1 + 4 * i)..end).unwrap_or_default(),
                 );
+                ctlr.commit_display(i as u8).unwrap();
             }
         }
-        should_draw = [false; 2];
+        should_draw.fill(false);
     }
 }
 
-fn draw(dt: &mut DrawTarget, encoder_states: &[f32; 4], font: &Font) {
+fn draw(dt: &mut DrawTarget, encoder_states: &[f32]) {
     const RADIUS: f32 = 20.;
     const OFFSET: f32 = 10.;
     const ANGLE_EXTENT: f32 = std::f32::consts::PI * 0.75;
@@ -77,8 +74,11 @@ fn draw(dt: &mut DrawTarget, encoder_states: &[f32; 4], font: &Font) {
         a: 0x00,
     });
 
-    let font_metrics = font.metrics();
-    let font_char_width = font_metrics.bounding_box.width() / font_metrics.units_per_em as f32;
+    let text_style = TextStyle {
+        font: FONT_8X13,
+        alignment: Alignment::Center,
+        ..Default::default()
+    };
 
     for (i, state) in encoder_states.iter().enumerate() {
         let mut pb = PathBuilder::new();
@@ -97,9 +97,14 @@ fn draw(dt: &mut DrawTarget, encoder_states: &[f32; 4], font: &Font) {
                 width: 4f32,
                 ..Default::default()
             },
-            &draw_opts
+            &draw_opts,
         );
 
-        dt.draw_text(font, 15., format!("E{i}").as_str(), Point::new(x - font_char_width * 15., y + 7.) , &src, &draw_opts);
+        let (x, y) = (x as i32, y as i32);
+        dt.draw_bitmap_text(
+            format!("E{i}").as_str(),
+            IntRect::new((x - 12, y - 7).into(), (x + 12, y + 7).into()),
+            &text_style,
+        );
     }
 }
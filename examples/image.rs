@@ -0,0 +1,28 @@
+use maschine::display::{Fit, Image};
+use maschine::{get_device, DitherMode, EventContext};
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .expect("usage: image <path to PNG, BMP or GIF>");
+    let image = Image::open(path).unwrap();
+
+    let mut ctlr = get_device().unwrap();
+    let fb = ctlr.get_framebuffer(0).unwrap();
+    let (width, height) = (fb.width(), fb.height());
+
+    let mut animation = image.animate(width, height, Fit::Contain, DitherMode::FloydSteinberg);
+
+    loop {
+        animation.update(ctlr.as_mut(), 0).unwrap();
+
+        // Allow controller to do work and update any events
+        let mut context = EventContext::new();
+        ctlr.tick(&mut context).unwrap();
+
+        // Handle any generated events
+        while let Some(event) = context.events.pop_front() {
+            println!("{event:?}");
+        }
+    }
+}
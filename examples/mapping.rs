@@ -0,0 +1,30 @@
+use maschine::mapping::ProfileWatcher;
+use maschine::{get_device, EventContext};
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "examples/profiles/transport.toml".to_string());
+    let mut ctlr = get_device().unwrap();
+    let mut watcher = ProfileWatcher::new(path, &ctlr.capabilities()).unwrap();
+
+    loop {
+        // Pick up edits to the profile while running
+        match watcher.poll() {
+            Ok(true) => println!("Reloaded {:?}", watcher.profile().name),
+            Ok(false) => {}
+            Err(e) => println!("{e}"),
+        }
+
+        // Allow controller to do work and update any events
+        let mut context = EventContext::new();
+        ctlr.tick(&mut context).unwrap();
+
+        // Handle any generated events
+        while let Some(event) = context.events.pop_front() {
+            if let Some(action) = watcher.profile().resolve(&event) {
+                println!("{event:?} => {action:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use maschine::sequencer::Sequencer;
+use maschine::{get_device, EventContext};
+use std::time::Instant;
+
+fn main() {
+    let mut ctlr = get_device().unwrap();
+
+    // Four drum tracks, print the notes instead of sending them
+    let mut sequencer = Sequencer::new(4, |note| println!("{note:?}"));
+
+    loop {
+        // Allow controller to do work and update any events
+        let mut context = EventContext::new();
+        ctlr.tick(&mut context).unwrap();
+
+        while let Some(event) = context.events.pop_front() {
+            sequencer.handle_event(&event);
+        }
+
+        sequencer.advance(Instant::now());
+        sequencer.render_leds(ctlr.as_mut());
+    }
+}
@@ -0,0 +1,71 @@
+use maschine::display::Simulator;
+use maschine::{DitherMode, MaschineMk2};
+use raqote::{
+    DrawOptions, DrawTarget, GradientStop, LineCap, LineJoin, PathBuilder, Point, SolidSource,
+    Source, Spread, StrokeStyle,
+};
+
+fn main() {
+    let mut dt = DrawTarget::new(256, 64);
+
+    // Gradient background to show off the dithering
+    let mut pb = PathBuilder::new();
+    pb.rect(0., 0., 256., 64.);
+    dt.fill(
+        &pb.finish(),
+        &Source::new_linear_gradient(
+            raqote::Gradient {
+                stops: vec![
+                    GradientStop {
+                        position: 0.0,
+                        color: raqote::Color::new(0xFF, 0x00, 0x00, 0x00),
+                    },
+                    GradientStop {
+                        position: 1.0,
+                        color: raqote::Color::new(0xFF, 0xFF, 0xFF, 0xFF),
+                    },
+                ],
+            },
+            Point::new(0., 0.),
+            Point::new(256., 0.),
+            Spread::Pad,
+        ),
+        &DrawOptions::new(),
+    );
+
+    let mut pb = PathBuilder::new();
+    pb.arc(128., 32., 20., 0., 2. * std::f32::consts::PI);
+    pb.close();
+    dt.stroke(
+        &pb.finish(),
+        &Source::Solid(SolidSource {
+            r: 0xFF,
+            g: 0xFF,
+            b: 0xFF,
+            a: 0xFF,
+        }),
+        &StrokeStyle {
+            cap: LineCap::Square,
+            join: LineJoin::Bevel,
+            width: 2f32,
+            miter_limit: 2f32,
+            ..Default::default()
+        },
+        &DrawOptions::new(),
+    );
+
+    // Render the same image with each conversion, one file per display
+    let mut simulator = Simulator::mk2();
+    for (display_idx, mode) in [(0, DitherMode::default()), (1, DitherMode::FloydSteinberg)] {
+        for report in MaschineMk2::encode_frame(display_idx, &dt, mode) {
+            simulator.write_report(&report).unwrap();
+        }
+        let file_name = format!("display-{display_idx}.png");
+        simulator
+            .display(display_idx)
+            .unwrap()
+            .save_png(&file_name)
+            .unwrap();
+        println!("Wrote {file_name}");
+    }
+}
@@ -0,0 +1,51 @@
+use maschine::display::SpanCanvas;
+use maschine::{get_device, EventContext};
+use raqote::{DrawOptions, PathBuilder, SolidSource, Source, StrokeStyle};
+
+fn main() {
+    let mut ctlr = get_device().unwrap();
+
+    // One canvas over both Mk2 displays
+    let mut span = SpanCanvas::new(ctlr.as_ref());
+    let (width, height) = (span.width() as f32, span.height() as f32);
+
+    let mut phase = 0f32;
+    loop {
+        let dt = span.canvas();
+        dt.clear(SolidSource {
+            r: 0x00,
+            g: 0x00,
+            b: 0x00,
+            a: 0x00,
+        });
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., height / 2.);
+        for x in 0..width as i32 {
+            let t = x as f32 / width * std::f32::consts::PI * 8. + phase;
+            pb.line_to(x as f32, height / 2. + t.sin() * (height / 2. - 4.));
+        }
+        dt.stroke(
+            &pb.finish(),
+            &Source::Solid(SolidSource {
+                r: 0xFF,
+                g: 0xFF,
+                b: 0xFF,
+                a: 0xFF,
+            }),
+            &StrokeStyle::default(),
+            &DrawOptions::new(),
+        );
+        span.flush(ctlr.as_mut()).unwrap();
+        phase += 0.05;
+
+        // Allow controller to do work and update any events
+        let mut context = EventContext::new();
+        ctlr.tick(&mut context).unwrap();
+
+        // Handle any generated events
+        while let Some(event) = context.events.pop_front() {
+            println!("{event:?}");
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use maschine::widgets::{Knob, Label, Screen, SoftKeyLayout};
+use maschine::{get_device, Color, Event, EventContext};
+
+fn main() {
+    let mut ctlr = get_device().unwrap();
+
+    // Only the Mk2 has a second display
+    let layout = if ctlr.capabilities().displays.len() > 1 {
+        SoftKeyLayout::mk2()
+    } else {
+        SoftKeyLayout::mikro_mk2()
+    };
+
+    let mut screen = Screen::new();
+    let mut knobs = Vec::new();
+    for column in 0..layout.len() {
+        let (display_idx, bounds) = layout.label(column).unwrap();
+        screen.add(
+            display_idx,
+            Label::new(bounds, &format!("Page {}", column + 1)),
+        );
+
+        let (display_idx, bounds) = layout.body(column).unwrap();
+        let mut knob = Knob::new(bounds, &format!("Param {}", column + 1));
+        knob.value = 0.5;
+        knob.value_text = "50%".to_string();
+        knobs.push(screen.add(display_idx, knob));
+    }
+
+    loop {
+        // Allow controller to do work and update any events
+        let mut context = EventContext::new();
+        ctlr.tick(&mut context).unwrap();
+
+        // Handle any generated events
+        while let Some(event) = context.events.pop_front() {
+            match event {
+                Event::Encoder(idx, _value, delta, _shift) if idx > 0 => {
+                    // Encoder 0 is the main encoder, the rest line up with the columns
+                    if let Some(knob) = knobs
+                        .get(idx as usize - 1)
+                        .and_then(|&id| screen.get_mut::<Knob>(id))
+                    {
+                        knob.value = (knob.value + delta as f32 / 1024.).clamp(0., 1.);
+                        knob.value_text = format!("{:.0}%", knob.value * 100.);
+                    }
+                }
+                Event::Button(button, pressed, _shift) if layout.column_of(button).is_some() => {
+                    // Light the soft-key while it is held
+                    let color = if pressed {
+                        Color::new(0xFF, 0xFF, 0xFF, 0xFF)
+                    } else {
+                        Color::new(0xFF, 0, 0, 0)
+                    };
+                    ctlr.set_button_led(button, color);
+                }
+                _ => {}
+            }
+        }
+
+        screen.render(ctlr.as_mut()).unwrap();
+    }
+}
@@ -0,0 +1,429 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use hidapi::HidApi;
+use maschine::display::{Alignment, DrawText, TextStyle};
+use maschine::{
+    get_device, Color, Device, DitherMode, Error, Event, EventContext, MaschineJam,
+    MaschineMikroMk1, MaschineMikroMk2, MaschineMikroMk3, MaschineMk1, MaschineMk2, MaschineMk3,
+    MaschineStudio, StripLeds,
+};
+use raqote::{DrawTarget, IntRect};
+use std::io::Write;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+///
+/// Diagnostic tool for Native Instruments Maschine controllers
+///
+#[derive(Parser)]
+#[command(name = "maschine", version)]
+struct Cli {
+    /// Controller to open, the first one found by default
+    #[arg(short, long, value_enum, default_value_t = Model::Auto)]
+    model: Model,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Model {
+    Auto,
+    Mk1,
+    Mk2,
+    Mk3,
+    MikroMk1,
+    MikroMk2,
+    MikroMk3,
+    Studio,
+    Jam,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List attached Native Instruments HID devices
+    List,
+
+    /// Print the identification and capabilities of a controller
+    Info,
+
+    /// Print decoded events
+    Events,
+
+    /// Dump raw HID input reports in hex
+    Raw {
+        /// Stop after this many reports
+        #[arg(short, long)]
+        count: Option<usize>,
+    },
+
+    /// Light every button and pad LED in sequence
+    Leds {
+        /// Time each LED is lit for in milliseconds
+        #[arg(short, long, default_value_t = 80)]
+        delay: u64,
+    },
+
+    /// Draw a test pattern on every display
+    Pattern,
+
+    /// Show pad pressure as bars in the terminal
+    Pads,
+}
+
+const VENDOR_ID: u16 = 0x17cc;
+
+fn main() {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::List => list(),
+        Command::Info => open(cli.model).and_then(|ctlr| info(ctlr.as_ref())),
+        Command::Events => open(cli.model).and_then(|mut ctlr| events(ctlr.as_mut())),
+        Command::Raw { count } => raw(cli.model, count),
+        Command::Leds { delay } => {
+            open(cli.model).and_then(|mut ctlr| leds(ctlr.as_mut(), Duration::from_millis(delay)))
+        }
+        Command::Pattern => open(cli.model).and_then(|mut ctlr| pattern(ctlr.as_mut())),
+        Command::Pads => open(cli.model).and_then(|mut ctlr| pads(ctlr.as_mut())),
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+/// Open the selected controller
+fn open(model: Model) -> Result<Box<dyn Device>, Error> {
+    Ok(match model {
+        Model::Auto => get_device()?,
+        Model::Mk1 => Box::new(MaschineMk1::new()?),
+        Model::Mk2 => Box::new(MaschineMk2::new()?),
+        Model::Mk3 => Box::new(MaschineMk3::new()?),
+        Model::MikroMk1 => Box::new(MaschineMikroMk1::new()?),
+        Model::MikroMk2 => Box::new(MaschineMikroMk2::new()?),
+        Model::MikroMk3 => Box::new(MaschineMikroMk3::new()?),
+        Model::Studio => Box::new(MaschineStudio::new()?),
+        Model::Jam => Box::new(MaschineJam::new()?),
+    })
+}
+
+/// Product name of a supported controller
+fn product_name(product_id: u16) -> Option<&'static str> {
+    match product_id {
+        MaschineMk1::PRODUCT_ID => Some("Maschine Mk1"),
+        MaschineMk2::PRODUCT_ID => Some("Maschine Mk2"),
+        MaschineMk3::PRODUCT_ID => Some("Maschine Mk3"),
+        MaschineMikroMk1::PRODUCT_ID => Some("Maschine Mikro Mk1"),
+        MaschineMikroMk2::PRODUCT_ID => Some("Maschine Mikro Mk2"),
+        MaschineMikroMk3::PRODUCT_ID => Some("Maschine Mikro Mk3"),
+        MaschineStudio::PRODUCT_ID => Some("Maschine Studio"),
+        MaschineJam::PRODUCT_ID => Some("Maschine Jam"),
+        _ => None,
+    }
+}
+
+fn list() -> Result<(), Error> {
+    let hid_api = HidApi::new()?;
+    let mut found = false;
+    for info in hid_api
+        .device_list()
+        .filter(|info| info.vendor_id() == VENDOR_ID)
+    {
+        found = true;
+        let name = product_name(info.product_id())
+            .map(str::to_string)
+            .or_else(|| info.product_string().map(|s| format!("{s} (unsupported)")))
+            .unwrap_or_else(|| "Unknown device (unsupported)".to_string());
+        println!(
+            "{:04x}:{:04x}  {}  serial {}  {}",
+            info.vendor_id(),
+            info.product_id(),
+            name,
+            info.serial_number().unwrap_or("-"),
+            info.path().to_string_lossy(),
+        );
+    }
+    if !found {
+        println!("No Native Instruments devices found");
+    }
+
+    Ok(())
+}
+
+fn info(ctlr: &dyn Device) -> Result<(), Error> {
+    let info = ctlr.device_info()?;
+    let capabilities = ctlr.capabilities();
+
+    println!("Model:         {}", capabilities.model);
+    println!(
+        "Manufacturer:  {}",
+        info.manufacturer.as_deref().unwrap_or("-")
+    );
+    println!("Product:       {}", info.product.as_deref().unwrap_or("-"));
+    println!(
+        "Serial:        {}",
+        info.serial_number.as_deref().unwrap_or("-")
+    );
+    println!(
+        "Release:       {} ({:#06x})",
+        info.version(),
+        info.release_number
+    );
+    println!("Interface:     {}", info.interface_number);
+    println!("Path:          {}", info.path);
+    for (idx, display) in capabilities.displays.iter().enumerate() {
+        println!("Display {idx}:     {}x{}", display.width, display.height);
+    }
+    println!(
+        "Encoders:      {} ({} touch sensitive)",
+        capabilities.encoders.len(),
+        capabilities.encoders.iter().filter(|e| e.touch).count()
+    );
+    println!("Pads:          {}", capabilities.pad_count);
+    println!(
+        "Touch strips:  {} ({} LEDs each)",
+        capabilities.strip_count, capabilities.strip_leds
+    );
+    if let Some(grid) = &capabilities.grid {
+        println!("Grid:          {}x{}", grid.columns, grid.rows);
+    }
+    for (idx, meter) in capabilities.meters.iter().enumerate() {
+        println!("Meter {idx}:       {} segments", meter.segments);
+    }
+    if capabilities.jog_wheel {
+        println!("Jog wheel:     yes");
+    }
+    println!("Buttons:       {}", capabilities.buttons.len());
+
+    Ok(())
+}
+
+fn events(ctlr: &mut dyn Device) -> Result<(), Error> {
+    loop {
+        let mut context = EventContext::new();
+        ctlr.tick(&mut context)?;
+        while let Some(event) = context.events.pop_front() {
+            println!("{event:?}");
+        }
+    }
+}
+
+fn raw(model: Model, count: Option<usize>) -> Result<(), Error> {
+    let hid_api = HidApi::new()?;
+    let product_ids = match model {
+        Model::Auto => vec![
+            MaschineJam::PRODUCT_ID,
+            MaschineStudio::PRODUCT_ID,
+            MaschineMikroMk3::PRODUCT_ID,
+            MaschineMk3::PRODUCT_ID,
+            MaschineMikroMk2::PRODUCT_ID,
+            MaschineMk2::PRODUCT_ID,
+            MaschineMikroMk1::PRODUCT_ID,
+            MaschineMk1::PRODUCT_ID,
+        ],
+        Model::Mk1 => vec![MaschineMk1::PRODUCT_ID],
+        Model::Mk2 => vec![MaschineMk2::PRODUCT_ID],
+        Model::Mk3 => vec![MaschineMk3::PRODUCT_ID],
+        Model::MikroMk1 => vec![MaschineMikroMk1::PRODUCT_ID],
+        Model::MikroMk2 => vec![MaschineMikroMk2::PRODUCT_ID],
+        Model::MikroMk3 => vec![MaschineMikroMk3::PRODUCT_ID],
+        Model::Studio => vec![MaschineStudio::PRODUCT_ID],
+        Model::Jam => vec![MaschineJam::PRODUCT_ID],
+    };
+    let device = product_ids
+        .into_iter()
+        .find_map(|product_id| hid_api.open(VENDOR_ID, product_id).ok())
+        .ok_or(Error::NoDevices)?;
+
+    let start = Instant::now();
+    let mut buffer = [0u8; 512];
+    let mut reports = 0;
+    while count.is_none_or(|count| reports < count) {
+        let bytes_read = device.read(&mut buffer)?;
+        if bytes_read == 0 {
+            continue;
+        }
+        let hex: Vec<String> = buffer[..bytes_read]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        println!(
+            "{:>10.3}  {:>3}  {}",
+            start.elapsed().as_secs_f64(),
+            bytes_read,
+            hex.join(" ")
+        );
+        reports += 1;
+    }
+
+    Ok(())
+}
+
+/// Tick the controller for a while so changes are sent
+fn run_for(ctlr: &mut dyn Device, duration: Duration) -> Result<(), Error> {
+    let end = Instant::now() + duration;
+    loop {
+        ctlr.tick(&mut EventContext::new())?;
+        if Instant::now() >= end {
+            return Ok(());
+        }
+    }
+}
+
+fn leds(ctlr: &mut dyn Device, delay: Duration) -> Result<(), Error> {
+    let off = Color::new(0x00, 0x00, 0x00, 0x00);
+    let white = Color::new(0xFF, 0xFF, 0xFF, 0xFF);
+
+    let capabilities = ctlr.capabilities();
+    for (button, kind) in capabilities.button_leds {
+        println!("{button} ({kind:?})");
+        ctlr.set_button_led(button, white);
+        run_for(ctlr, delay)?;
+        ctlr.set_button_led(button, off);
+    }
+
+    let colors = [
+        ("red", Color::new(0xFF, 0xFF, 0x00, 0x00)),
+        ("green", Color::new(0xFF, 0x00, 0xFF, 0x00)),
+        ("blue", Color::new(0xFF, 0x00, 0x00, 0xFF)),
+        ("white", white),
+    ];
+    for (name, color) in colors {
+        println!("Pads {name}");
+        for pad in 0..capabilities.pad_count as u8 {
+            ctlr.set_pad_led(pad, color);
+            run_for(ctlr, delay)?;
+            ctlr.set_pad_led(pad, off);
+        }
+    }
+
+    if let Some(grid) = &capabilities.grid {
+        println!("Grid");
+        for row in 0..grid.rows {
+            for column in 0..grid.columns {
+                ctlr.set_grid_led(column, row, white);
+                run_for(ctlr, delay)?;
+                ctlr.set_grid_led(column, row, off);
+            }
+        }
+    }
+
+    if capabilities.strip_leds > 0 {
+        for strip in 0..capabilities.strip_count as u8 {
+            println!("Strip {strip}");
+            for led in 0..capabilities.strip_leds {
+                let position = led as f32 / (capabilities.strip_leds - 1).max(1) as f32;
+                ctlr.set_strip_leds(strip, StripLeds::Position(position), white);
+                run_for(ctlr, delay)?;
+            }
+            ctlr.set_strip_leds(strip, StripLeds::Off, off);
+        }
+    }
+
+    for (meter, info) in capabilities.meters.iter().enumerate() {
+        println!("Meter {meter}");
+        for segment in 0..=info.segments {
+            ctlr.set_meter(meter as u8, segment as f32 / info.segments as f32);
+            run_for(ctlr, delay)?;
+        }
+        ctlr.set_meter(meter as u8, 0.0);
+    }
+    run_for(ctlr, delay)
+}
+
+fn pattern(ctlr: &mut dyn Device) -> Result<(), Error> {
+    for (display_idx, display) in ctlr.capabilities().displays.iter().enumerate() {
+        let display_idx = display_idx as u8;
+        println!(
+            "Display {display_idx}: {}x{}",
+            display.width, display.height
+        );
+        draw_pattern(ctlr.get_display(display_idx)?, display_idx);
+        ctlr.set_dither_mode(display_idx, DitherMode::Bayer)?;
+        ctlr.commit_display(display_idx)?;
+    }
+
+    events(ctlr)
+}
+
+/// Border, checkerboard, grey ramp and a label identifying the display
+fn draw_pattern(dt: &mut DrawTarget, display_idx: u8) {
+    let (width, height) = (dt.width(), dt.height());
+    let data = dt.get_data_mut();
+    for y in 0..height {
+        for x in 0..width {
+            let border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            let checker = x < width / 4 && ((x / 4) + (y / 4)) % 2 == 0;
+            let level = if x >= width * 3 / 4 {
+                (y * 0xFF / (height - 1)) as u32
+            } else if border || checker {
+                0xFF
+            } else {
+                0x00
+            };
+            data[(y * width + x) as usize] = 0xFF00_0000 | level << 16 | level << 8 | level;
+        }
+    }
+
+    dt.draw_bitmap_text(
+        &format!("Display {display_idx}"),
+        IntRect::new((width / 4, 8).into(), (width * 3 / 4, 24).into()),
+        &TextStyle {
+            alignment: Alignment::Center,
+            ..Default::default()
+        },
+    );
+    dt.draw_bitmap_text(
+        &format!("{width}x{height}"),
+        IntRect::new((width / 4, 40).into(), (width * 3 / 4, 56).into()),
+        &TextStyle {
+            alignment: Alignment::Center,
+            ..Default::default()
+        },
+    );
+}
+
+fn pads(ctlr: &mut dyn Device) -> Result<(), Error> {
+    const BAR_WIDTH: usize = 64;
+
+    let mut velocities = vec![0u8; ctlr.capabilities().pad_count];
+    let mut stdout = std::io::stdout();
+    let mut first = true;
+    loop {
+        let mut context = EventContext::new();
+        ctlr.tick(&mut context)?;
+
+        let mut changed = first;
+        while let Some(event) = context.events.pop_front() {
+            if let Event::Pad(pad, velocity, _shift) = event {
+                if let Some(v) = velocities.get_mut(pad as usize) {
+                    changed |= *v != velocity;
+                    *v = velocity;
+                }
+            }
+        }
+        if !changed {
+            sleep(Duration::from_millis(1));
+            continue;
+        }
+
+        if !first {
+            // Move back over the previous bars
+            write!(stdout, "\x1b[{}A", velocities.len())?;
+        }
+        first = false;
+        for (pad, velocity) in velocities.iter().enumerate() {
+            let filled = *velocity as usize * BAR_WIDTH / 0xFF;
+            writeln!(
+                stdout,
+                "Pad {:>2} {:>3} |{}{}|",
+                pad + 1,
+                velocity,
+                "#".repeat(filled),
+                " ".repeat(BAR_WIDTH - filled)
+            )?;
+        }
+        stdout.flush()?;
+    }
+}
@@ -0,0 +1,607 @@
+use crate::{Button, Color, Device, Event, LedColor};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Clock ticks per beat, the resolution of MIDI clock
+pub const PPQN: u64 = 24;
+
+/// MIDI clock message, sent once per tick
+pub const MIDI_CLOCK: u8 = 0xF8;
+/// MIDI start message, play from the beginning
+pub const MIDI_START: u8 = 0xFA;
+/// MIDI continue message, play on from the current position
+pub const MIDI_CONTINUE: u8 = 0xFB;
+/// MIDI stop message
+pub const MIDI_STOP: u8 = 0xFC;
+
+const MIN_BPM: f32 = 20.0;
+const MAX_BPM: f32 = 300.0;
+
+/// Taps further apart than this start a new tempo
+const TAP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Number of intervals averaged for tap tempo
+const TAP_HISTORY: usize = 4;
+
+/// Most ticks caught up in one call, after a longer stall the missed ticks are skipped
+const MAX_CATCH_UP: u64 = PPQN;
+
+/// Part of a beat a pulsing LED is lit for
+const PULSE_LENGTH: u64 = PPQN / 4;
+
+///
+/// Timing source of a clock
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClockSource {
+    ///
+    /// Ticks at the clock tempo, advanced by `Clock::advance`
+    ///
+    Internal,
+
+    ///
+    /// Ticks follow the MIDI clock messages passed to `Clock::handle_midi`
+    ///
+    Midi,
+}
+
+///
+/// Musical position of a clock
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Position {
+    /// Bar, counting from 0
+    pub bar: u64,
+
+    /// Beat within the bar, counting from 0
+    pub beat: u32,
+
+    /// Tick within the beat, 0 - 23
+    pub tick: u32,
+}
+
+///
+/// Tempo clock
+///
+/// Counts ticks at 24 per beat from an internal tempo or from incoming MIDI clock. Tap tempo is
+/// taken from a chosen button, Tempo by default. When sending is turned on the clock queues the
+/// MIDI clock, start and stop messages to pass on.
+///
+pub struct Clock {
+    source: ClockSource,
+    bpm: f32,
+    beats_per_bar: u32,
+
+    running: bool,
+    ticks: u64,
+    anchor: Option<(u64, Instant)>,
+    last_midi_clock: Option<Instant>,
+
+    tap_button: Option<Button>,
+    taps: Vec<Instant>,
+
+    send_midi: bool,
+    midi_output: Vec<u8>,
+
+    pulse_led: Option<LedColor>,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(120.0)
+    }
+}
+
+impl Clock {
+    ///
+    /// Create a stopped internal clock in 4/4
+    ///
+    /// **Arguments**
+    /// - bpm - Tempo in beats per minute
+    pub fn new(bpm: f32) -> Self {
+        Self {
+            source: ClockSource::Internal,
+            bpm: bpm.clamp(MIN_BPM, MAX_BPM),
+            beats_per_bar: 4,
+
+            running: false,
+            ticks: 0,
+            anchor: None,
+            last_midi_clock: None,
+
+            tap_button: Some(Button::Tempo),
+            taps: Vec::new(),
+
+            send_midi: false,
+            midi_output: Vec::new(),
+
+            pulse_led: None,
+        }
+    }
+
+    /// Timing source of the clock
+    pub fn source(&self) -> ClockSource {
+        self.source
+    }
+
+    ///
+    /// Change the timing source of the clock
+    ///
+    /// **Arguments**
+    /// - source - New timing source
+    pub fn set_source(&mut self, source: ClockSource) {
+        self.source = source;
+        self.anchor = None;
+        self.last_midi_clock = None;
+    }
+
+    /// Tempo in beats per minute, measured from the incoming clock when following MIDI
+    pub fn tempo(&self) -> f32 {
+        self.bpm
+    }
+
+    ///
+    /// Set the tempo of the internal clock
+    ///
+    /// **Arguments**
+    /// - bpm - Beats per minute, limited to 20 - 300
+    pub fn set_tempo(&mut self, bpm: f32) {
+        // Keep the next tick where it is, the ticks after it follow the new tempo
+        self.anchor = self.tick_time(self.ticks).map(|at| (self.ticks, at));
+        self.bpm = bpm.clamp(MIN_BPM, MAX_BPM);
+    }
+
+    /// Beats in a bar
+    pub fn beats_per_bar(&self) -> u32 {
+        self.beats_per_bar
+    }
+
+    ///
+    /// Set the beats in a bar
+    ///
+    /// **Arguments**
+    /// - beats - Beats in a bar, at least 1
+    pub fn set_beats_per_bar(&mut self, beats: u32) {
+        self.beats_per_bar = beats.max(1);
+    }
+
+    ///
+    /// Choose the button that taps the tempo
+    ///
+    /// **Arguments**
+    /// - button - Tap tempo button, `None` turns tap tempo off
+    pub fn set_tap_button(&mut self, button: Option<Button>) {
+        self.tap_button = button;
+        self.taps.clear();
+    }
+
+    ///
+    /// Queue MIDI clock messages for `take_midi_output`
+    ///
+    /// **Arguments**
+    /// - send - Queue clock, start and stop messages
+    pub fn set_send_midi(&mut self, send: bool) {
+        self.send_midi = send;
+        self.midi_output.clear();
+    }
+
+    /// Start from the beginning
+    pub fn start(&mut self) {
+        self.ticks = 0;
+        self.resume();
+        self.queue_midi(MIDI_START);
+    }
+
+    /// Continue from the current position
+    pub fn resume(&mut self) {
+        if !self.running && self.ticks > 0 {
+            self.queue_midi(MIDI_CONTINUE);
+        }
+        self.running = true;
+        self.anchor = None;
+    }
+
+    /// Stop at the current position
+    pub fn stop(&mut self) {
+        if self.running {
+            self.queue_midi(MIDI_STOP);
+        }
+        self.running = false;
+    }
+
+    /// Clock is running
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Ticks since the clock was started
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Bar, beat and tick of the next tick
+    pub fn position(&self) -> Position {
+        let beat = self.ticks / PPQN;
+        Position {
+            bar: beat / self.beats_per_bar as u64,
+            beat: (beat % self.beats_per_bar as u64) as u32,
+            tick: (self.ticks % PPQN) as u32,
+        }
+    }
+
+    ///
+    /// Run the internal clock up to a time
+    ///
+    /// Call this regularly, eg after each device tick. Returns the ticks that passed, nothing when
+    /// stopped or following MIDI clock. After a stall of more than a beat the missed ticks are
+    /// skipped rather than played in a burst.
+    ///
+    /// **Arguments**
+    /// - now - Current time
+    pub fn advance(&mut self, now: Instant) -> Range<u64> {
+        let start = self.ticks;
+        if self.source != ClockSource::Internal || !self.running {
+            return start..start;
+        }
+
+        let behind = self
+            .tick_time(self.ticks + MAX_CATCH_UP)
+            .is_none_or(|at| at <= now);
+        if behind {
+            self.anchor = Some((self.ticks, now));
+        }
+        while self.tick_time(self.ticks).is_some_and(|at| at <= now) {
+            self.ticks += 1;
+            self.queue_midi(MIDI_CLOCK);
+        }
+
+        start..self.ticks
+    }
+
+    ///
+    /// Time a tick falls on
+    ///
+    /// Counted at the current tempo from the latest tick with a known time. `None` until the clock
+    /// has ticked since it was started or its source changed.
+    ///
+    /// **Arguments**
+    /// - tick - Tick since the clock was started
+    pub fn tick_time(&self, tick: u64) -> Option<Instant> {
+        let (anchor_tick, anchor_at) = self.anchor?;
+        let length = |ticks: u64| {
+            Duration::from_secs_f64(ticks as f64 * 60.0 / (self.bpm as f64 * PPQN as f64))
+        };
+        if tick >= anchor_tick {
+            anchor_at.checked_add(length(tick - anchor_tick))
+        } else {
+            anchor_at.checked_sub(length(anchor_tick - tick))
+        }
+    }
+
+    ///
+    /// Follow an incoming MIDI real time message
+    ///
+    /// Returns the ticks that passed, nothing unless following MIDI clock.
+    ///
+    /// **Arguments**
+    /// - message - MIDI status byte, other messages are ignored
+    /// - now - Time the message arrived
+    pub fn handle_midi(&mut self, message: u8, now: Instant) -> Range<u64> {
+        let start = self.ticks;
+        if self.source != ClockSource::Midi {
+            return start..start;
+        }
+
+        match message {
+            MIDI_CLOCK => {
+                // Smooth the measured tempo, individual clocks jitter
+                if let Some(last) = self.last_midi_clock {
+                    let interval = now.duration_since(last).as_secs_f32();
+                    if interval > 0.0 {
+                        let bpm = (60.0 / (interval * PPQN as f32)).clamp(MIN_BPM, MAX_BPM);
+                        self.bpm += (bpm - self.bpm) * 0.1;
+                    }
+                }
+                self.last_midi_clock = Some(now);
+
+                if self.running {
+                    self.anchor = Some((self.ticks, now));
+                    self.ticks += 1;
+                    self.queue_midi(MIDI_CLOCK);
+                }
+            }
+            // The gap over a stop or a restart is not a measure of the tempo
+            MIDI_START => {
+                self.last_midi_clock = None;
+                self.start();
+            }
+            MIDI_CONTINUE => {
+                self.last_midi_clock = None;
+                self.resume();
+            }
+            MIDI_STOP => {
+                self.last_midi_clock = None;
+                self.stop();
+            }
+            _ => {}
+        }
+
+        start..self.ticks
+    }
+
+    ///
+    /// Tap the tempo from the tap button
+    ///
+    /// **Arguments**
+    /// - event - Event from the device
+    /// - now - Time the event was read
+    pub fn handle_event(&mut self, event: &Event, now: Instant) {
+        if let Event::Button(button, true, _) = *event {
+            if Some(button) == self.tap_button {
+                self.tap(now);
+            }
+        }
+    }
+
+    ///
+    /// Tap the tempo, the average of the last few taps sets the tempo
+    ///
+    /// **Arguments**
+    /// - now - Time of the tap
+    pub fn tap(&mut self, now: Instant) {
+        if self
+            .taps
+            .last()
+            .is_some_and(|last| now.duration_since(*last) > TAP_TIMEOUT)
+        {
+            self.taps.clear();
+        }
+        self.taps.push(now);
+        if self.taps.len() > TAP_HISTORY + 1 {
+            self.taps.remove(0);
+        }
+
+        if let (Some(first), Some(last)) = (self.taps.first(), self.taps.last()) {
+            let intervals = self.taps.len() - 1;
+            if intervals > 0 && self.source == ClockSource::Internal {
+                let beat = last.duration_since(*first).as_secs_f32() / intervals as f32;
+                self.set_tempo(60.0 / beat);
+            }
+        }
+    }
+
+    /// Take the queued MIDI messages to send, in order
+    pub fn take_midi_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.midi_output)
+    }
+
+    ///
+    /// Strength of a LED pulsing on the beat
+    ///
+    /// Full on the first beat of a bar, half on the other beats and off between them or while
+    /// stopped.
+    ///
+    pub fn pulse(&self) -> f32 {
+        let position = self.position();
+        if !self.running || position.tick as u64 >= PULSE_LENGTH {
+            0.0
+        } else if position.beat == 0 {
+            1.0
+        } else {
+            0.5
+        }
+    }
+
+    ///
+    /// Pulse a button LED on the beat, eg Play
+    ///
+    /// Only updates the LED when the pulse changes.
+    ///
+    /// **Arguments**
+    /// - device - Device to update
+    /// - button - Button LED to pulse
+    /// - color - Colour on the first beat of a bar
+    pub fn render_pulse<D: Device + ?Sized>(
+        &mut self,
+        device: &mut D,
+        button: Button,
+        color: Color,
+    ) {
+        let pulse = self.pulse();
+        let scale = |channel: u8| (channel as f32 * pulse) as u8;
+        let led = LedColor::new(scale(color.r()), scale(color.g()), scale(color.b()));
+        if self.pulse_led != Some(led) {
+            self.pulse_led = Some(led);
+            device.set_button_led(button, led.into());
+        }
+    }
+
+    /// Add a message to the MIDI output when sending
+    fn queue_midi(&mut self, message: u8) {
+        if self.send_midi {
+            self.midi_output.push(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn internal_clock_ticks_at_the_tempo() {
+        let mut clock = Clock::new(125.0);
+        let start = Instant::now();
+        assert_eq!(clock.advance(start), 0..0);
+
+        // 20 ms per tick at 125 BPM
+        clock.start();
+        assert_eq!(clock.advance(start), 0..1);
+        assert_eq!(clock.advance(start + millis(50)), 1..3);
+
+        clock.stop();
+        assert_eq!(clock.advance(start + millis(1000)), 3..3);
+    }
+
+    #[test]
+    fn stalls_skip_the_missed_ticks() {
+        let mut clock = Clock::new(125.0);
+        clock.set_send_midi(true);
+        let start = Instant::now();
+        clock.start();
+        assert_eq!(clock.advance(start), 0..1);
+
+        // Up to a beat is caught up, a longer stall carries on from now
+        assert_eq!(clock.advance(start + millis(480)), 1..25);
+        assert_eq!(clock.advance(start + millis(5000)), 25..26);
+        assert_eq!(clock.advance(start + millis(5020)), 26..27);
+        assert_eq!(clock.tick_time(26), Some(start + millis(5020)));
+
+        let output = clock.take_midi_output();
+        assert_eq!(output.len(), 28);
+    }
+
+    #[test]
+    fn tempo_changes_keep_the_next_tick() {
+        let mut clock = Clock::new(125.0);
+        let start = Instant::now();
+        clock.start();
+        assert_eq!(clock.tick_time(0), None);
+        clock.advance(start);
+        assert_eq!(clock.tick_time(1), Some(start + millis(20)));
+
+        clock.set_tempo(62.5);
+        assert_eq!(clock.tick_time(1), Some(start + millis(20)));
+        assert_eq!(clock.tick_time(2), Some(start + millis(60)));
+    }
+
+    #[test]
+    fn position_counts_bars_and_beats() {
+        let mut clock = Clock::new(120.0);
+        clock.set_beats_per_bar(3);
+        clock.start();
+        let start = Instant::now();
+        clock.advance(start);
+
+        // Four beats and one tick at 500 ms per beat
+        for step in 1..=20 {
+            clock.advance(start + millis(100) * step);
+        }
+        clock.advance(start + millis(2001));
+        assert_eq!(
+            clock.position(),
+            Position {
+                bar: 1,
+                beat: 1,
+                tick: 1
+            }
+        );
+    }
+
+    #[test]
+    fn tap_tempo_averages_recent_taps() {
+        let mut clock = Clock::new(120.0);
+        let tap = Event::Button(Button::Tempo, true, false);
+        let start = Instant::now();
+        clock.handle_event(&tap, start);
+        clock.handle_event(
+            &Event::Button(Button::Play, true, false),
+            start + millis(100),
+        );
+        assert_eq!(clock.tempo(), 120.0);
+
+        clock.handle_event(&tap, start + millis(400));
+        clock.handle_event(&tap, start + millis(800));
+        assert!((clock.tempo() - 150.0).abs() < 0.01);
+
+        // A long pause starts over
+        clock.set_tap_button(Some(Button::Tap));
+        clock.handle_event(
+            &Event::Button(Button::Tap, true, false),
+            start + millis(5000),
+        );
+        clock.handle_event(
+            &Event::Button(Button::Tap, true, false),
+            start + millis(6000),
+        );
+        assert!((clock.tempo() - 60.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn follows_midi_clock() {
+        let mut clock = Clock::new(120.0);
+        clock.set_source(ClockSource::Midi);
+        let start = Instant::now();
+        clock.start();
+        assert_eq!(clock.advance(start + millis(1000)), 0..0);
+
+        clock.handle_midi(MIDI_STOP, start);
+        assert_eq!(clock.handle_midi(MIDI_CLOCK, start), 0..0);
+        clock.handle_midi(MIDI_START, start);
+
+        // 25 ms per clock is 100 BPM
+        for idx in 1..=100 {
+            clock.handle_midi(MIDI_CLOCK, start + millis(25) * idx);
+        }
+        assert_eq!(clock.ticks(), 100);
+        assert!((clock.tempo() - 100.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn midi_pause_keeps_the_tempo() {
+        let mut clock = Clock::new(120.0);
+        clock.set_source(ClockSource::Midi);
+        let start = Instant::now();
+        clock.handle_midi(MIDI_START, start);
+        for idx in 0..48 {
+            clock.handle_midi(MIDI_CLOCK, start + millis(25) * idx);
+        }
+        let tempo = clock.tempo();
+
+        // Paused for ten seconds, the gap to the first clock after continuing is not measured
+        let resumed = start + millis(10_000);
+        clock.handle_midi(MIDI_STOP, start + millis(25) * 48);
+        clock.handle_midi(MIDI_CONTINUE, resumed);
+        clock.handle_midi(MIDI_CLOCK, resumed);
+        assert_eq!(clock.tempo(), tempo);
+
+        clock.handle_midi(MIDI_CLOCK, resumed + millis(25));
+        assert!((clock.tempo() - tempo).abs() < 1.0);
+    }
+
+    #[test]
+    fn sends_midi_clock() {
+        let mut clock = Clock::new(125.0);
+        clock.set_send_midi(true);
+        let start = Instant::now();
+        clock.start();
+        clock.advance(start);
+        clock.advance(start + millis(30));
+        clock.stop();
+        clock.resume();
+        assert_eq!(
+            clock.take_midi_output(),
+            vec![MIDI_START, MIDI_CLOCK, MIDI_CLOCK, MIDI_STOP, MIDI_CONTINUE]
+        );
+        assert!(clock.take_midi_output().is_empty());
+    }
+
+    #[test]
+    fn pulse_on_the_beat() {
+        let mut clock = Clock::new(120.0);
+        assert_eq!(clock.pulse(), 0.0);
+
+        clock.start();
+        let start = Instant::now();
+        clock.advance(start);
+        assert_eq!(clock.pulse(), 1.0);
+        clock.advance(start + millis(200));
+        assert_eq!(clock.pulse(), 0.0);
+        clock.advance(start + millis(510));
+        assert_eq!(clock.pulse(), 0.5);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::Button;
+
+///
+/// Colour support of a LED
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LedKind {
+    ///
+    /// Single colour, on or off
+    ///
+    Mono,
+
+    ///
+    /// Separate red, green and blue channels
+    ///
+    Rgb,
+}
+
+///
+/// Resolution of a display
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayInfo {
+    pub width: usize,
+    pub height: usize,
+}
+
+///
+/// Value range of an encoder
+///
+/// Encoder values count from 0 up to `range - 1`, then wrap around.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncoderInfo {
+    pub range: u16,
+
+    /// Touching the encoder cap is reported, as in `Event::EncoderTouch`
+    pub touch: bool,
+}
+
+///
+/// Size of a button grid
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridInfo {
+    pub columns: u8,
+    pub rows: u8,
+}
+
+///
+/// LED level meter
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeterInfo {
+    /// Number of LEDs in the meter
+    pub segments: usize,
+}
+
+///
+/// Description of the controls and outputs of a device model
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities {
+    /// Model name
+    pub model: String,
+
+    /// USB vendor ID
+    pub vendor_id: u16,
+
+    /// USB product ID
+    pub product_id: u16,
+
+    /// Displays by display index
+    pub displays: Vec<DisplayInfo>,
+
+    /// Encoders, numbered as in `Event::Encoder`
+    pub encoders: Vec<EncoderInfo>,
+
+    /// Number of pads
+    pub pad_count: usize,
+
+    /// Number of touch strips, numbered as in `Event::Strip`
+    pub strip_count: usize,
+
+    /// Number of LEDs along each touch strip, 0 when the strips have none
+    pub strip_leds: usize,
+
+    /// Button grid with a RGB LED per cell, as in `Event::Grid`
+    pub grid: Option<GridInfo>,
+
+    /// LED level meters by meter index
+    pub meters: Vec<MeterInfo>,
+
+    /// Device has a jog wheel, as in `Event::Jog`
+    pub jog_wheel: bool,
+
+    /// Colour support of the pad LEDs
+    pub pad_leds: Option<LedKind>,
+
+    /// Buttons on the device
+    pub buttons: Vec<Button>,
+
+    /// Buttons that have a LED, and its colour support
+    pub button_leds: Vec<(Button, LedKind)>,
+}
+
+impl Capabilities {
+    ///
+    /// Describe a device model without any controls or outputs, to be filled in with the
+    /// struct update syntax
+    ///
+    /// **Arguments**
+    /// - model - Model name
+    /// - vendor_id - USB vendor ID
+    /// - product_id - USB product ID
+    pub fn new(model: &str, vendor_id: u16, product_id: u16) -> Self {
+        Self {
+            model: model.to_string(),
+            vendor_id,
+            product_id,
+            ..Self::default()
+        }
+    }
+
+    /// Device has at least one display that can be drawn on
+    pub fn has_display(&self) -> bool {
+        !self.displays.is_empty()
+    }
+
+    /// Device has the button
+    pub fn has_button(&self, button: Button) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    /// Colour support of the LED of a button, `None` if the button has no LED
+    pub fn button_led(&self, button: Button) -> Option<LedKind> {
+        self.button_leds
+            .iter()
+            .find(|(b, _)| *b == button)
+            .map(|(_, kind)| *kind)
+    }
+}
@@ -0,0 +1,152 @@
+use crate::error::Error;
+use crate::events::{Event, EventContext};
+
+/// Pad pressure a pad counts as pressed above, out of 4095
+const PAD_THRESHOLD: u16 = 512;
+
+///
+/// Input state of a device, decoded from its HID reports
+///
+/// Decoders are kept separate from the HID device so reports can be decoded without hardware.
+///
+pub(crate) trait ReportDecoder {
+    ///
+    /// Decode a report read from the device, including its report ID
+    ///
+    /// **Arguments**
+    /// - report - Report bytes
+    /// - context - Context receiving the generated events
+    fn decode(&mut self, report: &[u8], context: &mut EventContext) -> Result<(), Error>;
+}
+
+///
+/// Movement of a wrapping position counter, such as an encoder or jog wheel
+///
+/// Returns `None` when there is no previous position or it is unchanged, so the first report
+/// only establishes the starting position.
+///
+/// **Arguments**
+/// - previous - Last position seen
+/// - value - Current position, below `range`
+/// - range - Number of positions before the counter wraps
+pub(crate) fn step(previous: Option<u16>, value: u16, range: u16) -> Option<i16> {
+    let previous = previous?;
+    if previous == value {
+        return None;
+    }
+
+    // Take the shortest way around, the value wraps at the end of the range
+    let pos_delta = ((range + value - previous) % range) as i16;
+    let neg_delta = ((range - value + previous) % range) as i16;
+    Some(if pos_delta < neg_delta {
+        pos_delta
+    } else {
+        -neg_delta
+    })
+}
+
+///
+/// Decode the pressures of a pads report into pad events
+///
+/// Each pad is a little endian pair holding the pad number in the top 4 bits and a 12-bit
+/// pressure below it. Pressed pads report their velocity on every report, released pads once.
+///
+/// **Arguments**
+/// - buffer - Report bytes after the report ID, a pair per pad
+/// - pads_status - Pressed state of each pad, updated from the report
+/// - shift - Shift is held
+/// - context - Context receiving the generated events
+pub(crate) fn decode_pads(
+    buffer: &[u8],
+    pads_status: &mut [bool],
+    shift: bool,
+    context: &mut EventContext,
+) -> Result<(), Error> {
+    let Some(pairs) = buffer.get(..2 * pads_status.len()) else {
+        return Err(Error::InvalidReport);
+    };
+
+    for pair in pairs.chunks_exact(2) {
+        let (low_byte, high_byte) = (pair[0], pair[1]);
+        let pad = ((high_byte & 0xF0) >> 4) as usize;
+        let value = (((high_byte & 0x0F) as u16) << 8) | low_byte as u16;
+        let pressed = value > PAD_THRESHOLD;
+
+        let Some(status) = pads_status.get_mut(pad) else {
+            continue;
+        };
+        if pressed | *status {
+            *status = pressed;
+            context.add_event(Event::Pad(
+                pad as u8,
+                if pressed { (value >> 4) as u8 } else { 0 },
+                shift,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::ReportDecoder;
+    use crate::events::EventContext;
+
+    /// Parse a report written as space separated hex bytes
+    pub fn report(hex: &str) -> Vec<u8> {
+        hex.split_whitespace()
+            .map(|b| u8::from_str_radix(b, 16).unwrap())
+            .collect()
+    }
+
+    /// Decode hex fixture reports, returning the generated events
+    pub fn decode<D: ReportDecoder>(decoder: &mut D, reports: &[&str]) -> Vec<String> {
+        let mut context = EventContext::new();
+        for hex in reports {
+            decoder.decode(&report(hex), &mut context).unwrap();
+        }
+        context.events.iter().map(|e| format!("{e:?}")).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_position_has_no_step() {
+        assert_eq!(step(None, 5, 0x10), None);
+        assert_eq!(step(Some(5), 5, 0x10), None);
+    }
+
+    #[test]
+    fn step_takes_the_shortest_way_around() {
+        assert_eq!(step(Some(5), 7, 0x10), Some(2));
+        assert_eq!(step(Some(7), 5, 0x10), Some(-2));
+        assert_eq!(step(Some(0x0F), 0x01, 0x10), Some(2));
+        assert_eq!(step(Some(0x001), 0x3FE, 0x400), Some(-3));
+    }
+
+    #[test]
+    fn pads_report_hits_and_releases() {
+        let mut pads_status = [false; 4];
+        let mut context = EventContext::new();
+
+        // Pad 2 at 0x800, pad 3 below the threshold
+        let hit = [0x00, 0x00, 0x00, 0x10, 0x00, 0x28, 0x00, 0x32];
+        decode_pads(&hit, &mut pads_status, true, &mut context).unwrap();
+        assert_eq!(pads_status, [false, false, true, false]);
+
+        let release = [0x00, 0x00, 0x00, 0x10, 0x00, 0x20, 0x00, 0x30];
+        decode_pads(&release, &mut pads_status, false, &mut context).unwrap();
+        decode_pads(&release, &mut pads_status, false, &mut context).unwrap();
+
+        let events: Vec<String> = context.events.iter().map(|e| format!("{e:?}")).collect();
+        assert_eq!(events, vec!["Pad(2, 128, true)", "Pad(2, 0, false)"]);
+        assert!(matches!(
+            decode_pads(&release[..6], &mut pads_status, false, &mut context),
+            Err(Error::InvalidReport)
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::Error;
+use hidapi::HidDevice;
+
+///
+/// Identification of a connected unit, read from its USB HID descriptor strings
+///
+/// No feature reports are read: none of the supported controllers are known to report firmware
+/// or serial details that way, and probing unknown report IDs could change the device state.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceInfo {
+    /// USB vendor ID
+    pub vendor_id: u16,
+
+    /// USB product ID
+    pub product_id: u16,
+
+    /// Manufacturer string
+    pub manufacturer: Option<String>,
+
+    /// Product string
+    pub product: Option<String>,
+
+    /// Serial number string
+    pub serial_number: Option<String>,
+
+    /// Device release number in binary coded decimal, identifies the firmware revision
+    pub release_number: u16,
+
+    /// USB interface number, -1 if unknown
+    pub interface_number: i32,
+
+    /// Platform specific device path
+    pub path: String,
+}
+
+impl DeviceInfo {
+    ///
+    /// Read the descriptor strings of an open device
+    ///
+    /// **Arguments**
+    /// - device - Open HID device
+    pub(crate) fn read(device: &HidDevice) -> Result<Self, Error> {
+        let info = device.get_device_info()?;
+        Ok(Self {
+            vendor_id: info.vendor_id(),
+            product_id: info.product_id(),
+            manufacturer: info.manufacturer_string().map(str::to_string),
+            product: info.product_string().map(str::to_string),
+            serial_number: info.serial_number().map(str::to_string),
+            release_number: info.release_number(),
+            interface_number: info.interface_number(),
+            path: info.path().to_string_lossy().into_owned(),
+        })
+    }
+
+    /// Release number formatted as a version, eg "1.04"
+    pub fn version(&self) -> String {
+        let digit = |shift: u16| (self.release_number >> shift) & 0x0F;
+        let major = digit(12) * 10 + digit(8);
+        format!("{}.{}{}", major, digit(4), digit(0))
+    }
+}
+
+impl std::fmt::Display for DeviceInfo {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "{} {} ({:04x}:{:04x}) version {} serial {}",
+            self.manufacturer.as_deref().unwrap_or("Unknown"),
+            self.product.as_deref().unwrap_or("device"),
+            self.vendor_id,
+            self.product_id,
+            self.version(),
+            self.serial_number.as_deref().unwrap_or("-"),
+        )
+    }
+}
@@ -0,0 +1,622 @@
+use crate::devices::decoder::{step, ReportDecoder};
+use crate::devices::palette::color_index;
+use crate::devices::{Capabilities, DeviceInfo, EncoderInfo, GridInfo, LedKind, MeterInfo};
+use crate::error::Error;
+use crate::events::{Button, Event, EventContext, EventTask};
+use crate::leds::{set_meter_leds, LedColor, LedSnapshot};
+use crate::strip::StripLeds;
+use crate::{Color, Device};
+use hidapi::{HidApi, HidDevice};
+
+const INPUT_BUFFER_SIZE: usize = 512;
+
+// Buttons, bit index in the buttons report. Buttons with a LED use the same index in the button
+// LED report.
+
+pub const BUTTON_SCENE1: u8 = 0;
+pub const BUTTON_SCENE2: u8 = 1;
+pub const BUTTON_SCENE3: u8 = 2;
+pub const BUTTON_SCENE4: u8 = 3;
+pub const BUTTON_SCENE5: u8 = 4;
+pub const BUTTON_SCENE6: u8 = 5;
+pub const BUTTON_SCENE7: u8 = 6;
+pub const BUTTON_SCENE8: u8 = 7;
+pub const BUTTON_GROUP_A: u8 = 8;
+pub const BUTTON_GROUP_B: u8 = 9;
+pub const BUTTON_GROUP_C: u8 = 10;
+pub const BUTTON_GROUP_D: u8 = 11;
+pub const BUTTON_GROUP_E: u8 = 12;
+pub const BUTTON_GROUP_F: u8 = 13;
+pub const BUTTON_GROUP_G: u8 = 14;
+pub const BUTTON_GROUP_H: u8 = 15;
+pub const BUTTON_SONG: u8 = 16;
+pub const BUTTON_STEP: u8 = 17;
+pub const BUTTON_PAD_MODE: u8 = 18;
+pub const BUTTON_CLEAR: u8 = 19;
+pub const BUTTON_DUPLICATE: u8 = 20;
+pub const BUTTON_NAVIGATE: u8 = 21;
+pub const BUTTON_SELECT: u8 = 22;
+pub const BUTTON_SOLO: u8 = 23;
+pub const BUTTON_MUTE: u8 = 24;
+pub const BUTTON_PLAY: u8 = 25;
+pub const BUTTON_REC: u8 = 26;
+pub const BUTTON_SHIFT: u8 = 27;
+pub const BUTTON_AUTO: u8 = 28;
+pub const BUTTON_LOCK: u8 = 29;
+pub const BUTTON_NOTE_REPEAT: u8 = 30;
+pub const BUTTON_BROWSE: u8 = 31;
+pub const BUTTON_MACRO: u8 = 32;
+pub const BUTTON_LEVEL: u8 = 33;
+pub const BUTTON_AUX: u8 = 34;
+pub const BUTTON_CONTROL: u8 = 35;
+pub const BUTTON_TUNE: u8 = 36;
+pub const BUTTON_SWING: u8 = 37;
+pub const BUTTON_TEMPO: u8 = 38;
+pub const BUTTON_PERFORM: u8 = 39;
+pub const BUTTON_NOTES: u8 = 40;
+pub const BUTTON_GRID: u8 = 41;
+pub const BUTTON_MASTER: u8 = 42;
+pub const BUTTON_INPUT: u8 = 43;
+pub const BUTTON_CUE: u8 = 44;
+pub const BUTTON_ARROW_UP: u8 = 45;
+pub const BUTTON_ARROW_RIGHT: u8 = 46;
+pub const BUTTON_ARROW_DOWN: u8 = 47;
+pub const BUTTON_ARROW_LEFT: u8 = 48;
+pub const BUTTON_MAIN_ENCODER: u8 = 49;
+
+const BUTTON_COUNT: usize = 50;
+
+const GRID_COLUMNS: u8 = 8;
+const GRID_ROWS: u8 = 8;
+const GRID_CELLS: usize = GRID_COLUMNS as usize * GRID_ROWS as usize;
+
+const ENCODER_RANGE: u16 = 0x10;
+
+const STRIP_COUNT: usize = 8;
+const STRIP_LED_COUNT: usize = 11;
+const METER_COUNT: usize = 2;
+const METER_SEGMENTS: usize = 8;
+
+// Buttons report: button bits, grid bits row by row, then the encoder position
+const BUTTONS_REPORT: u8 = 0x01;
+const GRID_OFFSET: usize = 7;
+const ENCODER_OFFSET: usize = GRID_OFFSET + GRID_CELLS / 8;
+const BUTTONS_REPORT_SIZE: usize = ENCODER_OFFSET + 1;
+
+// Strips report: touch count followed by 16 bit position and pressure for each strip
+const STRIPS_REPORT: u8 = 0x02;
+const STRIP_SIZE: usize = 5;
+const STRIPS_REPORT_SIZE: usize = STRIP_COUNT * STRIP_SIZE;
+
+// LED reports: indexed colour or brightness of each button LED, indexed colour of each grid
+// cell, the brightness of each meter segment, then the indexed colour of each strip LED from the
+// bottom of the first strip
+const BUTTON_LED_ADDR: u8 = 0x80;
+const GRID_LED_ADDR: u8 = 0x81;
+const METER_LED_ADDR: u8 = 0x82;
+const STRIP_LED_ADDR: u8 = 0x83;
+
+///
+/// Input state of a Jam, decoded from its HID reports
+///
+struct InputDecoder {
+    button_states: [bool; BUTTON_COUNT],
+    shift_pressed: bool,
+    grid_states: [bool; GRID_CELLS],
+    encoder_value: Option<u16>,
+    strips: [(u8, u16, u16); STRIP_COUNT],
+}
+
+impl InputDecoder {
+    fn new() -> Self {
+        Self {
+            button_states: [false; BUTTON_COUNT],
+            shift_pressed: false,
+            grid_states: [false; GRID_CELLS],
+            encoder_value: None,
+            strips: [(0, 0, 0); STRIP_COUNT],
+        }
+    }
+
+    /// Process a buttons report message
+    fn process_buttons(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        if buffer.len() < BUTTONS_REPORT_SIZE {
+            return Err(Error::InvalidReport);
+        }
+
+        let is_pressed = |btn: u8| (buffer[(btn >> 3) as usize] & (1 << (btn % 8))) != 0;
+
+        // Apply shift first so a button pressed together with it is reported as shifted
+        self.shift_pressed = is_pressed(BUTTON_SHIFT);
+        self.button_states[BUTTON_SHIFT as usize] = self.shift_pressed;
+
+        for btn in BUTTON_SCENE1..=BUTTON_MAIN_ENCODER {
+            let button_pressed = is_pressed(btn);
+            if btn != BUTTON_SHIFT && button_pressed != self.button_states[btn as usize] {
+                self.button_states[btn as usize] = button_pressed;
+
+                let button = as_device_button(btn);
+                context.add_event(Event::Button(button, button_pressed, self.shift_pressed));
+            }
+        }
+
+        for cell in 0..GRID_CELLS {
+            let pressed = (buffer[GRID_OFFSET + cell / 8] & (1 << (cell % 8))) != 0;
+            if pressed != self.grid_states[cell] {
+                self.grid_states[cell] = pressed;
+                context.add_event(Event::Grid(
+                    cell as u8 % GRID_COLUMNS,
+                    cell as u8 / GRID_COLUMNS,
+                    pressed,
+                    self.shift_pressed,
+                ));
+            }
+        }
+
+        let encoder_value = (buffer[ENCODER_OFFSET] & 0x0F) as u16;
+        if let Some(delta) = step(self.encoder_value, encoder_value, ENCODER_RANGE) {
+            context.add_event(Event::Encoder(0, encoder_value, delta, self.shift_pressed));
+        }
+        self.encoder_value = Some(encoder_value);
+
+        Ok(())
+    }
+
+    /// Process a strips report message
+    fn process_strips(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        if buffer.len() < STRIPS_REPORT_SIZE {
+            return Err(Error::InvalidReport);
+        }
+
+        for (idx, data) in buffer
+            .chunks_exact(STRIP_SIZE)
+            .take(STRIP_COUNT)
+            .enumerate()
+        {
+            let strip = (
+                data[0],
+                u16::from_le_bytes([data[1], data[2]]) & 0x3FF,
+                u16::from_le_bytes([data[3], data[4]]) & 0x3FF,
+            );
+            if strip != self.strips[idx] {
+                self.strips[idx] = strip;
+                let (touches, position, pressure) = strip;
+                context.add_event(Event::Strip(
+                    idx as u8,
+                    position,
+                    pressure,
+                    touches,
+                    self.shift_pressed,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ReportDecoder for InputDecoder {
+    fn decode(&mut self, report: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        match report.split_first() {
+            Some((&BUTTONS_REPORT, buffer)) => self.process_buttons(buffer, context),
+            Some((&STRIPS_REPORT, buffer)) => self.process_strips(buffer, context),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Convert a button code into a button enum
+fn as_device_button(button: u8) -> Button {
+    match button {
+        BUTTON_SCENE1 => Button::Scene1,
+        BUTTON_SCENE2 => Button::Scene2,
+        BUTTON_SCENE3 => Button::Scene3,
+        BUTTON_SCENE4 => Button::Scene4,
+        BUTTON_SCENE5 => Button::Scene5,
+        BUTTON_SCENE6 => Button::Scene6,
+        BUTTON_SCENE7 => Button::Scene7,
+        BUTTON_SCENE8 => Button::Scene8,
+        BUTTON_GROUP_A => Button::GroupA,
+        BUTTON_GROUP_B => Button::GroupB,
+        BUTTON_GROUP_C => Button::GroupC,
+        BUTTON_GROUP_D => Button::GroupD,
+        BUTTON_GROUP_E => Button::GroupE,
+        BUTTON_GROUP_F => Button::GroupF,
+        BUTTON_GROUP_G => Button::GroupG,
+        BUTTON_GROUP_H => Button::GroupH,
+        BUTTON_SONG => Button::Song,
+        BUTTON_STEP => Button::Step,
+        BUTTON_PAD_MODE => Button::PadMode,
+        BUTTON_CLEAR => Button::Clear,
+        BUTTON_DUPLICATE => Button::Duplicate,
+        BUTTON_NAVIGATE => Button::Nav,
+        BUTTON_SELECT => Button::Select,
+        BUTTON_SOLO => Button::Solo,
+        BUTTON_MUTE => Button::Mute,
+        BUTTON_PLAY => Button::Play,
+        BUTTON_REC => Button::Rec,
+        BUTTON_AUTO => Button::Autowrite,
+        BUTTON_LOCK => Button::Lock,
+        BUTTON_NOTE_REPEAT => Button::NoteRepeat,
+        BUTTON_BROWSE => Button::Browse,
+        BUTTON_MACRO => Button::Macro,
+        BUTTON_LEVEL => Button::Level,
+        BUTTON_AUX => Button::Aux,
+        BUTTON_CONTROL => Button::Control,
+        BUTTON_TUNE => Button::Tune,
+        BUTTON_SWING => Button::Swing,
+        BUTTON_TEMPO => Button::Tempo,
+        BUTTON_PERFORM => Button::Perform,
+        BUTTON_NOTES => Button::Notes,
+        BUTTON_GRID => Button::Grid,
+        BUTTON_MASTER => Button::Master,
+        BUTTON_INPUT => Button::Input,
+        BUTTON_CUE => Button::Cue,
+        BUTTON_ARROW_UP => Button::ArrowUp,
+        BUTTON_ARROW_RIGHT => Button::ArrowRight,
+        BUTTON_ARROW_DOWN => Button::ArrowDown,
+        BUTTON_ARROW_LEFT => Button::ArrowLeft,
+        BUTTON_MAIN_ENCODER => Button::MainEncoder,
+        _ => Button::Unknown,
+    }
+}
+
+/// Convert a button into a LED index, the encoder has no LED
+fn button_to_led(button: Button) -> Option<u8> {
+    (BUTTON_SCENE1..BUTTON_MAIN_ENCODER)
+        .find(|&code| code != BUTTON_SHIFT && as_device_button(code) == button)
+}
+
+/// Determine if a button LED is RGB or Mono
+fn is_rgb_led(led: u8) -> bool {
+    (BUTTON_SCENE1..=BUTTON_GROUP_H).contains(&led)
+}
+
+///
+/// Maschine Jam Controller
+///
+/// Requires a valid HID device. The Jam has no displays and no pads, it has an 8x8 button grid,
+/// eight touch strips and two level meters instead.
+///
+/// The report layouts have not been verified against captured USB traffic yet, the test fixtures
+/// are written from the same layout as the decoder and only guard against regressions, so
+/// `get_device` does not open this driver.
+///
+pub struct MaschineJam {
+    device: HidDevice,
+    tick_state: u8,
+
+    button_leds: [u8; BUTTON_COUNT],
+    button_leds_dirty: bool,
+
+    grid_leds: [u8; GRID_CELLS],
+    grid_leds_dirty: bool,
+
+    meter_leds: [u8; METER_COUNT * METER_SEGMENTS],
+    meter_leds_dirty: bool,
+
+    strip_leds: [u8; STRIP_COUNT * STRIP_LED_COUNT],
+    strip_leds_dirty: bool,
+
+    led_snapshot: LedSnapshot,
+
+    input: InputDecoder,
+}
+
+impl MaschineJam {
+    pub const VENDOR_ID: u16 = 0x17cc;
+    pub const PRODUCT_ID: u16 = 0x1500;
+
+    /// Buttons on the controller
+    pub const BUTTONS: [Button; 49] = [
+        Button::Scene1,
+        Button::Scene2,
+        Button::Scene3,
+        Button::Scene4,
+        Button::Scene5,
+        Button::Scene6,
+        Button::Scene7,
+        Button::Scene8,
+        Button::GroupA,
+        Button::GroupB,
+        Button::GroupC,
+        Button::GroupD,
+        Button::GroupE,
+        Button::GroupF,
+        Button::GroupG,
+        Button::GroupH,
+        Button::Song,
+        Button::Step,
+        Button::PadMode,
+        Button::Clear,
+        Button::Duplicate,
+        Button::Nav,
+        Button::Select,
+        Button::Solo,
+        Button::Mute,
+        Button::Play,
+        Button::Rec,
+        Button::Autowrite,
+        Button::Lock,
+        Button::NoteRepeat,
+        Button::Browse,
+        Button::Macro,
+        Button::Level,
+        Button::Aux,
+        Button::Control,
+        Button::Tune,
+        Button::Swing,
+        Button::Tempo,
+        Button::Perform,
+        Button::Notes,
+        Button::Grid,
+        Button::Master,
+        Button::Input,
+        Button::Cue,
+        Button::ArrowUp,
+        Button::ArrowRight,
+        Button::ArrowDown,
+        Button::ArrowLeft,
+        Button::MainEncoder,
+    ];
+
+    /// Number of touch strips
+    pub const STRIP_COUNT: usize = STRIP_COUNT;
+
+    /// Number of encoders, numbered as in `Event::Encoder`
+    pub const ENCODER_COUNT: usize = 1;
+
+    /// Update LEDs if the arrays have been updated
+    fn send_leds(&mut self) -> Result<(), Error> {
+        if self.button_leds_dirty {
+            let mut buffer: Vec<u8> = vec![BUTTON_LED_ADDR];
+            buffer.extend_from_slice(&self.button_leds);
+            self.device.write(buffer.as_slice())?;
+            self.button_leds_dirty = false;
+        }
+        if self.grid_leds_dirty {
+            let mut buffer: Vec<u8> = vec![GRID_LED_ADDR];
+            buffer.extend_from_slice(&self.grid_leds);
+            self.device.write(buffer.as_slice())?;
+            self.grid_leds_dirty = false;
+        }
+        if self.meter_leds_dirty {
+            let mut buffer: Vec<u8> = vec![METER_LED_ADDR];
+            buffer.extend_from_slice(&self.meter_leds);
+            self.device.write(buffer.as_slice())?;
+            self.meter_leds_dirty = false;
+        }
+        if self.strip_leds_dirty {
+            let mut buffer: Vec<u8> = vec![STRIP_LED_ADDR];
+            buffer.extend_from_slice(&self.strip_leds);
+            self.device.write(buffer.as_slice())?;
+            self.strip_leds_dirty = false;
+        }
+
+        Ok(())
+    }
+
+    /// Read incoming reports from the device
+    fn read(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        let mut buffer = [0u8; INPUT_BUFFER_SIZE];
+
+        for _ in 0..32 {
+            let bytes_read = self.device.read(&mut buffer)?;
+            let shift_pressed = self.input.shift_pressed;
+            self.input.decode(&buffer[..bytes_read], context)?;
+
+            // Shift has no event, show it is held on its LED instead
+            if self.input.shift_pressed != shift_pressed {
+                self.button_leds[BUTTON_SHIFT as usize] =
+                    if self.input.shift_pressed { 0xFF } else { 0x00 };
+                self.button_leds_dirty = true;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Device for MaschineJam {
+    fn new() -> Result<Self, Error> {
+        let hid_api = HidApi::new()?;
+        let mut led_snapshot = LedSnapshot::new(0);
+        led_snapshot.grid = vec![LedColor::OFF; GRID_CELLS];
+
+        Ok(MaschineJam {
+            device: hid_api.open(MaschineJam::VENDOR_ID, MaschineJam::PRODUCT_ID)?,
+            tick_state: 0,
+
+            button_leds: [0; BUTTON_COUNT],
+            button_leds_dirty: true,
+
+            grid_leds: [0; GRID_CELLS],
+            grid_leds_dirty: true,
+
+            meter_leds: [0; METER_COUNT * METER_SEGMENTS],
+            meter_leds_dirty: true,
+
+            strip_leds: [0; STRIP_COUNT * STRIP_LED_COUNT],
+            strip_leds_dirty: true,
+
+            led_snapshot,
+
+            input: InputDecoder::new(),
+        })
+    }
+
+    fn set_button_led(&mut self, button: Button, color: Color) {
+        if let Some(led) = button_to_led(button) {
+            let led_color = LedColor::from(color);
+            self.button_leds[led as usize] = if is_rgb_led(led) {
+                color_index(led_color)
+            } else {
+                led_color.brightness()
+            };
+            self.button_leds_dirty = true;
+            self.led_snapshot.buttons.insert(button, led_color);
+        }
+    }
+
+    fn set_pad_led(&mut self, _pad: u8, _color: Color) {}
+
+    fn set_grid_led(&mut self, column: u8, row: u8, color: Color) {
+        if column < GRID_COLUMNS && row < GRID_ROWS {
+            let cell = (row * GRID_COLUMNS + column) as usize;
+            self.grid_leds[cell] = color_index(color.into());
+            self.grid_leds_dirty = true;
+            self.led_snapshot.grid[cell] = color.into();
+        }
+    }
+
+    fn set_meter(&mut self, meter: u8, level: f32) {
+        self.meter_leds_dirty |= set_meter_leds(&mut self.meter_leds, METER_SEGMENTS, meter, level);
+    }
+
+    fn set_strip_leds(&mut self, strip: u8, leds: StripLeds, color: Color) {
+        if (strip as usize) < STRIP_COUNT {
+            let start = strip as usize * STRIP_LED_COUNT;
+            leds.write(
+                &mut self.strip_leds[start..start + STRIP_LED_COUNT],
+                color_index(color.into()),
+            );
+            self.strip_leds_dirty = true;
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            encoders: vec![EncoderInfo {
+                range: ENCODER_RANGE,
+                touch: false,
+            }],
+            strip_count: STRIP_COUNT,
+            strip_leds: STRIP_LED_COUNT,
+            grid: Some(GridInfo {
+                columns: GRID_COLUMNS,
+                rows: GRID_ROWS,
+            }),
+            meters: vec![
+                MeterInfo {
+                    segments: METER_SEGMENTS
+                };
+                METER_COUNT
+            ],
+            buttons: MaschineJam::BUTTONS.to_vec(),
+            button_leds: MaschineJam::BUTTONS
+                .iter()
+                .filter_map(|&button| {
+                    let led = button_to_led(button)?;
+                    let kind = if is_rgb_led(led) {
+                        LedKind::Rgb
+                    } else {
+                        LedKind::Mono
+                    };
+                    Some((button, kind))
+                })
+                .collect(),
+            ..Capabilities::new(
+                "Maschine Jam",
+                MaschineJam::VENDOR_ID,
+                MaschineJam::PRODUCT_ID,
+            )
+        }
+    }
+
+    fn device_info(&self) -> Result<DeviceInfo, Error> {
+        DeviceInfo::read(&self.device)
+    }
+
+    fn led_snapshot(&self) -> LedSnapshot {
+        self.led_snapshot.clone()
+    }
+
+    fn reconnect(&mut self) -> Result<(), Error> {
+        let hid_api = HidApi::new()?;
+        self.device = hid_api.open(MaschineJam::VENDOR_ID, MaschineJam::PRODUCT_ID)?;
+        self.tick_state = 0;
+
+        // Restore the device to the last known state
+        self.button_leds_dirty = true;
+        self.grid_leds_dirty = true;
+        self.meter_leds_dirty = true;
+        self.strip_leds_dirty = true;
+
+        Ok(())
+    }
+}
+
+impl EventTask for MaschineJam {
+    fn tick(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        if self.tick_state == 0 {
+            self.send_leds()?;
+        } else if self.tick_state == 1 {
+            self.read(context)?;
+        }
+
+        self.tick_state = (self.tick_state + 1) % 2;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::decoder::fixtures::decode;
+
+    // Hand-built from the layout the decoder expects, not captured from hardware. Replace with
+    // USB captures once available.
+
+    // Buttons reports: ID, 7 bytes of button bits, 8 bytes of grid bits, encoder position
+    const BUTTONS_IDLE: &str = "01 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 04";
+    const BUTTONS_SCENE3_SONG: &str = "01 04 00 01 00 00 00 00 00 00 00 00 00 00 00 00 04";
+    const BUTTONS_SHIFT_GRID_CORNERS: &str = "01 00 00 00 08 00 00 00 01 00 00 00 00 00 00 80 04";
+
+    // Strips report: ID, touch count, position and pressure of each of the 8 strips
+    const STRIPS_IDLE: &str = "02 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 \
+        00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00";
+    const STRIPS_STRIP7_TOUCH: &str = "02 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 \
+        00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 01 00 01 40 00";
+
+    #[test]
+    fn buttons_in_both_button_bytes() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_SCENE3_SONG]);
+        assert_eq!(
+            events,
+            vec!["Button(Scene3, true, false)", "Button(Song, true, false)"]
+        );
+    }
+
+    #[test]
+    fn grid_cells_report_column_and_row() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(
+            &mut decoder,
+            &[BUTTONS_IDLE, BUTTONS_SHIFT_GRID_CORNERS, BUTTONS_IDLE],
+        );
+        assert_eq!(
+            events,
+            vec![
+                "Grid(0, 0, true, true)",
+                "Grid(7, 7, true, true)",
+                "Grid(0, 0, false, false)",
+                "Grid(7, 7, false, false)"
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_are_numbered() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(
+            &mut decoder,
+            &[STRIPS_IDLE, STRIPS_STRIP7_TOUCH, STRIPS_IDLE],
+        );
+        assert_eq!(
+            events,
+            vec!["Strip(7, 256, 64, 1, false)", "Strip(7, 0, 0, 0, false)"]
+        );
+    }
+}
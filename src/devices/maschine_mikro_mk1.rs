@@ -0,0 +1,403 @@
+use crate::devices::decoder::{decode_pads, step, ReportDecoder};
+use crate::devices::maschine_mikro_mk2::MikroPanel;
+use crate::devices::{Capabilities, DeviceInfo};
+use crate::display::{DitherMode, Surface};
+use crate::error::Error;
+use crate::events::{Button, Event, EventContext, EventTask};
+use crate::leds::LedSnapshot;
+use crate::{Color, Device, MaschineMikroMk2};
+use hidapi::{HidApi, HidDevice};
+use raqote::DrawTarget;
+
+const INPUT_BUFFER_SIZE: usize = 512;
+
+// Buttons, bit index in the buttons report
+
+pub const BUTTON_MUTE: u8 = 0;
+pub const BUTTON_SOLO: u8 = 1;
+pub const BUTTON_SELECT: u8 = 2;
+pub const BUTTON_DUPLICATE: u8 = 3;
+pub const BUTTON_VIEW: u8 = 4;
+pub const BUTTON_PAD_MODE: u8 = 5;
+pub const BUTTON_PATTERN: u8 = 6;
+pub const BUTTON_SCENE: u8 = 7;
+pub const BUTTON_NOTE_REPEAT: u8 = 8;
+pub const BUTTON_SAMPLING: u8 = 9;
+pub const BUTTON_BROWSE: u8 = 10;
+pub const BUTTON_GROUP: u8 = 11;
+pub const BUTTON_MAIN: u8 = 12;
+pub const BUTTON_BROWSE_RIGHT: u8 = 13;
+pub const BUTTON_BROWSE_LEFT: u8 = 14;
+pub const BUTTON_NAV: u8 = 15;
+pub const BUTTON_CONTROL: u8 = 16;
+pub const BUTTON_F3: u8 = 17;
+pub const BUTTON_F2: u8 = 18;
+pub const BUTTON_F1: u8 = 19;
+pub const BUTTON_MAIN_ENCODER: u8 = 20;
+pub const BUTTON_SHIFT: u8 = 24;
+pub const BUTTON_ERASE: u8 = 25;
+pub const BUTTON_REC: u8 = 26;
+pub const BUTTON_PLAY: u8 = 27;
+pub const BUTTON_GRID: u8 = 28;
+pub const BUTTON_TRANSPORT_RIGHT: u8 = 29;
+pub const BUTTON_TRANSPORT_LEFT: u8 = 30;
+pub const BUTTON_RESTART: u8 = 31;
+
+const BUTTON_COUNT: usize = 32;
+const PAD_COUNT: usize = 16;
+
+const ENCODER_RANGE: u16 = 0x10;
+
+const BUTTONS_REPORT: u8 = 0x01;
+const BUTTONS_REPORT_SIZE: usize = 5;
+const PADS_REPORT: u8 = 0x20;
+
+///
+/// Input state of a Mikro Mk1, decoded from its HID reports
+///
+struct InputDecoder {
+    button_states: [bool; BUTTON_COUNT],
+    shift_pressed: bool,
+    pads_status: [bool; PAD_COUNT],
+    encoder_value: Option<u16>,
+}
+
+impl InputDecoder {
+    fn new() -> Self {
+        Self {
+            button_states: [false; BUTTON_COUNT],
+            shift_pressed: false,
+            pads_status: [false; PAD_COUNT],
+            encoder_value: None,
+        }
+    }
+
+    /// Process a buttons report message
+    ///
+    /// 4 bytes of button bits followed by the encoder position in the low nibble.
+    fn process_buttons(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        if buffer.len() < BUTTONS_REPORT_SIZE {
+            return Err(Error::InvalidReport);
+        }
+
+        let is_pressed = |btn: u8| (buffer[(btn >> 3) as usize] & (1 << (btn % 8))) != 0;
+
+        // Apply Shift before the other buttons so a button pressed together with it is reported
+        // as shifted
+        self.shift_pressed = is_pressed(BUTTON_SHIFT);
+        self.button_states[BUTTON_SHIFT as usize] = self.shift_pressed;
+
+        for btn in BUTTON_MUTE..=BUTTON_RESTART {
+            let button = as_device_button(btn);
+            let button_pressed = is_pressed(btn);
+            if button != Button::Unknown && button_pressed != self.button_states[btn as usize] {
+                self.button_states[btn as usize] = button_pressed;
+                context.add_event(Event::Button(button, button_pressed, self.shift_pressed));
+            }
+        }
+
+        let encoder_value = (buffer[4] & 0x0F) as u16;
+        if let Some(delta) = step(self.encoder_value, encoder_value, ENCODER_RANGE) {
+            context.add_event(Event::Encoder(0, encoder_value, delta, self.shift_pressed));
+        }
+        self.encoder_value = Some(encoder_value);
+
+        Ok(())
+    }
+
+    /// Process a pads report message
+    ///
+    /// A 12-bit pressure value per pad, little endian with the pad number in the top 4 bits.
+    fn process_pads(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        decode_pads(buffer, &mut self.pads_status, self.shift_pressed, context)
+    }
+}
+
+impl ReportDecoder for InputDecoder {
+    fn decode(&mut self, report: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        match report.split_first() {
+            Some((&BUTTONS_REPORT, buffer)) => self.process_buttons(buffer, context),
+            Some((&PADS_REPORT, buffer)) => self.process_pads(buffer, context),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Convert a button code into a button enum
+fn as_device_button(button: u8) -> Button {
+    match button {
+        BUTTON_MUTE => Button::Mute,
+        BUTTON_SOLO => Button::Solo,
+        BUTTON_SELECT => Button::Select,
+        BUTTON_DUPLICATE => Button::Duplicate,
+        BUTTON_VIEW => Button::View,
+        BUTTON_PAD_MODE => Button::PadMode,
+        BUTTON_PATTERN => Button::Pattern,
+        BUTTON_SCENE => Button::Scene,
+        BUTTON_NOTE_REPEAT => Button::NoteRepeat,
+        BUTTON_SAMPLING => Button::Sampling,
+        BUTTON_BROWSE => Button::Browse,
+        BUTTON_GROUP => Button::Group,
+        BUTTON_MAIN => Button::Main,
+        BUTTON_BROWSE_RIGHT => Button::BrowseRight,
+        BUTTON_BROWSE_LEFT => Button::BrowseLeft,
+        BUTTON_NAV => Button::Nav,
+        BUTTON_CONTROL => Button::Control,
+        BUTTON_F3 => Button::F3,
+        BUTTON_F2 => Button::F2,
+        BUTTON_F1 => Button::F1,
+        BUTTON_MAIN_ENCODER => Button::MainEncoder,
+        BUTTON_ERASE => Button::Erase,
+        BUTTON_REC => Button::Rec,
+        BUTTON_PLAY => Button::Play,
+        BUTTON_GRID => Button::Grid,
+        BUTTON_TRANSPORT_RIGHT => Button::TransportRight,
+        BUTTON_TRANSPORT_LEFT => Button::TransportLeft,
+        BUTTON_RESTART => Button::Restart,
+        _ => Button::Unknown,
+    }
+}
+
+///
+/// Maschine Mikro (Mk1) Controller
+///
+/// Requires a valid HID device. The display and LEDs are driven as on the Mikro Mk2, all LEDs are
+/// single colour and show colours as their brightness. Only the input reports differ.
+///
+/// The report layouts have not been verified against captured USB traffic yet, the test fixtures
+/// are written from the same layout as the decoder and only guard against regressions. Reusing
+/// the Mikro Mk2 LED report, with a brightness in the first byte of the slots the Mk2 uses for
+/// RGB LEDs, is also unconfirmed, so `get_device` does not open this driver.
+///
+pub struct MaschineMikroMk1 {
+    device: HidDevice,
+    tick_state: u8,
+    panel: MikroPanel,
+    input: InputDecoder,
+}
+
+impl MaschineMikroMk1 {
+    pub const VENDOR_ID: u16 = 0x17cc;
+    pub const PRODUCT_ID: u16 = 0x1110;
+
+    /// Buttons on the controller, the same as on the Mikro Mk2
+    pub const BUTTONS: [Button; 28] = MaschineMikroMk2::BUTTONS;
+
+    /// Number of pads
+    pub const PAD_COUNT: usize = PAD_COUNT;
+
+    /// Number of encoders, numbered as in `Event::Encoder`
+    pub const ENCODER_COUNT: usize = 1;
+
+    ///
+    /// Encode a display image into the HID reports that are written to the device
+    ///
+    /// **Arguments**
+    /// - display - Image to encode
+    /// - mode - Conversion applied to produce 1-bit pixels
+    pub fn encode_frame(display: &DrawTarget, mode: DitherMode) -> Vec<Vec<u8>> {
+        MaschineMikroMk2::encode_frame(display, mode)
+    }
+
+    /// Read incoming reports from the device
+    fn read(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        let mut buffer = [0u8; INPUT_BUFFER_SIZE];
+
+        for _ in 0..32 {
+            let bytes_read = self.device.read(&mut buffer)?;
+            let shift_pressed = self.input.shift_pressed;
+            self.input.decode(&buffer[..bytes_read], context)?;
+
+            // Shift has no event, show it is held on its LED instead
+            if self.input.shift_pressed != shift_pressed {
+                self.panel.set_shift_led(self.input.shift_pressed);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Device for MaschineMikroMk1 {
+    fn new() -> Result<Self, Error> {
+        let hid_api = HidApi::new()?;
+        Ok(MaschineMikroMk1 {
+            device: hid_api.open(MaschineMikroMk1::VENDOR_ID, MaschineMikroMk1::PRODUCT_ID)?,
+            tick_state: 0,
+            // Unverified, mono brightness in the Mikro Mk2 LED report layout
+            panel: MikroPanel::new(false),
+            input: InputDecoder::new(),
+        })
+    }
+
+    fn set_button_led(&mut self, button: Button, color: Color) {
+        self.panel.set_button_led(button, color);
+    }
+
+    fn set_pad_led(&mut self, pad: u8, color: Color) {
+        self.panel.set_pad_led(pad, color);
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.panel.capabilities(
+            "Maschine Mikro Mk1",
+            MaschineMikroMk1::VENDOR_ID,
+            MaschineMikroMk1::PRODUCT_ID,
+        )
+    }
+
+    fn device_info(&self) -> Result<DeviceInfo, Error> {
+        DeviceInfo::read(&self.device)
+    }
+
+    fn led_snapshot(&self) -> LedSnapshot {
+        self.panel.led_snapshot()
+    }
+
+    fn surfaces(&self) -> &[Surface] {
+        std::slice::from_ref(&self.panel.display)
+    }
+
+    fn surfaces_mut(&mut self) -> &mut [Surface] {
+        std::slice::from_mut(&mut self.panel.display)
+    }
+
+    fn reconnect(&mut self) -> Result<(), Error> {
+        let hid_api = HidApi::new()?;
+        self.device = hid_api.open(MaschineMikroMk1::VENDOR_ID, MaschineMikroMk1::PRODUCT_ID)?;
+        self.tick_state = 0;
+
+        // Restore the device to the last known state
+        self.panel.invalidate();
+
+        Ok(())
+    }
+}
+
+impl EventTask for MaschineMikroMk1 {
+    fn tick(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        if self.tick_state == 0 {
+            self.panel.send_frame(&self.device)?;
+        } else if self.tick_state == 1 {
+            self.panel.send_leds(&self.device)?;
+        } else if self.tick_state == 2 {
+            self.read(context)?;
+        }
+
+        self.tick_state = (self.tick_state + 1) % 3;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::decoder::fixtures::decode;
+
+    // Hand-built from the layout the decoder expects, not captured from hardware. Replace with
+    // USB captures once available.
+
+    // Buttons reports: ID, 4 bytes of button bits, encoder position
+    const BUTTONS_IDLE: &str = "01 00 00 00 00 07";
+    const BUTTONS_PLAY: &str = "01 00 00 00 08 07";
+    const BUTTONS_SHIFT_MUTE: &str = "01 01 00 00 01 07";
+    const BUTTONS_F1_ENCODER_PUSH: &str = "01 00 00 18 00 07";
+    const BUTTONS_ENCODER_UP: &str = "01 00 00 00 00 08";
+    const BUTTONS_ENCODER_TOP: &str = "01 00 00 00 00 0f";
+    const BUTTONS_ENCODER_WRAPPED: &str = "01 00 00 00 00 01";
+
+    // Pads reports: ID, 16 pads of 12-bit pressure with the pad number in the top nibble
+    const PADS_IDLE: &str = "20 00 00 00 10 00 20 00 30 00 40 00 50 00 60 00 70 \
+        00 80 00 90 00 a0 00 b0 00 c0 00 d0 00 e0 00 f0";
+    const PADS_PAD0_PAD15_HIT: &str = "20 40 0a 00 10 00 20 00 30 00 40 00 50 00 60 00 70 \
+        00 80 00 90 00 a0 00 b0 00 c0 00 d0 00 e0 ff ff";
+
+    #[test]
+    fn first_report_sets_encoder_position_without_events() {
+        let mut decoder = InputDecoder::new();
+        assert!(decode(&mut decoder, &[BUTTONS_IDLE]).is_empty());
+    }
+
+    #[test]
+    fn button_press_and_release() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_PLAY, BUTTONS_IDLE]);
+        assert_eq!(
+            events,
+            vec!["Button(Play, true, false)", "Button(Play, false, false)"]
+        );
+    }
+
+    #[test]
+    fn shift_modifies_buttons_without_an_event() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_SHIFT_MUTE]);
+        assert_eq!(events, vec!["Button(Mute, true, true)"]);
+        assert!(decoder.shift_pressed);
+    }
+
+    #[test]
+    fn third_byte_buttons() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_F1_ENCODER_PUSH]);
+        assert_eq!(
+            events,
+            vec![
+                "Button(F1, true, false)",
+                "Button(MainEncoder, true, false)"
+            ]
+        );
+    }
+
+    #[test]
+    fn encoder_reports_deltas_and_wraps() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(
+            &mut decoder,
+            &[
+                BUTTONS_IDLE,
+                BUTTONS_ENCODER_UP,
+                BUTTONS_ENCODER_TOP,
+                BUTTONS_ENCODER_WRAPPED,
+            ],
+        );
+        assert_eq!(
+            events,
+            vec![
+                "Encoder(0, 8, 1, false)",
+                "Encoder(0, 15, 7, false)",
+                "Encoder(0, 1, 2, false)"
+            ]
+        );
+    }
+
+    #[test]
+    fn pad_hits_and_release() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[PADS_IDLE, PADS_PAD0_PAD15_HIT, PADS_IDLE]);
+        assert_eq!(
+            events,
+            vec![
+                "Pad(0, 164, false)",
+                "Pad(15, 255, false)",
+                "Pad(0, 0, false)",
+                "Pad(15, 0, false)"
+            ]
+        );
+    }
+
+    #[test]
+    fn short_reports_are_rejected() {
+        let mut decoder = InputDecoder::new();
+        let mut context = EventContext::new();
+        assert!(matches!(
+            decoder.decode(&[BUTTONS_REPORT, 0, 0], &mut context),
+            Err(Error::InvalidReport)
+        ));
+        assert!(matches!(
+            decoder.decode(&[PADS_REPORT, 0, 0], &mut context),
+            Err(Error::InvalidReport)
+        ));
+    }
+}
@@ -1,8 +1,13 @@
+use crate::devices::decoder::{decode_pads, step};
+use crate::devices::{Capabilities, DeviceInfo, DisplayInfo, EncoderInfo, LedKind};
+use crate::display::{encode_reports, pack, to_monochrome, DitherMode, PixelLayout, Surface};
 use crate::error::Error;
 use crate::events::{Button, Event, EventContext, EventTask};
+use crate::leds::{LedColor, LedSnapshot};
 use crate::{Color, Device};
 use hidapi::{HidApi, HidDevice};
 use raqote::DrawTarget;
+use std::time::Instant;
 
 const INPUT_BUFFER_SIZE: usize = 512;
 
@@ -99,72 +104,236 @@ const LED_ADDR: u8 = 0x80;
 pub struct MaschineMikroMk2 {
     device: HidDevice,
     tick_state: u8,
-    display: DrawTarget,
-    display_dirty: bool,
-    leds: [u8; LED_COUNT],
-    leds_dirty: bool,
+    panel: MikroPanel,
     button_states: [bool; BUTTON_COUNT],
     shift_pressed: bool,
-    pads_data: [u16; PAD_COUNT],
     pads_status: [bool; PAD_COUNT],
     encoder_value: u8,
 }
 
-impl MaschineMikroMk2 {
-    pub const VENDOR_ID: u16 = 0x17cc;
-    pub const PRODUCT_ID: u16 = 0x1200;
+///
+/// Display and LEDs of the Mikro Mk2, shared with the Mikro Mk1
+///
+/// The Mikro Mk1 is driven through the same display and LED reports, with single colour LEDs
+/// in place of the RGB ones.
+///
+pub(super) struct MikroPanel {
+    pub display: Surface,
+    leds: [u8; LED_COUNT],
+    leds_dirty: bool,
+    led_snapshot: LedSnapshot,
+    rgb: bool,
+}
+
+impl MikroPanel {
+    ///
+    /// Create a blank display with every LED off
+    ///
+    /// **Arguments**
+    /// - rgb - The Group button and pad LEDs are RGB (Mikro Mk2), otherwise every LED is single
+    ///   colour and dimmed to the colour brightness (Mikro Mk1)
+    pub fn new(rgb: bool) -> Self {
+        Self {
+            display: Surface::new(128, 64, PixelLayout::Paged),
+            leds: [0; LED_COUNT],
+            leds_dirty: true,
+            led_snapshot: LedSnapshot::new(PAD_COUNT),
+            rgb,
+        }
+    }
 
     /// Send a display frame for the graphics panel
-    fn send_frame(&mut self) -> Result<(), Error> {
-        let img_data: Vec<u8> = self
-            .display
-            .get_data()
-            .chunks(8)
-            .map(|data| {
-                data.iter()
-                    .enumerate()
-                    .map(|(i, d)| (((d & 0x00808080) > 0) as u8) << (8 - i))
-                    .sum()
-            })
-            .collect();
-
-        if self.display_dirty {
-            for row in (0..8).step_by(2) {
-                // The number of referenced bytes must be <= 256
-                // Eg Column width * number of rows
-                let mut buffer: Vec<u8> = vec![
-                    DISPLAY_ADDR,
-                    0x00,      // Column offset
-                    0x00,      // ?
-                    row as u8, // Row (a row is 8 pixels high)
-                    0x00,      // ?
-                    0x80,      // Columns per row, 128 is full width
-                    0x00,      // ?
-                    0x02,      // Number of rows
-                    0x00,      // ?
-                ];
-                let x_offset = row * 128;
-                buffer.extend_from_slice(&img_data[x_offset..(x_offset + 256)]);
-                self.device.write(buffer.as_slice())?;
+    pub fn send_frame(&mut self, device: &HidDevice) -> Result<(), Error> {
+        let start = Instant::now();
+        if let Some(img_data) = self.display.take_frame(start) {
+            let reports = encode_reports(
+                DISPLAY_ADDR,
+                &img_data,
+                self.display.width(),
+                PixelLayout::Paged,
+            );
+            for report in reports {
+                device.write(report.as_slice())?;
             }
+            self.display.record_transfer(start.elapsed());
         }
-        self.display_dirty = false;
 
         Ok(())
     }
 
     /// Update LEDs if the array has been updated
-    fn send_leds(&mut self) -> Result<(), Error> {
+    pub fn send_leds(&mut self, device: &HidDevice) -> Result<(), Error> {
         if self.leds_dirty {
             let mut buffer: Vec<u8> = vec![LED_ADDR];
             buffer.extend_from_slice(&self.leds);
-            self.device.write(buffer.as_slice())?;
+            device.write(buffer.as_slice())?;
         }
         self.leds_dirty = false;
 
         Ok(())
     }
 
+    /// Resend the display and LEDs, eg after reconnecting
+    pub fn invalidate(&mut self) {
+        self.display.invalidate();
+        self.leds_dirty = true;
+    }
+
+    /// Set the colour of a button LED
+    pub fn set_button_led(&mut self, button: Button, color: Color) {
+        if let Some(led) = button_to_led(button) {
+            self.set_led(led, color);
+            self.led_snapshot.buttons.insert(button, color.into());
+        }
+    }
+
+    /// Set the colour of a pad LED
+    pub fn set_pad_led(&mut self, pad: u8, color: Color) {
+        if let Some(led) = pad_to_led(pad) {
+            self.set_led(led, color);
+            self.led_snapshot.pads[pad as usize] = color.into();
+        }
+    }
+
+    /// Show Shift is held, it has no event
+    pub fn set_shift_led(&mut self, lit: bool) {
+        let level = if lit { 0xFF } else { 0x00 };
+        self.set_led(LED_SHIFT, Color::new(0xFF, level, level, level));
+    }
+
+    /// Last colours applied to the LEDs
+    pub fn led_snapshot(&self) -> LedSnapshot {
+        self.led_snapshot.clone()
+    }
+
+    ///
+    /// Describe the controls, display and LEDs
+    ///
+    /// **Arguments**
+    /// - model - Model name
+    /// - vendor_id - USB vendor ID
+    /// - product_id - USB product ID
+    pub fn capabilities(&self, model: &str, vendor_id: u16, product_id: u16) -> Capabilities {
+        Capabilities {
+            displays: vec![DisplayInfo {
+                width: self.display.width(),
+                height: self.display.height(),
+            }],
+            encoders: vec![EncoderInfo {
+                range: 0x10,
+                touch: false,
+            }],
+            pad_count: PAD_COUNT,
+            pad_leds: Some(if self.rgb {
+                LedKind::Rgb
+            } else {
+                LedKind::Mono
+            }),
+            buttons: MaschineMikroMk2::BUTTONS.to_vec(),
+            button_leds: MaschineMikroMk2::BUTTONS
+                .iter()
+                .filter_map(|&button| {
+                    let led = button_to_led(button)?;
+                    let kind = if self.is_rgb_led(led) {
+                        LedKind::Rgb
+                    } else {
+                        LedKind::Mono
+                    };
+                    Some((button, kind))
+                })
+                .collect(),
+            ..Capabilities::new(model, vendor_id, product_id)
+        }
+    }
+
+    /// Set the color of an LED
+    fn set_led(&mut self, led: u8, color: Color) {
+        let base = led as usize;
+
+        if self.is_rgb_led(led) {
+            self.leds[base] = color.r();
+            self.leds[base + 1] = color.g();
+            self.leds[base + 2] = color.b();
+            self.leds_dirty = true;
+        } else {
+            let level = LedColor::from(color).brightness();
+            let m = if !self.rgb {
+                level
+            } else if level > 0x80 {
+                0xFF
+            } else {
+                0x00
+            };
+            self.leds_dirty |= m != self.leds[base];
+            self.leds[base] = m;
+        }
+    }
+
+    /// Determine if an LED is RGB or Mono
+    fn is_rgb_led(&self, led: u8) -> bool {
+        self.rgb && ((led == LED_GROUP) | (LED_PAD13..=LED_PAD04).contains(&led))
+    }
+}
+
+impl MaschineMikroMk2 {
+    pub const VENDOR_ID: u16 = 0x17cc;
+    pub const PRODUCT_ID: u16 = 0x1200;
+
+    /// Buttons on the controller
+    pub const BUTTONS: [Button; 28] = [
+        Button::Erase,
+        Button::Rec,
+        Button::Play,
+        Button::Grid,
+        Button::TransportRight,
+        Button::TransportLeft,
+        Button::Restart,
+        Button::MainEncoder,
+        Button::NoteRepeat,
+        Button::Sampling,
+        Button::Browse,
+        Button::Group,
+        Button::Main,
+        Button::BrowseRight,
+        Button::BrowseLeft,
+        Button::Nav,
+        Button::Control,
+        Button::F3,
+        Button::F2,
+        Button::F1,
+        Button::Mute,
+        Button::Solo,
+        Button::Select,
+        Button::Duplicate,
+        Button::View,
+        Button::PadMode,
+        Button::Pattern,
+        Button::Scene,
+    ];
+
+    /// Number of pads
+    pub const PAD_COUNT: usize = PAD_COUNT;
+
+    /// Number of encoders, numbered as in `Event::Encoder`
+    pub const ENCODER_COUNT: usize = 1;
+
+    ///
+    /// Encode a display image into the HID reports that are written to the device
+    ///
+    /// **Arguments**
+    /// - display - Image to encode
+    /// - mode - Conversion applied to produce 1-bit pixels
+    pub fn encode_frame(display: &DrawTarget, mode: DitherMode) -> Vec<Vec<u8>> {
+        let width = display.width() as usize;
+        let img_data = pack(
+            &to_monochrome(display.get_data(), width, mode),
+            width,
+            PixelLayout::Paged,
+        );
+
+        encode_reports(DISPLAY_ADDR, &img_data, width, PixelLayout::Paged)
+    }
+
     /// Read incoming reports from the device
     fn read(&mut self, context: &mut EventContext) -> Result<(), Error> {
         let mut buffer = [0u8; INPUT_BUFFER_SIZE];
@@ -199,14 +368,7 @@ impl MaschineMikroMk2 {
 
                 if btn == BUTTON_SHIFT {
                     self.shift_pressed = button_pressed;
-                    self.set_led(
-                        LED_SHIFT,
-                        if button_pressed {
-                            Color::new(0xFF, 0xFF, 0xFF, 0xFF)
-                        } else {
-                            Color::new(0xFF, 0x00, 0x00, 0x00)
-                        },
-                    );
+                    self.panel.set_shift_led(button_pressed);
                 } else {
                     let button = self.as_device_button(btn);
                     context.add_event(Event::Button(button, button_pressed, self.shift_pressed));
@@ -216,20 +378,8 @@ impl MaschineMikroMk2 {
 
         // Handle encoder data
         let encoder_value = buffer[4];
-        if self.encoder_value != encoder_value {
-            let encoder_range = 0x10;
-
-            let pos_delta =
-                ((encoder_range + encoder_value - self.encoder_value) % encoder_range) as i16;
-            let neg_delta =
-                ((encoder_range - encoder_value + self.encoder_value) % encoder_range) as i16;
-
-            let delta = if pos_delta < neg_delta {
-                pos_delta
-            } else {
-                -neg_delta
-            };
-
+        let previous = Some(self.encoder_value as u16);
+        if let Some(delta) = step(previous, encoder_value as u16, 0x10) {
             self.encoder_value = encoder_value;
             context.add_event(Event::Encoder(
                 0,
@@ -248,50 +398,7 @@ impl MaschineMikroMk2 {
             return Err(Error::InvalidReport);
         }
 
-        for idx in (0..32).step_by(2) {
-            let low_byte = buffer[idx];
-            let high_byte = buffer[idx + 1];
-            let pad = ((high_byte & 0xF0) >> 4) as usize;
-            let value = (((high_byte & 0x0F) as u16) << 8) | low_byte as u16;
-            let pressed = value > 512;
-
-            self.pads_data[pad] = value;
-            if pressed | self.pads_status[pad] {
-                self.pads_status[pad] = pressed;
-                context.add_event(Event::Pad(
-                    pad as u8,
-                    if pressed { (value >> 4) as u8 } else { 0 },
-                    self.shift_pressed,
-                ));
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Set the color of an LED
-    fn set_led(&mut self, led: u8, color: Color) {
-        let base = led as usize;
-
-        if self.is_rgb_led(led) {
-            self.leds[base] = color.r();
-            self.leds[base + 1] = color.g();
-            self.leds[base + 2] = color.b();
-            self.leds_dirty = true;
-        } else {
-            let m = if color.r() > 0x80 || color.g() > 0x80 || color.b() > 0x80 {
-                0xFF
-            } else {
-                0x00
-            };
-            self.leds_dirty = m != self.leds[base];
-            self.leds[base] = m;
-        }
-    }
-
-    /// Determine if an LED is RGB or Mono
-    fn is_rgb_led(&self, led: u8) -> bool {
-        (led == LED_GROUP) | (LED_PAD13..=LED_PAD04).contains(&led)
+        decode_pads(buffer, &mut self.pads_status, self.shift_pressed, context)
     }
 
     /// Convert a button code into a button enum
@@ -328,62 +435,62 @@ impl MaschineMikroMk2 {
             _ => Button::Unknown,
         }
     }
+}
 
-    /// Convert a button into a LED index
-    fn button_to_led(&self, button: Button) -> Option<u8> {
-        match button {
-            Button::Erase => Some(LED_ERASE),
-            Button::Rec => Some(LED_REC),
-            Button::Play => Some(LED_PLAY),
-            Button::Grid => Some(LED_GRID),
-            Button::TransportRight => Some(LED_TRANSPORT_RIGHT),
-            Button::TransportLeft => Some(LED_TRANSPORT_LEFT),
-            Button::Restart => Some(LED_RESTART),
-            Button::NoteRepeat => Some(LED_NOTE_REPEAT),
-            Button::Sampling => Some(LED_SAMPLING),
-            Button::Browse => Some(LED_BROWSE),
-            Button::Group => Some(LED_GROUP),
-            Button::Main => Some(LED_MAIN),
-            Button::BrowseRight => Some(LED_BROWSE_RIGHT),
-            Button::BrowseLeft => Some(LED_BROWSE_LEFT),
-            Button::Nav => Some(LED_NAV),
-            Button::Control => Some(LED_CONTROL),
-            Button::F3 => Some(LED_F3),
-            Button::F2 => Some(LED_F2),
-            Button::F1 => Some(LED_F1),
-            Button::Mute => Some(LED_MUTE),
-            Button::Solo => Some(LED_SOLO),
-            Button::Select => Some(LED_SELECT),
-            Button::Duplicate => Some(LED_DUPLICATE),
-            Button::View => Some(LED_VIEW),
-            Button::PadMode => Some(LED_PADMODE),
-            Button::Pattern => Some(LED_PATTERN),
-            Button::Scene => Some(LED_SCENE),
-            _ => None,
-        }
+/// Convert a button into a LED index
+fn button_to_led(button: Button) -> Option<u8> {
+    match button {
+        Button::Erase => Some(LED_ERASE),
+        Button::Rec => Some(LED_REC),
+        Button::Play => Some(LED_PLAY),
+        Button::Grid => Some(LED_GRID),
+        Button::TransportRight => Some(LED_TRANSPORT_RIGHT),
+        Button::TransportLeft => Some(LED_TRANSPORT_LEFT),
+        Button::Restart => Some(LED_RESTART),
+        Button::NoteRepeat => Some(LED_NOTE_REPEAT),
+        Button::Sampling => Some(LED_SAMPLING),
+        Button::Browse => Some(LED_BROWSE),
+        Button::Group => Some(LED_GROUP),
+        Button::Main => Some(LED_MAIN),
+        Button::BrowseRight => Some(LED_BROWSE_RIGHT),
+        Button::BrowseLeft => Some(LED_BROWSE_LEFT),
+        Button::Nav => Some(LED_NAV),
+        Button::Control => Some(LED_CONTROL),
+        Button::F3 => Some(LED_F3),
+        Button::F2 => Some(LED_F2),
+        Button::F1 => Some(LED_F1),
+        Button::Mute => Some(LED_MUTE),
+        Button::Solo => Some(LED_SOLO),
+        Button::Select => Some(LED_SELECT),
+        Button::Duplicate => Some(LED_DUPLICATE),
+        Button::View => Some(LED_VIEW),
+        Button::PadMode => Some(LED_PADMODE),
+        Button::Pattern => Some(LED_PATTERN),
+        Button::Scene => Some(LED_SCENE),
+        _ => None,
     }
+}
 
-    /// Convert a button into a LED index
-    fn pad_to_led(&self, pad: u8) -> Option<u8> {
-        match pad {
-            0x0 => Some(LED_PAD13),
-            0x1 => Some(LED_PAD14),
-            0x2 => Some(LED_PAD15),
-            0x3 => Some(LED_PAD16),
-            0x4 => Some(LED_PAD09),
-            0x5 => Some(LED_PAD10),
-            0x6 => Some(LED_PAD11),
-            0x7 => Some(LED_PAD12),
-            0x8 => Some(LED_PAD05),
-            0x9 => Some(LED_PAD06),
-            0xA => Some(LED_PAD07),
-            0xB => Some(LED_PAD08),
-            0xC => Some(LED_PAD01),
-            0xD => Some(LED_PAD02),
-            0xE => Some(LED_PAD03),
-            0xF => Some(LED_PAD04),
-            _ => None,
-        }
+/// Convert a pad number into a LED index
+fn pad_to_led(pad: u8) -> Option<u8> {
+    match pad {
+        0x0 => Some(LED_PAD13),
+        0x1 => Some(LED_PAD14),
+        0x2 => Some(LED_PAD15),
+        0x3 => Some(LED_PAD16),
+        0x4 => Some(LED_PAD09),
+        0x5 => Some(LED_PAD10),
+        0x6 => Some(LED_PAD11),
+        0x7 => Some(LED_PAD12),
+        0x8 => Some(LED_PAD05),
+        0x9 => Some(LED_PAD06),
+        0xA => Some(LED_PAD07),
+        0xB => Some(LED_PAD08),
+        0xC => Some(LED_PAD01),
+        0xD => Some(LED_PAD02),
+        0xE => Some(LED_PAD03),
+        0xF => Some(LED_PAD04),
+        _ => None,
     }
 }
 
@@ -393,46 +500,64 @@ impl Device for MaschineMikroMk2 {
         Ok(MaschineMikroMk2 {
             device: hid_api.open(MaschineMikroMk2::VENDOR_ID, MaschineMikroMk2::PRODUCT_ID)?,
             tick_state: 0,
-            display: DrawTarget::new(128, 64),
-            display_dirty: true,
-            leds: [0; LED_COUNT],
-            leds_dirty: true,
+            panel: MikroPanel::new(true),
             button_states: [false; BUTTON_COUNT],
             shift_pressed: false,
-            pads_data: [0; PAD_COUNT],
             pads_status: [false; PAD_COUNT],
             encoder_value: 0,
         })
     }
 
     fn set_button_led(&mut self, button: Button, color: Color) {
-        if let Some(led) = self.button_to_led(button) {
-            self.set_led(led, color);
-        }
+        self.panel.set_button_led(button, color);
     }
 
     fn set_pad_led(&mut self, pad: u8, color: Color) {
-        if let Some(led) = self.pad_to_led(pad) {
-            self.set_led(led, color);
-        }
+        self.panel.set_pad_led(pad, color);
     }
 
-    fn get_display(&mut self, display_idx: u8) -> Result<&mut DrawTarget, Error> {
-        if display_idx != 0 {
-            Err(Error::InvalidDisplay(display_idx))
-        } else {
-            self.display_dirty = true;
-            Ok(&mut self.display)
-        }
+    fn capabilities(&self) -> Capabilities {
+        self.panel.capabilities(
+            "Maschine Mikro Mk2",
+            MaschineMikroMk2::VENDOR_ID,
+            MaschineMikroMk2::PRODUCT_ID,
+        )
+    }
+
+    fn device_info(&self) -> Result<DeviceInfo, Error> {
+        DeviceInfo::read(&self.device)
+    }
+
+    fn led_snapshot(&self) -> LedSnapshot {
+        self.panel.led_snapshot()
+    }
+
+    fn surfaces(&self) -> &[Surface] {
+        std::slice::from_ref(&self.panel.display)
+    }
+
+    fn surfaces_mut(&mut self) -> &mut [Surface] {
+        std::slice::from_mut(&mut self.panel.display)
+    }
+
+    fn reconnect(&mut self) -> Result<(), Error> {
+        let hid_api = HidApi::new()?;
+        self.device = hid_api.open(MaschineMikroMk2::VENDOR_ID, MaschineMikroMk2::PRODUCT_ID)?;
+        self.tick_state = 0;
+
+        // Restore the device to the last known state
+        self.panel.invalidate();
+
+        Ok(())
     }
 }
 
 impl EventTask for MaschineMikroMk2 {
     fn tick(&mut self, context: &mut EventContext) -> Result<(), Error> {
         if self.tick_state == 0 {
-            self.send_frame()?;
+            self.panel.send_frame(&self.device)?;
         } else if self.tick_state == 1 {
-            self.send_leds()?;
+            self.panel.send_leds(&self.device)?;
         } else if self.tick_state == 2 {
             self.read(context)?;
         }
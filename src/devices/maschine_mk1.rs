@@ -0,0 +1,587 @@
+use crate::devices::decoder::{decode_pads, step, ReportDecoder};
+use crate::devices::{Capabilities, DeviceInfo, DisplayInfo, EncoderInfo, LedKind};
+use crate::display::{encode_reports, pack, to_monochrome, DitherMode, PixelLayout, Surface};
+use crate::error::Error;
+use crate::events::{Button, Event, EventContext, EventTask};
+use crate::leds::{LedColor, LedSnapshot};
+use crate::{Color, Device};
+use hidapi::{HidApi, HidDevice};
+use raqote::DrawTarget;
+use std::time::Instant;
+
+const INPUT_BUFFER_SIZE: usize = 512;
+
+// Buttons, bit index in the buttons report
+
+pub const BUTTON_DISPLAY1: u8 = 0;
+pub const BUTTON_DISPLAY2: u8 = 1;
+pub const BUTTON_DISPLAY3: u8 = 2;
+pub const BUTTON_DISPLAY4: u8 = 3;
+pub const BUTTON_DISPLAY5: u8 = 4;
+pub const BUTTON_DISPLAY6: u8 = 5;
+pub const BUTTON_DISPLAY7: u8 = 6;
+pub const BUTTON_DISPLAY8: u8 = 7;
+pub const BUTTON_CONTROL: u8 = 8;
+pub const BUTTON_STEP: u8 = 9;
+pub const BUTTON_BROWSE: u8 = 10;
+pub const BUTTON_SAMPLING: u8 = 11;
+pub const BUTTON_BROWSELEFT: u8 = 12;
+pub const BUTTON_BROWSERIGHT: u8 = 13;
+pub const BUTTON_SNAP: u8 = 14;
+pub const BUTTON_AUTOWRITE: u8 = 15;
+pub const BUTTON_NOTEREPEAT: u8 = 16;
+pub const BUTTON_GROUPA: u8 = 17;
+pub const BUTTON_GROUPB: u8 = 18;
+pub const BUTTON_GROUPC: u8 = 19;
+pub const BUTTON_GROUPD: u8 = 20;
+pub const BUTTON_GROUPE: u8 = 21;
+pub const BUTTON_GROUPF: u8 = 22;
+pub const BUTTON_GROUPG: u8 = 23;
+pub const BUTTON_GROUPH: u8 = 24;
+pub const BUTTON_RESTART: u8 = 25;
+pub const BUTTON_TRANSPORTLEFT: u8 = 26;
+pub const BUTTON_TRANSPORTRIGHT: u8 = 27;
+pub const BUTTON_GRID: u8 = 28;
+pub const BUTTON_PLAY: u8 = 29;
+pub const BUTTON_REC: u8 = 30;
+pub const BUTTON_ERASE: u8 = 31;
+pub const BUTTON_SHIFT: u8 = 32;
+pub const BUTTON_SCENE: u8 = 33;
+pub const BUTTON_PATTERN: u8 = 34;
+pub const BUTTON_PADMODE: u8 = 35;
+pub const BUTTON_NAVIGATE: u8 = 36;
+pub const BUTTON_DUPLICATE: u8 = 37;
+pub const BUTTON_SELECT: u8 = 38;
+pub const BUTTON_SOLO: u8 = 39;
+pub const BUTTON_MUTE: u8 = 40;
+
+const BUTTON_COUNT: usize = 41;
+const PAD_COUNT: usize = 16;
+
+// Encoders, in `Event::Encoder` numbering: 0 is volume, 1-8 are the knobs below the displays
+// as on the Mk2, 9 is tempo and 10 is swing. The Mk1 has no main encoder.
+
+const ENCODER_COUNT: usize = 11;
+const ENCODER_RANGE: u16 = 0x400;
+
+const BUTTONS_REPORT: u8 = 0x01;
+const BUTTONS_REPORT_SIZE: usize = 6 + 2 * ENCODER_COUNT;
+const PADS_REPORT: u8 = 0x20;
+
+const DISPLAY_COUNT: u8 = 2;
+const DISPLAY_ADDR: u8 = 0xE0;
+
+const BUTTON_LED_ADDR: u8 = 0x82;
+const PAD_LED_ADDR: u8 = 0x80;
+
+///
+/// Input state of a Mk1, decoded from its HID reports
+///
+struct InputDecoder {
+    button_states: [bool; BUTTON_COUNT],
+    shift_pressed: bool,
+    pads_status: [bool; PAD_COUNT],
+    encoder_values: [Option<u16>; ENCODER_COUNT],
+}
+
+impl InputDecoder {
+    fn new() -> Self {
+        Self {
+            button_states: [false; BUTTON_COUNT],
+            shift_pressed: false,
+            pads_status: [false; PAD_COUNT],
+            encoder_values: [None; ENCODER_COUNT],
+        }
+    }
+
+    /// Process a buttons report message
+    ///
+    /// 6 bytes of button bits followed by an absolute 10-bit value per encoder, little endian.
+    fn process_buttons(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        if buffer.len() < BUTTONS_REPORT_SIZE {
+            return Err(Error::InvalidReport);
+        }
+
+        let is_pressed = |btn: u8| (buffer[(btn >> 3) as usize] & (1 << (btn % 8))) != 0;
+
+        // Shift comes after most buttons in the report, apply it first so a button pressed
+        // together with it is reported as shifted
+        self.shift_pressed = is_pressed(BUTTON_SHIFT);
+        self.button_states[BUTTON_SHIFT as usize] = self.shift_pressed;
+
+        for btn in BUTTON_DISPLAY1..=BUTTON_MUTE {
+            let button_pressed = is_pressed(btn);
+            if btn != BUTTON_SHIFT && button_pressed != self.button_states[btn as usize] {
+                self.button_states[btn as usize] = button_pressed;
+
+                let button = as_device_button(btn);
+                context.add_event(Event::Button(button, button_pressed, self.shift_pressed));
+            }
+        }
+
+        for i in 0..ENCODER_COUNT {
+            let value = u16::from_le_bytes([buffer[6 + 2 * i], buffer[7 + 2 * i]]) % ENCODER_RANGE;
+
+            if let Some(delta) = step(self.encoder_values[i], value, ENCODER_RANGE) {
+                context.add_event(Event::Encoder(i as u8, value, delta, self.shift_pressed));
+            }
+            self.encoder_values[i] = Some(value);
+        }
+
+        Ok(())
+    }
+
+    /// Process a pads report message
+    ///
+    /// A 12-bit pressure value per pad, little endian with the pad number in the top 4 bits.
+    fn process_pads(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        decode_pads(buffer, &mut self.pads_status, self.shift_pressed, context)
+    }
+}
+
+impl ReportDecoder for InputDecoder {
+    fn decode(&mut self, report: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        match report.split_first() {
+            Some((&BUTTONS_REPORT, buffer)) => self.process_buttons(buffer, context),
+            Some((&PADS_REPORT, buffer)) => self.process_pads(buffer, context),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Convert a button code into a button enum
+fn as_device_button(button: u8) -> Button {
+    match button {
+        BUTTON_DISPLAY1 => Button::Display1,
+        BUTTON_DISPLAY2 => Button::Display2,
+        BUTTON_DISPLAY3 => Button::Display3,
+        BUTTON_DISPLAY4 => Button::Display4,
+        BUTTON_DISPLAY5 => Button::Display5,
+        BUTTON_DISPLAY6 => Button::Display6,
+        BUTTON_DISPLAY7 => Button::Display7,
+        BUTTON_DISPLAY8 => Button::Display8,
+        BUTTON_CONTROL => Button::Control,
+        BUTTON_STEP => Button::Step,
+        BUTTON_BROWSE => Button::Browse,
+        BUTTON_SAMPLING => Button::Sampling,
+        BUTTON_BROWSELEFT => Button::BrowseLeft,
+        BUTTON_BROWSERIGHT => Button::BrowseRight,
+        BUTTON_SNAP => Button::All,
+        BUTTON_AUTOWRITE => Button::Autowrite,
+        BUTTON_NOTEREPEAT => Button::NoteRepeat,
+        BUTTON_GROUPA => Button::GroupA,
+        BUTTON_GROUPB => Button::GroupB,
+        BUTTON_GROUPC => Button::GroupC,
+        BUTTON_GROUPD => Button::GroupD,
+        BUTTON_GROUPE => Button::GroupE,
+        BUTTON_GROUPF => Button::GroupF,
+        BUTTON_GROUPG => Button::GroupG,
+        BUTTON_GROUPH => Button::GroupH,
+        BUTTON_RESTART => Button::Restart,
+        BUTTON_TRANSPORTLEFT => Button::TransportLeft,
+        BUTTON_TRANSPORTRIGHT => Button::TransportRight,
+        BUTTON_GRID => Button::Grid,
+        BUTTON_PLAY => Button::Play,
+        BUTTON_REC => Button::Rec,
+        BUTTON_ERASE => Button::Erase,
+        BUTTON_SCENE => Button::Scene,
+        BUTTON_PATTERN => Button::Pattern,
+        BUTTON_PADMODE => Button::PadMode,
+        BUTTON_NAVIGATE => Button::Nav,
+        BUTTON_DUPLICATE => Button::Duplicate,
+        BUTTON_SELECT => Button::Select,
+        BUTTON_SOLO => Button::Solo,
+        BUTTON_MUTE => Button::Mute,
+        _ => Button::Unknown,
+    }
+}
+
+/// Convert a button into its code, every Mk1 button has a mono LED at the same index
+fn button_to_code(button: Button) -> Option<u8> {
+    (BUTTON_DISPLAY1..=BUTTON_MUTE)
+        .find(|&code| code != BUTTON_SHIFT && as_device_button(code) == button)
+}
+
+///
+/// Maschine (Mk1) Controller
+///
+/// Requires a valid HID device
+///
+/// The report layouts and LED addresses have not been verified against captured USB traffic yet,
+/// the test fixtures are written from the same layout as the decoder and only guard against
+/// regressions. The Mk1 may not use plain HID reports at all, so `get_device` does not open it.
+///
+pub struct MaschineMk1 {
+    device: HidDevice,
+    tick_state: u8,
+    displays: [Surface; DISPLAY_COUNT as usize],
+
+    button_leds: [u8; BUTTON_COUNT],
+    button_leds_dirty: bool,
+
+    pad_leds: [u8; PAD_COUNT],
+    pad_leds_dirty: bool,
+
+    led_snapshot: LedSnapshot,
+
+    input: InputDecoder,
+}
+
+impl MaschineMk1 {
+    pub const VENDOR_ID: u16 = 0x17cc;
+    pub const PRODUCT_ID: u16 = 0x0808;
+
+    /// Buttons on the controller
+    pub const BUTTONS: [Button; 40] = [
+        Button::Display1,
+        Button::Display2,
+        Button::Display3,
+        Button::Display4,
+        Button::Display5,
+        Button::Display6,
+        Button::Display7,
+        Button::Display8,
+        Button::Erase,
+        Button::Rec,
+        Button::Play,
+        Button::Grid,
+        Button::TransportRight,
+        Button::TransportLeft,
+        Button::Restart,
+        Button::NoteRepeat,
+        Button::Sampling,
+        Button::Browse,
+        Button::All,
+        Button::Autowrite,
+        Button::GroupA,
+        Button::GroupB,
+        Button::GroupC,
+        Button::GroupD,
+        Button::GroupE,
+        Button::GroupF,
+        Button::GroupG,
+        Button::GroupH,
+        Button::BrowseRight,
+        Button::BrowseLeft,
+        Button::Nav,
+        Button::Control,
+        Button::Step,
+        Button::Mute,
+        Button::Solo,
+        Button::Select,
+        Button::Duplicate,
+        Button::PadMode,
+        Button::Pattern,
+        Button::Scene,
+    ];
+
+    /// Number of pads
+    pub const PAD_COUNT: usize = PAD_COUNT;
+
+    /// Number of encoders, numbered as in `Event::Encoder`
+    pub const ENCODER_COUNT: usize = ENCODER_COUNT;
+
+    ///
+    /// Encode a display image into the HID reports that are written to the device
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    /// - display - Image to encode
+    /// - mode - Conversion applied to produce 1-bit pixels
+    pub fn encode_frame(display_idx: u8, display: &DrawTarget, mode: DitherMode) -> Vec<Vec<u8>> {
+        let width = display.width() as usize;
+        let img_data = pack(
+            &to_monochrome(display.get_data(), width, mode),
+            width,
+            PixelLayout::Horizontal,
+        );
+
+        encode_reports(
+            DISPLAY_ADDR | display_idx,
+            &img_data,
+            width,
+            PixelLayout::Horizontal,
+        )
+    }
+
+    /// Send a display frame for the graphics panel
+    fn send_frame(&mut self, display_idx: u8) -> Result<(), Error> {
+        if display_idx >= DISPLAY_COUNT {
+            return Err(Error::InvalidDisplay(display_idx));
+        }
+        let display = &mut self.displays[display_idx as usize];
+        let start = Instant::now();
+        if let Some(img_data) = display.take_frame(start) {
+            let reports = encode_reports(
+                DISPLAY_ADDR | display_idx,
+                &img_data,
+                display.width(),
+                PixelLayout::Horizontal,
+            );
+            for report in reports {
+                self.device.write(report.as_slice())?;
+            }
+            display.record_transfer(start.elapsed());
+        }
+
+        Ok(())
+    }
+
+    /// Update LEDs if the array has been updated
+    fn send_leds(&mut self) -> Result<(), Error> {
+        if self.button_leds_dirty {
+            let mut buffer: Vec<u8> = vec![BUTTON_LED_ADDR];
+            buffer.extend_from_slice(&self.button_leds);
+            self.device.write(buffer.as_slice())?;
+            self.button_leds_dirty = false;
+        }
+        if self.pad_leds_dirty {
+            let mut buffer: Vec<u8> = vec![PAD_LED_ADDR];
+            buffer.extend_from_slice(&self.pad_leds);
+            self.device.write(buffer.as_slice())?;
+            self.pad_leds_dirty = false;
+        }
+
+        Ok(())
+    }
+
+    /// Read incoming reports from the device
+    fn read(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        let mut buffer = [0u8; INPUT_BUFFER_SIZE];
+
+        for _ in 0..32 {
+            let bytes_read = self.device.read(&mut buffer)?;
+            let shift_pressed = self.input.shift_pressed;
+            self.input.decode(&buffer[..bytes_read], context)?;
+
+            // Shift has no event, show it is held on its LED instead
+            if self.input.shift_pressed != shift_pressed {
+                self.button_leds[BUTTON_SHIFT as usize] =
+                    if self.input.shift_pressed { 0xFF } else { 0x00 };
+                self.button_leds_dirty = true;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Device for MaschineMk1 {
+    fn new() -> Result<Self, Error> {
+        let hid_api = HidApi::new()?;
+        Ok(MaschineMk1 {
+            device: hid_api.open(MaschineMk1::VENDOR_ID, MaschineMk1::PRODUCT_ID)?,
+            tick_state: 0,
+            displays: [
+                Surface::new(256, 64, PixelLayout::Horizontal),
+                Surface::new(256, 64, PixelLayout::Horizontal),
+            ],
+
+            button_leds: [0; BUTTON_COUNT],
+            button_leds_dirty: true,
+
+            pad_leds: [0; PAD_COUNT],
+            pad_leds_dirty: true,
+
+            led_snapshot: LedSnapshot::new(PAD_COUNT),
+
+            input: InputDecoder::new(),
+        })
+    }
+
+    fn set_button_led(&mut self, button: Button, color: Color) {
+        if let Some(code) = button_to_code(button) {
+            self.button_leds[code as usize] = LedColor::from(color).brightness();
+            self.button_leds_dirty = true;
+            self.led_snapshot.buttons.insert(button, color.into());
+        }
+    }
+
+    fn set_pad_led(&mut self, pad: u8, color: Color) {
+        if (pad as usize) < PAD_COUNT {
+            self.pad_leds[pad as usize] = LedColor::from(color).brightness();
+            self.pad_leds_dirty = true;
+            self.led_snapshot.pads[pad as usize] = color.into();
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            displays: self
+                .displays
+                .iter()
+                .map(|display| DisplayInfo {
+                    width: display.width(),
+                    height: display.height(),
+                })
+                .collect(),
+            encoders: vec![
+                EncoderInfo {
+                    range: ENCODER_RANGE,
+                    touch: false,
+                };
+                ENCODER_COUNT
+            ],
+            pad_count: PAD_COUNT,
+            pad_leds: Some(LedKind::Mono),
+            buttons: MaschineMk1::BUTTONS.to_vec(),
+            button_leds: MaschineMk1::BUTTONS
+                .iter()
+                .map(|&button| (button, LedKind::Mono))
+                .collect(),
+            ..Capabilities::new(
+                "Maschine Mk1",
+                MaschineMk1::VENDOR_ID,
+                MaschineMk1::PRODUCT_ID,
+            )
+        }
+    }
+
+    fn device_info(&self) -> Result<DeviceInfo, Error> {
+        DeviceInfo::read(&self.device)
+    }
+
+    fn led_snapshot(&self) -> LedSnapshot {
+        self.led_snapshot.clone()
+    }
+
+    fn surfaces(&self) -> &[Surface] {
+        &self.displays
+    }
+
+    fn surfaces_mut(&mut self) -> &mut [Surface] {
+        &mut self.displays
+    }
+
+    fn reconnect(&mut self) -> Result<(), Error> {
+        let hid_api = HidApi::new()?;
+        self.device = hid_api.open(MaschineMk1::VENDOR_ID, MaschineMk1::PRODUCT_ID)?;
+        self.tick_state = 0;
+
+        // Restore the device to the last known state
+        for display in self.displays.iter_mut() {
+            display.invalidate();
+        }
+        self.button_leds_dirty = true;
+        self.pad_leds_dirty = true;
+
+        Ok(())
+    }
+}
+
+impl EventTask for MaschineMk1 {
+    fn tick(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        if self.tick_state == 0 {
+            for i in 0..DISPLAY_COUNT {
+                self.send_frame(i)?;
+            }
+        } else if self.tick_state == 1 {
+            self.send_leds()?;
+        } else if self.tick_state == 2 {
+            self.read(context)?;
+        }
+
+        self.tick_state = (self.tick_state + 1) % 3;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::decoder::fixtures::decode;
+
+    // Hand-built from the layout the decoder expects, not captured from hardware. Replace with
+    // USB captures once available.
+
+    // Buttons reports: ID, 6 bytes of button bits, 11 encoders
+    const BUTTONS_IDLE: &str = "01 00 00 00 00 00 00 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_PLAY: &str = "01 00 00 00 20 00 00 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_SHIFT_GROUP_A: &str = "01 00 00 02 00 01 00 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_KNOB1_UP_TEMPO_DOWN: &str = "01 00 00 00 00 00 00 \
+        00 02 05 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 fe 01 00 02";
+    const BUTTONS_VOLUME_WRAP: &str = "01 00 00 00 00 00 00 \
+        ff 03 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_VOLUME_WRAPPED: &str = "01 00 00 00 00 00 00 \
+        02 00 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+
+    // Pads reports: ID, 16 pads of 12-bit pressure with the pad number in the top nibble
+    const PADS_IDLE: &str = "20 00 00 00 10 00 20 00 30 00 40 00 50 00 60 00 70 \
+        00 80 00 90 00 a0 00 b0 00 c0 00 d0 00 e0 00 f0";
+    const PADS_PAD6_HIT: &str = "20 00 00 00 10 00 20 00 30 00 40 00 50 00 6c 00 70 \
+        00 80 00 90 00 a0 00 b0 00 c0 00 d0 00 e0 00 f0";
+
+    #[test]
+    fn first_report_sets_encoder_positions_without_events() {
+        let mut decoder = InputDecoder::new();
+        assert!(decode(&mut decoder, &[BUTTONS_IDLE]).is_empty());
+    }
+
+    #[test]
+    fn button_press_and_release() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_PLAY, BUTTONS_IDLE]);
+        assert_eq!(
+            events,
+            vec!["Button(Play, true, false)", "Button(Play, false, false)"]
+        );
+    }
+
+    #[test]
+    fn shift_modifies_buttons_without_an_event() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_SHIFT_GROUP_A]);
+        assert_eq!(events, vec!["Button(GroupA, true, true)"]);
+        assert!(decoder.shift_pressed);
+    }
+
+    #[test]
+    fn encoders_report_deltas() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_KNOB1_UP_TEMPO_DOWN]);
+        assert_eq!(
+            events,
+            vec!["Encoder(1, 517, 5, false)", "Encoder(9, 510, -2, false)"]
+        );
+    }
+
+    #[test]
+    fn encoder_delta_wraps_around_the_range() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_VOLUME_WRAP, BUTTONS_VOLUME_WRAPPED]);
+        assert_eq!(events, vec!["Encoder(0, 2, 3, false)"]);
+    }
+
+    #[test]
+    fn pad_hit_and_release() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[PADS_IDLE, PADS_PAD6_HIT, PADS_IDLE]);
+        assert_eq!(events, vec!["Pad(6, 192, false)", "Pad(6, 0, false)"]);
+    }
+
+    #[test]
+    fn short_reports_are_rejected() {
+        let mut decoder = InputDecoder::new();
+        let mut context = EventContext::new();
+        assert!(matches!(
+            decoder.decode(&[BUTTONS_REPORT, 0, 0], &mut context),
+            Err(Error::InvalidReport)
+        ));
+        assert!(matches!(
+            decoder.decode(&[PADS_REPORT, 0, 0], &mut context),
+            Err(Error::InvalidReport)
+        ));
+    }
+
+    #[test]
+    fn every_button_has_a_code() {
+        for button in MaschineMk1::BUTTONS {
+            let code = button_to_code(button).unwrap();
+            assert_eq!(as_device_button(code), button);
+        }
+    }
+}
@@ -1,8 +1,13 @@
+use crate::devices::decoder::{decode_pads, step};
+use crate::devices::{Capabilities, DeviceInfo, DisplayInfo, EncoderInfo, LedKind};
+use crate::display::{encode_reports, pack, to_monochrome, DitherMode, PixelLayout, Surface};
 use crate::error::Error;
 use crate::events::{Button, Event, EventContext, EventTask};
+use crate::leds::LedSnapshot;
 use crate::{Color, Device};
 use hidapi::{HidApi, HidDevice};
 use raqote::DrawTarget;
+use std::time::Instant;
 
 const INPUT_BUFFER_SIZE: usize = 512;
 
@@ -148,8 +153,7 @@ const PAD_LED_COUNT: usize = 49;
 pub struct MaschineMk2 {
     device: HidDevice,
     tick_state: u8,
-    displays: [DrawTarget; DISPLAY_COUNT as usize],
-    displays_dirty: [bool; DISPLAY_COUNT as usize],
+    displays: [Surface; DISPLAY_COUNT as usize],
 
     button_leds: [u8; BUTTON_LED_COUNT],
     button_leds_dirty: bool,
@@ -160,9 +164,10 @@ pub struct MaschineMk2 {
     pad_leds: [u8; PAD_LED_COUNT],
     pad_leds_dirty: bool,
 
+    led_snapshot: LedSnapshot,
+
     button_states: [bool; BUTTON_COUNT],
     shift_pressed: bool,
-    pads_data: [u16; PAD_COUNT],
     pads_status: [bool; PAD_COUNT],
     encoder_values: [u16; ENCODER_COUNT],
 }
@@ -171,44 +176,104 @@ impl MaschineMk2 {
     pub const VENDOR_ID: u16 = 0x17cc;
     pub const PRODUCT_ID: u16 = 0x1140;
 
+    /// Buttons on the controller
+    pub const BUTTONS: [Button; 47] = [
+        Button::Display1,
+        Button::Display2,
+        Button::Display3,
+        Button::Display4,
+        Button::Display5,
+        Button::Display6,
+        Button::Display7,
+        Button::Display8,
+        Button::Erase,
+        Button::Rec,
+        Button::Play,
+        Button::Grid,
+        Button::TransportRight,
+        Button::TransportLeft,
+        Button::Restart,
+        Button::MainEncoder,
+        Button::NoteRepeat,
+        Button::Sampling,
+        Button::Browse,
+        Button::All,
+        Button::Autowrite,
+        Button::Volume,
+        Button::Swing,
+        Button::Tempo,
+        Button::Enter,
+        Button::GroupA,
+        Button::GroupB,
+        Button::GroupC,
+        Button::GroupD,
+        Button::GroupE,
+        Button::GroupF,
+        Button::GroupG,
+        Button::GroupH,
+        Button::BrowseRight,
+        Button::BrowseLeft,
+        Button::MainRight,
+        Button::MainLeft,
+        Button::Nav,
+        Button::Control,
+        Button::Step,
+        Button::Mute,
+        Button::Solo,
+        Button::Select,
+        Button::Duplicate,
+        Button::PadMode,
+        Button::Pattern,
+        Button::Scene,
+    ];
+
+    /// Number of pads
+    pub const PAD_COUNT: usize = PAD_COUNT;
+
+    /// Number of encoders, numbered as in `Event::Encoder`
+    pub const ENCODER_COUNT: usize = ENCODER_COUNT;
+
+    ///
+    /// Encode a display image into the HID reports that are written to the device
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    /// - display - Image to encode
+    /// - mode - Conversion applied to produce 1-bit pixels
+    pub fn encode_frame(display_idx: u8, display: &DrawTarget, mode: DitherMode) -> Vec<Vec<u8>> {
+        let width = display.width() as usize;
+        let img_data = pack(
+            &to_monochrome(display.get_data(), width, mode),
+            width,
+            PixelLayout::Horizontal,
+        );
+
+        encode_reports(
+            DISPLAY_ADDR | display_idx,
+            &img_data,
+            width,
+            PixelLayout::Horizontal,
+        )
+    }
+
     /// Send a display frame for the graphics panel
     fn send_frame(&mut self, display_idx: u8) -> Result<(), Error> {
         if display_idx >= DISPLAY_COUNT {
             return Err(Error::InvalidDisplay(display_idx));
         }
-        if self.displays_dirty[display_idx as usize] {
-            let img_data: Vec<u8> = self.displays[display_idx as usize]
-                .get_data()
-                .chunks(8)
-                .map(|data| {
-                    data.iter()
-                        .enumerate()
-                        .map(|(i, d)| (((d & 0x00808080) > 0) as u8) << (7 - i))
-                        .sum()
-                })
-                .collect();
-
-            for chunk in 0..8 {
-                // The number of referenced bytes must be <= 256
-                // Eg Column width * number of rows
-                let mut buffer: Vec<u8> = vec![
-                    DISPLAY_ADDR | display_idx,
-                    0x00,              // Column offset
-                    0x00,              // ?
-                    (chunk * 8) as u8, // Row (a row is 8 pixels high)
-                    0x00,              // ?
-                    0x20,              // Columns per row, 128 is full width
-                    0x00,              // ?
-                    0x08,              // Number of rows
-                    0x00,              // ?
-                ];
-                let x_offset = chunk * 256;
-
-                buffer.extend_from_slice(&img_data[x_offset..(x_offset + 256)]);
-
-                self.device.write(buffer.as_slice())?;
+        let display = &mut self.displays[display_idx as usize];
+        let start = Instant::now();
+        if let Some(img_data) = display.take_frame(start) {
+            let reports = encode_reports(
+                DISPLAY_ADDR | display_idx,
+                &img_data,
+                display.width(),
+                PixelLayout::Horizontal,
+            );
+            for report in reports {
+                self.device.write(report.as_slice())?;
             }
-            self.displays_dirty[display_idx as usize] = false;
+            display.record_transfer(start.elapsed());
         }
 
         Ok(())
@@ -307,19 +372,7 @@ impl MaschineMk2 {
                 ((buffer[7 + 2 * i] as u16) << 8) + buffer[6 + 2 * i] as u16
             };
 
-            if self.encoder_values[i] != value {
-                // TODO: explainer comment
-                let pos_delta =
-                    ((encoder_range + value - self.encoder_values[i]) % encoder_range) as i16;
-                let neg_delta =
-                    ((encoder_range - value + self.encoder_values[i]) % encoder_range) as i16;
-
-                let delta = if pos_delta < neg_delta {
-                    pos_delta
-                } else {
-                    -neg_delta
-                };
-
+            if let Some(delta) = step(Some(self.encoder_values[i]), value, encoder_range) {
                 context.add_event(Event::Encoder(i as u8, value, delta, self.shift_pressed));
                 self.encoder_values[i] = value;
             }
@@ -334,25 +387,7 @@ impl MaschineMk2 {
             return Err(Error::InvalidReport);
         }
 
-        for idx in (0..32).step_by(2) {
-            let low_byte = buffer[idx];
-            let high_byte = buffer[idx + 1];
-            let pad = ((high_byte & 0xF0) >> 4) as usize;
-            let value = (((high_byte & 0x0F) as u16) << 8) | low_byte as u16;
-            let pressed = value > 512;
-
-            self.pads_data[pad] = value;
-            if pressed | self.pads_status[pad] {
-                self.pads_status[pad] = pressed;
-                context.add_event(Event::Pad(
-                    pad as u8,
-                    if pressed { (value >> 4) as u8 } else { 0 },
-                    self.shift_pressed,
-                ));
-            }
-        }
-
-        Ok(())
+        decode_pads(buffer, &mut self.pads_status, self.shift_pressed, context)
     }
 
     /// Set the color of an LED
@@ -538,8 +573,10 @@ impl Device for MaschineMk2 {
         Ok(MaschineMk2 {
             device: hid_api.open(MaschineMk2::VENDOR_ID, MaschineMk2::PRODUCT_ID)?,
             tick_state: 0,
-            displays: [DrawTarget::new(256, 64), DrawTarget::new(256, 64)],
-            displays_dirty: [true; DISPLAY_COUNT as usize],
+            displays: [
+                Surface::new(256, 64, PixelLayout::Horizontal),
+                Surface::new(256, 64, PixelLayout::Horizontal),
+            ],
 
             button_leds: [0; BUTTON_LED_COUNT],
             button_leds_dirty: true,
@@ -550,9 +587,10 @@ impl Device for MaschineMk2 {
             pad_leds: [0; PAD_LED_COUNT],
             pad_leds_dirty: true,
 
+            led_snapshot: LedSnapshot::new(PAD_COUNT),
+
             button_states: [false; BUTTON_COUNT],
             shift_pressed: false,
-            pads_data: [0; PAD_COUNT],
             pads_status: [false; PAD_COUNT],
             encoder_values: [0; ENCODER_COUNT],
         })
@@ -561,22 +599,86 @@ impl Device for MaschineMk2 {
     fn set_button_led(&mut self, button: Button, color: Color) {
         if let Some(led) = self.button_to_led(button) {
             self.set_led(led, color);
+            self.led_snapshot.buttons.insert(button, color.into());
         }
     }
 
     fn set_pad_led(&mut self, pad: u8, color: Color) {
         if let Some(led) = self.pad_to_led(pad) {
             self.set_led(led, color);
+            self.led_snapshot.pads[pad as usize] = color.into();
         }
     }
 
-    fn get_display(&mut self, display_idx: u8) -> Result<&mut DrawTarget, Error> {
-        if display_idx >= DISPLAY_COUNT {
-            Err(Error::InvalidDisplay(display_idx))
-        } else {
-            self.displays_dirty[display_idx as usize] = true;
-            Ok(&mut self.displays[display_idx as usize])
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            displays: self
+                .displays
+                .iter()
+                .map(|display| DisplayInfo {
+                    width: display.width(),
+                    height: display.height(),
+                })
+                .collect(),
+            encoders: (0..ENCODER_COUNT)
+                .map(|i| EncoderInfo {
+                    range: if i == 0 { 0x10 } else { 0x400 },
+                    touch: false,
+                })
+                .collect(),
+            pad_count: PAD_COUNT,
+            pad_leds: Some(LedKind::Rgb),
+            buttons: MaschineMk2::BUTTONS.to_vec(),
+            button_leds: MaschineMk2::BUTTONS
+                .iter()
+                .filter_map(|&button| {
+                    let led = self.button_to_led(button)?;
+                    let kind = if self.is_rgb_led(led) {
+                        LedKind::Rgb
+                    } else {
+                        LedKind::Mono
+                    };
+                    Some((button, kind))
+                })
+                .collect(),
+            ..Capabilities::new(
+                "Maschine Mk2",
+                MaschineMk2::VENDOR_ID,
+                MaschineMk2::PRODUCT_ID,
+            )
+        }
+    }
+
+    fn device_info(&self) -> Result<DeviceInfo, Error> {
+        DeviceInfo::read(&self.device)
+    }
+
+    fn led_snapshot(&self) -> LedSnapshot {
+        self.led_snapshot.clone()
+    }
+
+    fn surfaces(&self) -> &[Surface] {
+        &self.displays
+    }
+
+    fn surfaces_mut(&mut self) -> &mut [Surface] {
+        &mut self.displays
+    }
+
+    fn reconnect(&mut self) -> Result<(), Error> {
+        let hid_api = HidApi::new()?;
+        self.device = hid_api.open(MaschineMk2::VENDOR_ID, MaschineMk2::PRODUCT_ID)?;
+        self.tick_state = 0;
+
+        // Restore the device to the last known state
+        for display in self.displays.iter_mut() {
+            display.invalidate();
         }
+        self.button_leds_dirty = true;
+        self.group_leds_dirty = true;
+        self.pad_leds_dirty = true;
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,758 @@
+use crate::devices::decoder::{step, ReportDecoder};
+use crate::devices::palette::color_index;
+use crate::devices::{Capabilities, DeviceInfo, EncoderInfo, LedKind};
+use crate::error::Error;
+use crate::events::{Button, Event, EventContext, EventTask};
+use crate::leds::{LedColor, LedSnapshot};
+use crate::strip::StripLeds;
+use crate::{Color, Device};
+use hidapi::{HidApi, HidDevice};
+
+const INPUT_BUFFER_SIZE: usize = 512;
+
+// Buttons, bit index in the buttons report. Buttons with a LED use the same index in the button
+// LED report.
+
+pub const BUTTON_CHANNEL: u8 = 0;
+pub const BUTTON_PLUGIN: u8 = 1;
+pub const BUTTON_ARRANGER: u8 = 2;
+pub const BUTTON_MIXER: u8 = 3;
+pub const BUTTON_BROWSE: u8 = 4;
+pub const BUTTON_SAMPLING: u8 = 5;
+pub const BUTTON_BROWSE_LEFT: u8 = 6;
+pub const BUTTON_BROWSE_RIGHT: u8 = 7;
+pub const BUTTON_FILE: u8 = 8;
+pub const BUTTON_SETTINGS: u8 = 9;
+pub const BUTTON_AUTO: u8 = 10;
+pub const BUTTON_MACRO: u8 = 11;
+pub const BUTTON_DISPLAY1: u8 = 12;
+pub const BUTTON_DISPLAY2: u8 = 13;
+pub const BUTTON_DISPLAY3: u8 = 14;
+pub const BUTTON_DISPLAY4: u8 = 15;
+pub const BUTTON_DISPLAY5: u8 = 16;
+pub const BUTTON_DISPLAY6: u8 = 17;
+pub const BUTTON_DISPLAY7: u8 = 18;
+pub const BUTTON_DISPLAY8: u8 = 19;
+pub const BUTTON_VOLUME: u8 = 20;
+pub const BUTTON_SWING: u8 = 21;
+pub const BUTTON_NOTE_REPEAT: u8 = 22;
+pub const BUTTON_TEMPO: u8 = 23;
+pub const BUTTON_LOCK: u8 = 24;
+pub const BUTTON_PITCH: u8 = 25;
+pub const BUTTON_MOD: u8 = 26;
+pub const BUTTON_PERFORM: u8 = 27;
+pub const BUTTON_NOTES: u8 = 28;
+pub const BUTTON_RESTART: u8 = 29;
+pub const BUTTON_ERASE: u8 = 30;
+pub const BUTTON_TAP: u8 = 31;
+pub const BUTTON_FOLLOW: u8 = 32;
+pub const BUTTON_PLAY: u8 = 33;
+pub const BUTTON_REC: u8 = 34;
+pub const BUTTON_STOP: u8 = 35;
+pub const BUTTON_SHIFT: u8 = 36;
+pub const BUTTON_FIXED_VELOCITY: u8 = 37;
+pub const BUTTON_PAD_MODE: u8 = 38;
+pub const BUTTON_KEYBOARD: u8 = 39;
+pub const BUTTON_CHORDS: u8 = 40;
+pub const BUTTON_STEP: u8 = 41;
+pub const BUTTON_SCENE: u8 = 42;
+pub const BUTTON_PATTERN: u8 = 43;
+pub const BUTTON_EVENTS: u8 = 44;
+pub const BUTTON_VARIATION: u8 = 45;
+pub const BUTTON_DUPLICATE: u8 = 46;
+pub const BUTTON_SELECT: u8 = 47;
+pub const BUTTON_SOLO: u8 = 48;
+pub const BUTTON_MUTE: u8 = 49;
+pub const BUTTON_GROUP_A: u8 = 50;
+pub const BUTTON_GROUP_B: u8 = 51;
+pub const BUTTON_GROUP_C: u8 = 52;
+pub const BUTTON_GROUP_D: u8 = 53;
+pub const BUTTON_GROUP_E: u8 = 54;
+pub const BUTTON_GROUP_F: u8 = 55;
+pub const BUTTON_GROUP_G: u8 = 56;
+pub const BUTTON_GROUP_H: u8 = 57;
+pub const BUTTON_MAIN_ENCODER: u8 = 58;
+pub const BUTTON_ENCODER_UP: u8 = 59;
+pub const BUTTON_ENCODER_RIGHT: u8 = 60;
+pub const BUTTON_ENCODER_DOWN: u8 = 61;
+pub const BUTTON_ENCODER_LEFT: u8 = 62;
+
+const BUTTON_COUNT: usize = 63;
+const PAD_COUNT: usize = 16;
+
+// Encoder 0 is the 4-D encoder, 1-8 are the knobs below the displays
+const ENCODER_COUNT: usize = 9;
+const MAIN_ENCODER_RANGE: u16 = 0x10;
+const ENCODER_RANGE: u16 = 0x400;
+
+const PAD_THRESHOLD: u16 = 0x100;
+const STRIP_LED_COUNT: usize = 25;
+
+// Buttons report: button bits, 4-D encoder position, 8 encoders as 16 bit values, then the touch
+// strip as a touch count followed by 16 bit position and pressure. The bit above the 4-D encoder
+// position and the top bit of each encoder value are set while the encoder is touched.
+const BUTTONS_REPORT: u8 = 0x01;
+const ENCODERS_OFFSET: usize = 8;
+const MAIN_ENCODER_TOUCH: u16 = 0x10;
+const ENCODER_TOUCH: u16 = 0x8000;
+const STRIP_OFFSET: usize = ENCODERS_OFFSET + 1 + 2 * (ENCODER_COUNT - 1);
+const BUTTONS_REPORT_SIZE: usize = STRIP_OFFSET + 5;
+
+// Pads report: 16 bit pressure of each pad in pad order
+const PADS_REPORT: u8 = 0x02;
+const PADS_REPORT_SIZE: usize = 2 * PAD_COUNT;
+
+// LED reports: indexed colour or brightness of each button LED, then pads followed by the strip
+const BUTTON_LED_ADDR: u8 = 0x80;
+const PAD_LED_ADDR: u8 = 0x81;
+
+///
+/// Input state of a Mk3, decoded from its HID reports
+///
+struct InputDecoder {
+    button_states: [bool; BUTTON_COUNT],
+    shift_pressed: bool,
+    pads_velocity: [u8; PAD_COUNT],
+    pads_status: [bool; PAD_COUNT],
+    encoder_values: [Option<u16>; ENCODER_COUNT],
+    encoders_touched: [bool; ENCODER_COUNT],
+    strip: (u8, u16, u16),
+}
+
+impl InputDecoder {
+    fn new() -> Self {
+        Self {
+            button_states: [false; BUTTON_COUNT],
+            shift_pressed: false,
+            pads_velocity: [0; PAD_COUNT],
+            pads_status: [false; PAD_COUNT],
+            encoder_values: [None; ENCODER_COUNT],
+            encoders_touched: [false; ENCODER_COUNT],
+            strip: (0, 0, 0),
+        }
+    }
+
+    /// Process a buttons report message
+    fn process_buttons(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        if buffer.len() < BUTTONS_REPORT_SIZE {
+            return Err(Error::InvalidReport);
+        }
+
+        let is_pressed = |btn: u8| (buffer[(btn >> 3) as usize] & (1 << (btn % 8))) != 0;
+
+        // Apply shift first so a button pressed together with it is reported as shifted
+        self.shift_pressed = is_pressed(BUTTON_SHIFT);
+        self.button_states[BUTTON_SHIFT as usize] = self.shift_pressed;
+
+        for btn in BUTTON_CHANNEL..=BUTTON_ENCODER_LEFT {
+            let button_pressed = is_pressed(btn);
+            if btn != BUTTON_SHIFT && button_pressed != self.button_states[btn as usize] {
+                self.button_states[btn as usize] = button_pressed;
+
+                let button = as_device_button(btn);
+                context.add_event(Event::Button(button, button_pressed, self.shift_pressed));
+            }
+        }
+
+        for i in 0..ENCODER_COUNT {
+            let (value, range, touched) = if i == 0 {
+                let value = buffer[ENCODERS_OFFSET] as u16;
+                (
+                    value & 0x0F,
+                    MAIN_ENCODER_RANGE,
+                    value & MAIN_ENCODER_TOUCH != 0,
+                )
+            } else {
+                let offset = ENCODERS_OFFSET + 1 + 2 * (i - 1);
+                let value = u16::from_le_bytes([buffer[offset], buffer[offset + 1]]);
+                (
+                    value % ENCODER_RANGE,
+                    ENCODER_RANGE,
+                    value & ENCODER_TOUCH != 0,
+                )
+            };
+            if touched != self.encoders_touched[i] {
+                self.encoders_touched[i] = touched;
+                context.add_event(Event::EncoderTouch(i as u8, touched, self.shift_pressed));
+            }
+            self.process_encoder(i, value, range, context);
+        }
+
+        let strip = (
+            buffer[STRIP_OFFSET],
+            u16::from_le_bytes([buffer[STRIP_OFFSET + 1], buffer[STRIP_OFFSET + 2]]) & 0x3FF,
+            u16::from_le_bytes([buffer[STRIP_OFFSET + 3], buffer[STRIP_OFFSET + 4]]) & 0x3FF,
+        );
+        if strip != self.strip {
+            self.strip = strip;
+            let (touches, position, pressure) = strip;
+            context.add_event(Event::Strip(
+                0,
+                position,
+                pressure,
+                touches,
+                self.shift_pressed,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Compare an encoder position with the last one, the first only sets the starting position
+    fn process_encoder(&mut self, idx: usize, value: u16, range: u16, context: &mut EventContext) {
+        if let Some(delta) = step(self.encoder_values[idx], value, range) {
+            context.add_event(Event::Encoder(idx as u8, value, delta, self.shift_pressed));
+        }
+        self.encoder_values[idx] = Some(value);
+    }
+
+    /// Process a pads report message
+    ///
+    /// Pressure changes of a held pad are reported as further pad events.
+    fn process_pads(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        if buffer.len() < PADS_REPORT_SIZE {
+            return Err(Error::InvalidReport);
+        }
+
+        for pad in 0..PAD_COUNT {
+            let value = u16::from_le_bytes([buffer[2 * pad], buffer[2 * pad + 1]]) & 0x0FFF;
+            let pressed = value > PAD_THRESHOLD;
+            let velocity = if pressed { (value >> 4) as u8 } else { 0 };
+
+            if (pressed | self.pads_status[pad]) && velocity != self.pads_velocity[pad] {
+                self.pads_status[pad] = pressed;
+                self.pads_velocity[pad] = velocity;
+                context.add_event(Event::Pad(pad as u8, velocity, self.shift_pressed));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ReportDecoder for InputDecoder {
+    fn decode(&mut self, report: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        match report.split_first() {
+            Some((&BUTTONS_REPORT, buffer)) => self.process_buttons(buffer, context),
+            Some((&PADS_REPORT, buffer)) => self.process_pads(buffer, context),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Convert a button code into a button enum
+fn as_device_button(button: u8) -> Button {
+    match button {
+        BUTTON_CHANNEL => Button::Channel,
+        BUTTON_PLUGIN => Button::Plugin,
+        BUTTON_ARRANGER => Button::Arranger,
+        BUTTON_MIXER => Button::Mixer,
+        BUTTON_BROWSE => Button::Browse,
+        BUTTON_SAMPLING => Button::Sampling,
+        BUTTON_BROWSE_LEFT => Button::BrowseLeft,
+        BUTTON_BROWSE_RIGHT => Button::BrowseRight,
+        BUTTON_FILE => Button::File,
+        BUTTON_SETTINGS => Button::Settings,
+        BUTTON_AUTO => Button::Autowrite,
+        BUTTON_MACRO => Button::Macro,
+        BUTTON_DISPLAY1 => Button::Display1,
+        BUTTON_DISPLAY2 => Button::Display2,
+        BUTTON_DISPLAY3 => Button::Display3,
+        BUTTON_DISPLAY4 => Button::Display4,
+        BUTTON_DISPLAY5 => Button::Display5,
+        BUTTON_DISPLAY6 => Button::Display6,
+        BUTTON_DISPLAY7 => Button::Display7,
+        BUTTON_DISPLAY8 => Button::Display8,
+        BUTTON_VOLUME => Button::Volume,
+        BUTTON_SWING => Button::Swing,
+        BUTTON_NOTE_REPEAT => Button::NoteRepeat,
+        BUTTON_TEMPO => Button::Tempo,
+        BUTTON_LOCK => Button::Lock,
+        BUTTON_PITCH => Button::Pitch,
+        BUTTON_MOD => Button::Mod,
+        BUTTON_PERFORM => Button::Perform,
+        BUTTON_NOTES => Button::Notes,
+        BUTTON_RESTART => Button::Restart,
+        BUTTON_ERASE => Button::Erase,
+        BUTTON_TAP => Button::Tap,
+        BUTTON_FOLLOW => Button::Follow,
+        BUTTON_PLAY => Button::Play,
+        BUTTON_REC => Button::Rec,
+        BUTTON_STOP => Button::Stop,
+        BUTTON_FIXED_VELOCITY => Button::FixedVelocity,
+        BUTTON_PAD_MODE => Button::PadMode,
+        BUTTON_KEYBOARD => Button::Keyboard,
+        BUTTON_CHORDS => Button::Chords,
+        BUTTON_STEP => Button::Step,
+        BUTTON_SCENE => Button::Scene,
+        BUTTON_PATTERN => Button::Pattern,
+        BUTTON_EVENTS => Button::Events,
+        BUTTON_VARIATION => Button::Variation,
+        BUTTON_DUPLICATE => Button::Duplicate,
+        BUTTON_SELECT => Button::Select,
+        BUTTON_SOLO => Button::Solo,
+        BUTTON_MUTE => Button::Mute,
+        BUTTON_GROUP_A => Button::GroupA,
+        BUTTON_GROUP_B => Button::GroupB,
+        BUTTON_GROUP_C => Button::GroupC,
+        BUTTON_GROUP_D => Button::GroupD,
+        BUTTON_GROUP_E => Button::GroupE,
+        BUTTON_GROUP_F => Button::GroupF,
+        BUTTON_GROUP_G => Button::GroupG,
+        BUTTON_GROUP_H => Button::GroupH,
+        BUTTON_MAIN_ENCODER => Button::MainEncoder,
+        BUTTON_ENCODER_UP => Button::EncoderUp,
+        BUTTON_ENCODER_RIGHT => Button::EncoderRight,
+        BUTTON_ENCODER_DOWN => Button::EncoderDown,
+        BUTTON_ENCODER_LEFT => Button::EncoderLeft,
+        _ => Button::Unknown,
+    }
+}
+
+/// Convert a button into a LED index, the 4-D encoder has no LEDs
+fn button_to_led(button: Button) -> Option<u8> {
+    (BUTTON_CHANNEL..BUTTON_MAIN_ENCODER)
+        .find(|&code| code != BUTTON_SHIFT && as_device_button(code) == button)
+}
+
+/// Determine if a button LED is RGB or Mono
+fn is_rgb_led(led: u8) -> bool {
+    (BUTTON_GROUP_A..=BUTTON_GROUP_H).contains(&led)
+}
+
+///
+/// Maschine Mk3 Controller
+///
+/// Requires a valid HID device. Buttons, encoders, the touch strip, pads and LEDs are supported,
+/// the colour displays are not driven over HID and are not available.
+///
+/// The report layouts have not been verified against captured USB traffic yet, the test fixtures
+/// are written from the same layout as the decoder and only guard against regressions. This
+/// includes the encoder touch bits behind `Event::EncoderTouch`, so `get_device` does not open
+/// this driver.
+///
+pub struct MaschineMk3 {
+    device: HidDevice,
+    tick_state: u8,
+
+    button_leds: [u8; BUTTON_COUNT],
+    button_leds_dirty: bool,
+
+    pad_leds: [u8; PAD_COUNT + STRIP_LED_COUNT],
+    pad_leds_dirty: bool,
+
+    led_snapshot: LedSnapshot,
+
+    input: InputDecoder,
+}
+
+impl MaschineMk3 {
+    pub const VENDOR_ID: u16 = 0x17cc;
+    pub const PRODUCT_ID: u16 = 0x1600;
+
+    /// Buttons on the controller
+    pub const BUTTONS: [Button; 62] = [
+        Button::Channel,
+        Button::Plugin,
+        Button::Arranger,
+        Button::Mixer,
+        Button::Browse,
+        Button::Sampling,
+        Button::BrowseLeft,
+        Button::BrowseRight,
+        Button::File,
+        Button::Settings,
+        Button::Autowrite,
+        Button::Macro,
+        Button::Display1,
+        Button::Display2,
+        Button::Display3,
+        Button::Display4,
+        Button::Display5,
+        Button::Display6,
+        Button::Display7,
+        Button::Display8,
+        Button::Volume,
+        Button::Swing,
+        Button::NoteRepeat,
+        Button::Tempo,
+        Button::Lock,
+        Button::Pitch,
+        Button::Mod,
+        Button::Perform,
+        Button::Notes,
+        Button::Restart,
+        Button::Erase,
+        Button::Tap,
+        Button::Follow,
+        Button::Play,
+        Button::Rec,
+        Button::Stop,
+        Button::FixedVelocity,
+        Button::PadMode,
+        Button::Keyboard,
+        Button::Chords,
+        Button::Step,
+        Button::Scene,
+        Button::Pattern,
+        Button::Events,
+        Button::Variation,
+        Button::Duplicate,
+        Button::Select,
+        Button::Solo,
+        Button::Mute,
+        Button::GroupA,
+        Button::GroupB,
+        Button::GroupC,
+        Button::GroupD,
+        Button::GroupE,
+        Button::GroupF,
+        Button::GroupG,
+        Button::GroupH,
+        Button::MainEncoder,
+        Button::EncoderUp,
+        Button::EncoderRight,
+        Button::EncoderDown,
+        Button::EncoderLeft,
+    ];
+
+    /// Number of pads
+    pub const PAD_COUNT: usize = PAD_COUNT;
+
+    /// Number of encoders, numbered as in `Event::Encoder`
+    pub const ENCODER_COUNT: usize = ENCODER_COUNT;
+
+    /// Update LEDs if the arrays have been updated
+    fn send_leds(&mut self) -> Result<(), Error> {
+        if self.button_leds_dirty {
+            let mut buffer: Vec<u8> = vec![BUTTON_LED_ADDR];
+            buffer.extend_from_slice(&self.button_leds);
+            self.device.write(buffer.as_slice())?;
+            self.button_leds_dirty = false;
+        }
+        if self.pad_leds_dirty {
+            let mut buffer: Vec<u8> = vec![PAD_LED_ADDR];
+            buffer.extend_from_slice(&self.pad_leds);
+            self.device.write(buffer.as_slice())?;
+            self.pad_leds_dirty = false;
+        }
+
+        Ok(())
+    }
+
+    /// Read incoming reports from the device
+    fn read(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        let mut buffer = [0u8; INPUT_BUFFER_SIZE];
+
+        for _ in 0..32 {
+            let bytes_read = self.device.read(&mut buffer)?;
+            let shift_pressed = self.input.shift_pressed;
+            self.input.decode(&buffer[..bytes_read], context)?;
+
+            // Shift has no event, show it is held on its LED instead
+            if self.input.shift_pressed != shift_pressed {
+                self.button_leds[BUTTON_SHIFT as usize] =
+                    if self.input.shift_pressed { 0xFF } else { 0x00 };
+                self.button_leds_dirty = true;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Device for MaschineMk3 {
+    fn new() -> Result<Self, Error> {
+        let hid_api = HidApi::new()?;
+        Ok(MaschineMk3 {
+            device: hid_api.open(MaschineMk3::VENDOR_ID, MaschineMk3::PRODUCT_ID)?,
+            tick_state: 0,
+
+            button_leds: [0; BUTTON_COUNT],
+            button_leds_dirty: true,
+
+            pad_leds: [0; PAD_COUNT + STRIP_LED_COUNT],
+            pad_leds_dirty: true,
+
+            led_snapshot: LedSnapshot::new(PAD_COUNT),
+
+            input: InputDecoder::new(),
+        })
+    }
+
+    fn set_button_led(&mut self, button: Button, color: Color) {
+        if let Some(led) = button_to_led(button) {
+            let led_color = LedColor::from(color);
+            self.button_leds[led as usize] = if is_rgb_led(led) {
+                color_index(led_color)
+            } else {
+                led_color.brightness()
+            };
+            self.button_leds_dirty = true;
+            self.led_snapshot.buttons.insert(button, led_color);
+        }
+    }
+
+    fn set_pad_led(&mut self, pad: u8, color: Color) {
+        if (pad as usize) < PAD_COUNT {
+            self.pad_leds[pad as usize] = color_index(color.into());
+            self.pad_leds_dirty = true;
+            self.led_snapshot.pads[pad as usize] = color.into();
+        }
+    }
+
+    fn set_strip_leds(&mut self, strip: u8, leds: StripLeds, color: Color) {
+        if strip == 0 {
+            leds.write(&mut self.pad_leds[PAD_COUNT..], color_index(color.into()));
+            self.pad_leds_dirty = true;
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let mut encoders = vec![
+            EncoderInfo {
+                range: ENCODER_RANGE,
+                touch: true,
+            };
+            ENCODER_COUNT
+        ];
+        encoders[0].range = MAIN_ENCODER_RANGE;
+
+        Capabilities {
+            encoders,
+            pad_count: PAD_COUNT,
+            strip_count: 1,
+            strip_leds: STRIP_LED_COUNT,
+            pad_leds: Some(LedKind::Rgb),
+            buttons: MaschineMk3::BUTTONS.to_vec(),
+            button_leds: MaschineMk3::BUTTONS
+                .iter()
+                .filter_map(|&button| {
+                    let led = button_to_led(button)?;
+                    let kind = if is_rgb_led(led) {
+                        LedKind::Rgb
+                    } else {
+                        LedKind::Mono
+                    };
+                    Some((button, kind))
+                })
+                .collect(),
+            ..Capabilities::new(
+                "Maschine Mk3",
+                MaschineMk3::VENDOR_ID,
+                MaschineMk3::PRODUCT_ID,
+            )
+        }
+    }
+
+    fn device_info(&self) -> Result<DeviceInfo, Error> {
+        DeviceInfo::read(&self.device)
+    }
+
+    fn led_snapshot(&self) -> LedSnapshot {
+        self.led_snapshot.clone()
+    }
+
+    fn reconnect(&mut self) -> Result<(), Error> {
+        let hid_api = HidApi::new()?;
+        self.device = hid_api.open(MaschineMk3::VENDOR_ID, MaschineMk3::PRODUCT_ID)?;
+        self.tick_state = 0;
+
+        // Restore the device to the last known state
+        self.button_leds_dirty = true;
+        self.pad_leds_dirty = true;
+
+        Ok(())
+    }
+}
+
+impl EventTask for MaschineMk3 {
+    fn tick(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        if self.tick_state == 0 {
+            self.send_leds()?;
+        } else if self.tick_state == 1 {
+            self.read(context)?;
+        }
+
+        self.tick_state = (self.tick_state + 1) % 2;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::decoder::fixtures::decode;
+
+    // Hand-built from the layout the decoder expects, not captured from hardware. Replace with
+    // USB captures once available.
+
+    // Buttons reports: ID, 8 bytes of button bits, 4-D encoder, 8 encoders, touch strip
+    const BUTTONS_IDLE: &str = "01 00 00 00 00 00 00 00 00 03 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 00 00 00 00";
+    const BUTTONS_STOP: &str = "01 00 00 00 00 08 00 00 00 03 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 00 00 00 00";
+    const BUTTONS_SHIFT_GROUP_H: &str = "01 00 00 00 00 10 00 00 02 03 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 00 00 00 00";
+    const BUTTONS_ENCODER_PUSH_TILT_UP: &str = "01 00 00 00 00 00 00 00 0c 03 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 00 00 00 00";
+    const BUTTONS_MAIN_DOWN_KNOB8_UP: &str = "01 00 00 00 00 00 00 00 00 02 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 0a 02 00 00 00 00 00";
+    const BUTTONS_MAIN_KNOB1_TOUCH: &str = "01 00 00 00 00 00 00 00 00 13 \
+        00 82 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 00 00 00 00";
+    const BUTTONS_KNOB1_TOUCH_TURN: &str = "01 00 00 00 00 00 00 00 00 03 \
+        04 82 00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 00 00 00 00";
+    const BUTTONS_STRIP_TOUCH: &str = "01 00 00 00 00 00 00 00 00 03 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 01 00 02 80 00";
+    const BUTTONS_STRIP_TWO_FINGERS: &str = "01 00 00 00 00 00 00 00 00 03 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02 02 20 03 c0 00";
+
+    // Pads reports: ID, 16 bit pressure of each pad in pad order
+    const PADS_IDLE: &str = "02 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 \
+        00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00";
+    const PADS_PAD3_SOFT: &str = "02 00 00 00 00 00 00 00 02 00 00 00 00 00 00 00 00 \
+        00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00";
+    const PADS_PAD3_HARD: &str = "02 00 00 00 00 00 00 00 0f 00 00 00 00 00 00 00 00 \
+        00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00";
+
+    #[test]
+    fn first_report_sets_encoder_positions_without_events() {
+        let mut decoder = InputDecoder::new();
+        assert!(decode(&mut decoder, &[BUTTONS_IDLE]).is_empty());
+    }
+
+    #[test]
+    fn button_press_and_release() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_STOP, BUTTONS_IDLE]);
+        assert_eq!(
+            events,
+            vec!["Button(Stop, true, false)", "Button(Stop, false, false)"]
+        );
+    }
+
+    #[test]
+    fn shift_modifies_buttons_without_an_event() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_SHIFT_GROUP_H]);
+        assert_eq!(events, vec!["Button(GroupH, true, true)"]);
+    }
+
+    #[test]
+    fn four_d_encoder_push_and_tilt() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_ENCODER_PUSH_TILT_UP]);
+        assert_eq!(
+            events,
+            vec![
+                "Button(MainEncoder, true, false)",
+                "Button(EncoderUp, true, false)"
+            ]
+        );
+    }
+
+    #[test]
+    fn encoders_report_deltas() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_MAIN_DOWN_KNOB8_UP]);
+        assert_eq!(
+            events,
+            vec!["Encoder(0, 2, -1, false)", "Encoder(8, 522, 10, false)"]
+        );
+    }
+
+    #[test]
+    fn encoder_touch_and_release() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(
+            &mut decoder,
+            &[
+                BUTTONS_IDLE,
+                BUTTONS_MAIN_KNOB1_TOUCH,
+                BUTTONS_KNOB1_TOUCH_TURN,
+                BUTTONS_IDLE,
+            ],
+        );
+        assert_eq!(
+            events,
+            vec![
+                "EncoderTouch(0, true, false)",
+                "EncoderTouch(1, true, false)",
+                "EncoderTouch(0, false, false)",
+                "Encoder(1, 516, 4, false)",
+                "EncoderTouch(1, false, false)",
+                "Encoder(1, 512, -4, false)"
+            ]
+        );
+    }
+
+    #[test]
+    fn strip_touch_and_release() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(
+            &mut decoder,
+            &[
+                BUTTONS_IDLE,
+                BUTTONS_STRIP_TOUCH,
+                BUTTONS_STRIP_TWO_FINGERS,
+                BUTTONS_IDLE,
+            ],
+        );
+        assert_eq!(
+            events,
+            vec![
+                "Strip(0, 512, 128, 1, false)",
+                "Strip(0, 800, 192, 2, false)",
+                "Strip(0, 0, 0, 0, false)"
+            ]
+        );
+    }
+
+    #[test]
+    fn pad_pressure_changes_and_release() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(
+            &mut decoder,
+            &[
+                PADS_IDLE,
+                PADS_PAD3_SOFT,
+                PADS_PAD3_SOFT,
+                PADS_PAD3_HARD,
+                PADS_IDLE,
+            ],
+        );
+        assert_eq!(
+            events,
+            vec![
+                "Pad(3, 32, false)",
+                "Pad(3, 240, false)",
+                "Pad(3, 0, false)"
+            ]
+        );
+    }
+
+    #[test]
+    fn short_reports_are_rejected() {
+        let mut decoder = InputDecoder::new();
+        let mut context = EventContext::new();
+        assert!(matches!(
+            decoder.decode(&[BUTTONS_REPORT, 0, 0], &mut context),
+            Err(Error::InvalidReport)
+        ));
+        assert!(matches!(
+            decoder.decode(&[PADS_REPORT, 0, 0], &mut context),
+            Err(Error::InvalidReport)
+        ));
+    }
+
+    #[test]
+    fn colours_map_to_palette_indexes() {
+        assert_eq!(color_index(LedColor::OFF), 0);
+        assert_eq!(color_index(LedColor::new(0xFF, 0x00, 0x00)), 7);
+        assert_eq!(color_index(LedColor::new(0x40, 0x00, 0x00)), 5);
+        assert_eq!(color_index(LedColor::new(0x00, 0xFF, 0x00)), 4 + 5 * 4 + 3);
+        assert_eq!(color_index(LedColor::new(0x00, 0x00, 0xFF)), 4 + 11 * 4 + 3);
+        assert_eq!(color_index(LedColor::new(0xFF, 0xFF, 0xFF)), 71);
+    }
+}
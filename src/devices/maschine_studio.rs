@@ -0,0 +1,702 @@
+use crate::devices::decoder::{decode_pads, step, ReportDecoder};
+use crate::devices::{Capabilities, DeviceInfo, EncoderInfo, LedKind, MeterInfo};
+use crate::error::Error;
+use crate::events::{Button, Event, EventContext, EventTask};
+use crate::leds::{set_meter_leds, LedColor, LedSnapshot};
+use crate::{Color, Device};
+use hidapi::{HidApi, HidDevice};
+
+const INPUT_BUFFER_SIZE: usize = 512;
+
+// Buttons, bit index in the buttons report. Buttons with a mono LED use the same index in the
+// button LED report.
+
+pub const BUTTON_DISPLAY1: u8 = 0;
+pub const BUTTON_DISPLAY2: u8 = 1;
+pub const BUTTON_DISPLAY3: u8 = 2;
+pub const BUTTON_DISPLAY4: u8 = 3;
+pub const BUTTON_DISPLAY5: u8 = 4;
+pub const BUTTON_DISPLAY6: u8 = 5;
+pub const BUTTON_DISPLAY7: u8 = 6;
+pub const BUTTON_DISPLAY8: u8 = 7;
+pub const BUTTON_CONTROL: u8 = 8;
+pub const BUTTON_STEP: u8 = 9;
+pub const BUTTON_BROWSE: u8 = 10;
+pub const BUTTON_SAMPLING: u8 = 11;
+pub const BUTTON_BROWSE_LEFT: u8 = 12;
+pub const BUTTON_BROWSE_RIGHT: u8 = 13;
+pub const BUTTON_ALL: u8 = 14;
+pub const BUTTON_AUTO: u8 = 15;
+pub const BUTTON_VOLUME: u8 = 16;
+pub const BUTTON_SWING: u8 = 17;
+pub const BUTTON_TEMPO: u8 = 18;
+pub const BUTTON_NOTE_REPEAT: u8 = 19;
+pub const BUTTON_MAIN_ENCODER: u8 = 20;
+pub const BUTTON_BACK: u8 = 21;
+pub const BUTTON_ENTER: u8 = 22;
+pub const BUTTON_METRO: u8 = 23;
+pub const BUTTON_GROUP_A: u8 = 24;
+pub const BUTTON_GROUP_B: u8 = 25;
+pub const BUTTON_GROUP_C: u8 = 26;
+pub const BUTTON_GROUP_D: u8 = 27;
+pub const BUTTON_GROUP_E: u8 = 28;
+pub const BUTTON_GROUP_F: u8 = 29;
+pub const BUTTON_GROUP_G: u8 = 30;
+pub const BUTTON_GROUP_H: u8 = 31;
+pub const BUTTON_RESTART: u8 = 32;
+pub const BUTTON_TRANSPORT_LEFT: u8 = 33;
+pub const BUTTON_TRANSPORT_RIGHT: u8 = 34;
+pub const BUTTON_GRID: u8 = 35;
+pub const BUTTON_PLAY: u8 = 36;
+pub const BUTTON_REC: u8 = 37;
+pub const BUTTON_ERASE: u8 = 38;
+pub const BUTTON_SHIFT: u8 = 39;
+pub const BUTTON_SCENE: u8 = 40;
+pub const BUTTON_PATTERN: u8 = 41;
+pub const BUTTON_PAD_MODE: u8 = 42;
+pub const BUTTON_NAVIGATE: u8 = 43;
+pub const BUTTON_DUPLICATE: u8 = 44;
+pub const BUTTON_SELECT: u8 = 45;
+pub const BUTTON_SOLO: u8 = 46;
+pub const BUTTON_MUTE: u8 = 47;
+pub const BUTTON_CHANNEL: u8 = 48;
+pub const BUTTON_PLUGIN: u8 = 49;
+pub const BUTTON_COPY: u8 = 50;
+pub const BUTTON_PASTE: u8 = 51;
+pub const BUTTON_NOTE: u8 = 52;
+pub const BUTTON_NUDGE: u8 = 53;
+pub const BUTTON_UNDO: u8 = 54;
+pub const BUTTON_REDO: u8 = 55;
+pub const BUTTON_QUANTIZE: u8 = 56;
+pub const BUTTON_CLEAR: u8 = 57;
+pub const BUTTON_CLEAR_AUTO: u8 = 58;
+pub const BUTTON_MASTER: u8 = 59;
+pub const BUTTON_CUE: u8 = 60;
+pub const BUTTON_INPUT: u8 = 61;
+
+const BUTTON_COUNT: usize = 62;
+const PAD_COUNT: usize = 16;
+
+// Encoder 0 is the main encoder, 1-8 are the knobs below the displays
+const ENCODER_COUNT: usize = 9;
+const MAIN_ENCODER_RANGE: u16 = 0x10;
+const ENCODER_RANGE: u16 = 0x400;
+const JOG_RANGE: u16 = 0x100;
+
+const METER_COUNT: usize = 2;
+const METER_SEGMENTS: usize = 16;
+
+// Buttons report: button bits, main encoder position, jog wheel position, then 8 encoders as
+// 16 bit values
+const BUTTONS_REPORT: u8 = 0x01;
+const MAIN_ENCODER_OFFSET: usize = 8;
+const JOG_OFFSET: usize = 9;
+const ENCODERS_OFFSET: usize = 10;
+const BUTTONS_REPORT_SIZE: usize = ENCODERS_OFFSET + 2 * (ENCODER_COUNT - 1);
+
+// Pads report: 12 bit pressure per pad, little endian with the pad number in the top 4 bits
+const PADS_REPORT: u8 = 0x20;
+
+// LED reports: brightness of each mono button LED, RGB of the groups followed by the pads, then
+// the brightness of each meter segment
+const BUTTON_LED_ADDR: u8 = 0x80;
+const RGB_LED_ADDR: u8 = 0x81;
+const METER_LED_ADDR: u8 = 0x82;
+const RGB_LED_COUNT: usize = 8 + PAD_COUNT;
+
+///
+/// Input state of a Studio, decoded from its HID reports
+///
+struct InputDecoder {
+    button_states: [bool; BUTTON_COUNT],
+    shift_pressed: bool,
+    pads_status: [bool; PAD_COUNT],
+    encoder_values: [Option<u16>; ENCODER_COUNT],
+    jog_value: Option<u16>,
+}
+
+impl InputDecoder {
+    fn new() -> Self {
+        Self {
+            button_states: [false; BUTTON_COUNT],
+            shift_pressed: false,
+            pads_status: [false; PAD_COUNT],
+            encoder_values: [None; ENCODER_COUNT],
+            jog_value: None,
+        }
+    }
+
+    /// Process a buttons report message
+    fn process_buttons(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        if buffer.len() < BUTTONS_REPORT_SIZE {
+            return Err(Error::InvalidReport);
+        }
+
+        let is_pressed = |btn: u8| (buffer[(btn >> 3) as usize] & (1 << (btn % 8))) != 0;
+
+        // Apply shift first so a button pressed together with it is reported as shifted
+        self.shift_pressed = is_pressed(BUTTON_SHIFT);
+        self.button_states[BUTTON_SHIFT as usize] = self.shift_pressed;
+
+        for btn in BUTTON_DISPLAY1..=BUTTON_INPUT {
+            let button_pressed = is_pressed(btn);
+            if btn != BUTTON_SHIFT && button_pressed != self.button_states[btn as usize] {
+                self.button_states[btn as usize] = button_pressed;
+
+                let button = as_device_button(btn);
+                context.add_event(Event::Button(button, button_pressed, self.shift_pressed));
+            }
+        }
+
+        for i in 0..ENCODER_COUNT {
+            let (value, range) = if i == 0 {
+                let value = (buffer[MAIN_ENCODER_OFFSET] & 0x0F) as u16;
+                (value, MAIN_ENCODER_RANGE)
+            } else {
+                let offset = ENCODERS_OFFSET + 2 * (i - 1);
+                let value = u16::from_le_bytes([buffer[offset], buffer[offset + 1]]);
+                (value % ENCODER_RANGE, ENCODER_RANGE)
+            };
+
+            if let Some(delta) = step(self.encoder_values[i], value, range) {
+                context.add_event(Event::Encoder(i as u8, value, delta, self.shift_pressed));
+            }
+            self.encoder_values[i] = Some(value);
+        }
+
+        let jog_value = buffer[JOG_OFFSET] as u16;
+        if let Some(delta) = step(self.jog_value, jog_value, JOG_RANGE) {
+            context.add_event(Event::Jog(delta, self.shift_pressed));
+        }
+        self.jog_value = Some(jog_value);
+
+        Ok(())
+    }
+
+    /// Process a pads report message
+    fn process_pads(&mut self, buffer: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        decode_pads(buffer, &mut self.pads_status, self.shift_pressed, context)
+    }
+}
+
+impl ReportDecoder for InputDecoder {
+    fn decode(&mut self, report: &[u8], context: &mut EventContext) -> Result<(), Error> {
+        match report.split_first() {
+            Some((&BUTTONS_REPORT, buffer)) => self.process_buttons(buffer, context),
+            Some((&PADS_REPORT, buffer)) => self.process_pads(buffer, context),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Convert a button code into a button enum
+fn as_device_button(button: u8) -> Button {
+    match button {
+        BUTTON_DISPLAY1 => Button::Display1,
+        BUTTON_DISPLAY2 => Button::Display2,
+        BUTTON_DISPLAY3 => Button::Display3,
+        BUTTON_DISPLAY4 => Button::Display4,
+        BUTTON_DISPLAY5 => Button::Display5,
+        BUTTON_DISPLAY6 => Button::Display6,
+        BUTTON_DISPLAY7 => Button::Display7,
+        BUTTON_DISPLAY8 => Button::Display8,
+        BUTTON_CONTROL => Button::Control,
+        BUTTON_STEP => Button::Step,
+        BUTTON_BROWSE => Button::Browse,
+        BUTTON_SAMPLING => Button::Sampling,
+        BUTTON_BROWSE_LEFT => Button::BrowseLeft,
+        BUTTON_BROWSE_RIGHT => Button::BrowseRight,
+        BUTTON_ALL => Button::All,
+        BUTTON_AUTO => Button::Autowrite,
+        BUTTON_VOLUME => Button::Volume,
+        BUTTON_SWING => Button::Swing,
+        BUTTON_TEMPO => Button::Tempo,
+        BUTTON_NOTE_REPEAT => Button::NoteRepeat,
+        BUTTON_MAIN_ENCODER => Button::MainEncoder,
+        BUTTON_BACK => Button::Back,
+        BUTTON_ENTER => Button::Enter,
+        BUTTON_METRO => Button::Metro,
+        BUTTON_GROUP_A => Button::GroupA,
+        BUTTON_GROUP_B => Button::GroupB,
+        BUTTON_GROUP_C => Button::GroupC,
+        BUTTON_GROUP_D => Button::GroupD,
+        BUTTON_GROUP_E => Button::GroupE,
+        BUTTON_GROUP_F => Button::GroupF,
+        BUTTON_GROUP_G => Button::GroupG,
+        BUTTON_GROUP_H => Button::GroupH,
+        BUTTON_RESTART => Button::Restart,
+        BUTTON_TRANSPORT_LEFT => Button::TransportLeft,
+        BUTTON_TRANSPORT_RIGHT => Button::TransportRight,
+        BUTTON_GRID => Button::Grid,
+        BUTTON_PLAY => Button::Play,
+        BUTTON_REC => Button::Rec,
+        BUTTON_ERASE => Button::Erase,
+        BUTTON_SCENE => Button::Scene,
+        BUTTON_PATTERN => Button::Pattern,
+        BUTTON_PAD_MODE => Button::PadMode,
+        BUTTON_NAVIGATE => Button::Nav,
+        BUTTON_DUPLICATE => Button::Duplicate,
+        BUTTON_SELECT => Button::Select,
+        BUTTON_SOLO => Button::Solo,
+        BUTTON_MUTE => Button::Mute,
+        BUTTON_CHANNEL => Button::Channel,
+        BUTTON_PLUGIN => Button::Plugin,
+        BUTTON_COPY => Button::Copy,
+        BUTTON_PASTE => Button::Paste,
+        BUTTON_NOTE => Button::Note,
+        BUTTON_NUDGE => Button::Nudge,
+        BUTTON_UNDO => Button::Undo,
+        BUTTON_REDO => Button::Redo,
+        BUTTON_QUANTIZE => Button::Quantize,
+        BUTTON_CLEAR => Button::Clear,
+        BUTTON_CLEAR_AUTO => Button::ClearAuto,
+        BUTTON_MASTER => Button::Master,
+        BUTTON_CUE => Button::Cue,
+        BUTTON_INPUT => Button::Input,
+        _ => Button::Unknown,
+    }
+}
+
+/// Convert a button into a LED index, the main encoder has no LED
+fn button_to_led(button: Button) -> Option<u8> {
+    if button == Button::MainEncoder {
+        return None;
+    }
+    (BUTTON_DISPLAY1..=BUTTON_INPUT)
+        .find(|&code| code != BUTTON_SHIFT && as_device_button(code) == button)
+}
+
+/// Determine if a button LED is RGB or Mono
+fn is_rgb_led(led: u8) -> bool {
+    (BUTTON_GROUP_A..=BUTTON_GROUP_H).contains(&led)
+}
+
+///
+/// Maschine Studio Controller
+///
+/// Requires a valid HID device. Buttons, encoders, the jog wheel, pads, LEDs and the level
+/// meters are supported, the colour displays are not driven over HID and are not available.
+///
+/// The report layouts have not been verified against captured USB traffic yet, the test fixtures
+/// are written from the same layout as the decoder and only guard against regressions, so
+/// `get_device` does not open this driver.
+///
+pub struct MaschineStudio {
+    device: HidDevice,
+    tick_state: u8,
+
+    button_leds: [u8; BUTTON_COUNT],
+    button_leds_dirty: bool,
+
+    rgb_leds: [u8; 3 * RGB_LED_COUNT],
+    rgb_leds_dirty: bool,
+
+    meter_leds: [u8; METER_COUNT * METER_SEGMENTS],
+    meter_leds_dirty: bool,
+
+    led_snapshot: LedSnapshot,
+
+    input: InputDecoder,
+}
+
+impl MaschineStudio {
+    pub const VENDOR_ID: u16 = 0x17cc;
+    pub const PRODUCT_ID: u16 = 0x1300;
+
+    /// Buttons on the controller
+    pub const BUTTONS: [Button; 61] = [
+        Button::Display1,
+        Button::Display2,
+        Button::Display3,
+        Button::Display4,
+        Button::Display5,
+        Button::Display6,
+        Button::Display7,
+        Button::Display8,
+        Button::Control,
+        Button::Step,
+        Button::Browse,
+        Button::Sampling,
+        Button::BrowseLeft,
+        Button::BrowseRight,
+        Button::All,
+        Button::Autowrite,
+        Button::Volume,
+        Button::Swing,
+        Button::Tempo,
+        Button::NoteRepeat,
+        Button::MainEncoder,
+        Button::Back,
+        Button::Enter,
+        Button::Metro,
+        Button::GroupA,
+        Button::GroupB,
+        Button::GroupC,
+        Button::GroupD,
+        Button::GroupE,
+        Button::GroupF,
+        Button::GroupG,
+        Button::GroupH,
+        Button::Restart,
+        Button::TransportLeft,
+        Button::TransportRight,
+        Button::Grid,
+        Button::Play,
+        Button::Rec,
+        Button::Erase,
+        Button::Scene,
+        Button::Pattern,
+        Button::PadMode,
+        Button::Nav,
+        Button::Duplicate,
+        Button::Select,
+        Button::Solo,
+        Button::Mute,
+        Button::Channel,
+        Button::Plugin,
+        Button::Copy,
+        Button::Paste,
+        Button::Note,
+        Button::Nudge,
+        Button::Undo,
+        Button::Redo,
+        Button::Quantize,
+        Button::Clear,
+        Button::ClearAuto,
+        Button::Master,
+        Button::Cue,
+        Button::Input,
+    ];
+
+    /// Number of pads
+    pub const PAD_COUNT: usize = PAD_COUNT;
+
+    /// Number of encoders, numbered as in `Event::Encoder`
+    pub const ENCODER_COUNT: usize = ENCODER_COUNT;
+
+    /// Update LEDs if the arrays have been updated
+    fn send_leds(&mut self) -> Result<(), Error> {
+        if self.button_leds_dirty {
+            let mut buffer: Vec<u8> = vec![BUTTON_LED_ADDR];
+            buffer.extend_from_slice(&self.button_leds);
+            self.device.write(buffer.as_slice())?;
+            self.button_leds_dirty = false;
+        }
+        if self.rgb_leds_dirty {
+            let mut buffer: Vec<u8> = vec![RGB_LED_ADDR];
+            buffer.extend_from_slice(&self.rgb_leds);
+            self.device.write(buffer.as_slice())?;
+            self.rgb_leds_dirty = false;
+        }
+        if self.meter_leds_dirty {
+            let mut buffer: Vec<u8> = vec![METER_LED_ADDR];
+            buffer.extend_from_slice(&self.meter_leds);
+            self.device.write(buffer.as_slice())?;
+            self.meter_leds_dirty = false;
+        }
+
+        Ok(())
+    }
+
+    /// Read incoming reports from the device
+    fn read(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        let mut buffer = [0u8; INPUT_BUFFER_SIZE];
+
+        for _ in 0..32 {
+            let bytes_read = self.device.read(&mut buffer)?;
+            let shift_pressed = self.input.shift_pressed;
+            self.input.decode(&buffer[..bytes_read], context)?;
+
+            // Shift has no event, show it is held on its LED instead
+            if self.input.shift_pressed != shift_pressed {
+                self.button_leds[BUTTON_SHIFT as usize] =
+                    if self.input.shift_pressed { 0xFF } else { 0x00 };
+                self.button_leds_dirty = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set an RGB LED, groups come first followed by the pads
+    fn set_rgb_led(&mut self, idx: usize, color: Color) {
+        self.rgb_leds[3 * idx] = color.r();
+        self.rgb_leds[3 * idx + 1] = color.g();
+        self.rgb_leds[3 * idx + 2] = color.b();
+        self.rgb_leds_dirty = true;
+    }
+}
+
+impl Device for MaschineStudio {
+    fn new() -> Result<Self, Error> {
+        let hid_api = HidApi::new()?;
+        Ok(MaschineStudio {
+            device: hid_api.open(MaschineStudio::VENDOR_ID, MaschineStudio::PRODUCT_ID)?,
+            tick_state: 0,
+
+            button_leds: [0; BUTTON_COUNT],
+            button_leds_dirty: true,
+
+            rgb_leds: [0; 3 * RGB_LED_COUNT],
+            rgb_leds_dirty: true,
+
+            meter_leds: [0; METER_COUNT * METER_SEGMENTS],
+            meter_leds_dirty: true,
+
+            led_snapshot: LedSnapshot::new(PAD_COUNT),
+
+            input: InputDecoder::new(),
+        })
+    }
+
+    fn set_button_led(&mut self, button: Button, color: Color) {
+        if let Some(led) = button_to_led(button) {
+            if is_rgb_led(led) {
+                self.set_rgb_led((led - BUTTON_GROUP_A) as usize, color);
+            } else {
+                self.button_leds[led as usize] = LedColor::from(color).brightness();
+                self.button_leds_dirty = true;
+            }
+            self.led_snapshot.buttons.insert(button, color.into());
+        }
+    }
+
+    fn set_pad_led(&mut self, pad: u8, color: Color) {
+        if (pad as usize) < PAD_COUNT {
+            self.set_rgb_led(8 + pad as usize, color);
+            self.led_snapshot.pads[pad as usize] = color.into();
+        }
+    }
+
+    fn set_meter(&mut self, meter: u8, level: f32) {
+        self.meter_leds_dirty |= set_meter_leds(&mut self.meter_leds, METER_SEGMENTS, meter, level);
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let mut encoders = vec![
+            EncoderInfo {
+                range: ENCODER_RANGE,
+                touch: false,
+            };
+            ENCODER_COUNT
+        ];
+        encoders[0].range = MAIN_ENCODER_RANGE;
+
+        Capabilities {
+            encoders,
+            pad_count: PAD_COUNT,
+            meters: vec![
+                MeterInfo {
+                    segments: METER_SEGMENTS
+                };
+                METER_COUNT
+            ],
+            jog_wheel: true,
+            pad_leds: Some(LedKind::Rgb),
+            buttons: MaschineStudio::BUTTONS.to_vec(),
+            button_leds: MaschineStudio::BUTTONS
+                .iter()
+                .filter_map(|&button| {
+                    let led = button_to_led(button)?;
+                    let kind = if is_rgb_led(led) {
+                        LedKind::Rgb
+                    } else {
+                        LedKind::Mono
+                    };
+                    Some((button, kind))
+                })
+                .collect(),
+            ..Capabilities::new(
+                "Maschine Studio",
+                MaschineStudio::VENDOR_ID,
+                MaschineStudio::PRODUCT_ID,
+            )
+        }
+    }
+
+    fn device_info(&self) -> Result<DeviceInfo, Error> {
+        DeviceInfo::read(&self.device)
+    }
+
+    fn led_snapshot(&self) -> LedSnapshot {
+        self.led_snapshot.clone()
+    }
+
+    fn reconnect(&mut self) -> Result<(), Error> {
+        let hid_api = HidApi::new()?;
+        self.device = hid_api.open(MaschineStudio::VENDOR_ID, MaschineStudio::PRODUCT_ID)?;
+        self.tick_state = 0;
+
+        // Restore the device to the last known state
+        self.button_leds_dirty = true;
+        self.rgb_leds_dirty = true;
+        self.meter_leds_dirty = true;
+
+        Ok(())
+    }
+}
+
+impl EventTask for MaschineStudio {
+    fn tick(&mut self, context: &mut EventContext) -> Result<(), Error> {
+        if self.tick_state == 0 {
+            self.send_leds()?;
+        } else if self.tick_state == 1 {
+            self.read(context)?;
+        }
+
+        self.tick_state = (self.tick_state + 1) % 2;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::decoder::fixtures::decode;
+
+    // Hand-built from the layout the decoder expects, not captured from hardware. Replace with
+    // USB captures once available.
+
+    // Buttons reports: ID, 8 bytes of button bits, main encoder, jog wheel, 8 encoders
+    const BUTTONS_IDLE: &str = "01 00 00 00 00 00 00 00 00 05 80 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_UNDO: &str = "01 00 00 00 00 00 00 40 00 05 80 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_SHIFT_PLAY: &str = "01 00 00 00 00 90 00 00 00 05 80 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_ENCODERS_TURNED: &str = "01 00 00 00 00 00 00 00 00 04 80 \
+        03 02 00 02 00 02 00 02 00 02 00 02 00 02 fe 01";
+    const BUTTONS_ENCODER1_TOP: &str = "01 00 00 00 00 00 00 00 00 05 80 \
+        ff 03 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_ENCODER1_WRAPPED: &str = "01 00 00 00 00 00 00 00 00 05 80 \
+        01 00 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_JOG_BACK: &str = "01 00 00 00 00 00 00 00 00 05 7d \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    // Jumps half way round the wheel, taken as the shorter way back
+    const BUTTONS_JOG_JUMP: &str = "01 00 00 00 00 00 00 00 00 05 02 \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+    const BUTTONS_JOG_WRAP: &str = "01 00 00 00 00 00 00 00 00 05 fe \
+        00 02 00 02 00 02 00 02 00 02 00 02 00 02 00 02";
+
+    // Pads reports: ID, 16 pads of 12-bit pressure with the pad number in the top nibble
+    const PADS_IDLE: &str = "20 00 00 00 10 00 20 00 30 00 40 00 50 00 60 00 70 \
+        00 80 00 90 00 a0 00 b0 00 c0 00 d0 00 e0 00 f0";
+    const PADS_PAD3_HIT: &str = "20 00 00 00 10 00 20 00 38 00 40 00 50 00 60 00 70 \
+        00 80 00 90 00 a0 00 b0 00 c0 00 d0 00 e0 00 f0";
+    const PADS_PAD5_LIGHT: &str = "20 00 00 00 10 00 20 00 30 00 40 f0 51 00 60 00 70 \
+        00 80 00 90 00 a0 00 b0 00 c0 00 d0 00 e0 00 f0";
+
+    #[test]
+    fn button_press() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_UNDO]);
+        assert_eq!(events, vec!["Button(Undo, true, false)"]);
+    }
+
+    #[test]
+    fn shift_modifies_buttons_without_an_event() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_SHIFT_PLAY]);
+        assert_eq!(events, vec!["Button(Play, true, true)"]);
+    }
+
+    #[test]
+    fn encoders_report_deltas() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(&mut decoder, &[BUTTONS_IDLE, BUTTONS_ENCODERS_TURNED]);
+        assert_eq!(
+            events,
+            vec![
+                "Encoder(0, 4, -1, false)",
+                "Encoder(1, 515, 3, false)",
+                "Encoder(8, 510, -2, false)"
+            ]
+        );
+    }
+
+    #[test]
+    fn encoders_wrap() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(
+            &mut decoder,
+            &[BUTTONS_IDLE, BUTTONS_ENCODER1_TOP, BUTTONS_ENCODER1_WRAPPED],
+        );
+        assert_eq!(
+            events,
+            vec!["Encoder(1, 1023, 511, false)", "Encoder(1, 1, 2, false)"]
+        );
+    }
+
+    #[test]
+    fn jog_wheel_reports_deltas() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(
+            &mut decoder,
+            &[
+                BUTTONS_IDLE,
+                BUTTONS_JOG_BACK,
+                BUTTONS_JOG_JUMP,
+                BUTTONS_JOG_WRAP,
+            ],
+        );
+        assert_eq!(
+            events,
+            vec!["Jog(-3, false)", "Jog(-123, false)", "Jog(-4, false)"]
+        );
+    }
+
+    #[test]
+    fn pad_hit_and_release() {
+        let mut decoder = InputDecoder::new();
+        let events = decode(
+            &mut decoder,
+            &[PADS_IDLE, PADS_PAD3_HIT, PADS_PAD5_LIGHT, PADS_IDLE],
+        );
+        assert_eq!(events, vec!["Pad(3, 128, false)", "Pad(3, 0, false)"]);
+    }
+
+    #[test]
+    fn short_reports_are_rejected() {
+        let mut decoder = InputDecoder::new();
+        let mut context = EventContext::new();
+        assert!(matches!(
+            decoder.decode(&[BUTTONS_REPORT, 0, 0], &mut context),
+            Err(Error::InvalidReport)
+        ));
+        assert!(matches!(
+            decoder.decode(&[PADS_REPORT, 0, 0], &mut context),
+            Err(Error::InvalidReport)
+        ));
+    }
+
+    #[test]
+    fn meters_fill_their_half_of_the_report() {
+        let mut leds = [0; METER_COUNT * METER_SEGMENTS];
+        assert!(set_meter_leds(&mut leds, METER_SEGMENTS, 1, 0.25));
+        assert!(leds[..METER_SEGMENTS].iter().all(|&led| led == 0x00));
+        assert_eq!(
+            leds[METER_SEGMENTS..],
+            [
+                0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00
+            ]
+        );
+        assert!(!set_meter_leds(
+            &mut leds,
+            METER_SEGMENTS,
+            METER_COUNT as u8,
+            1.0
+        ));
+    }
+
+    #[test]
+    fn button_leds_match_button_codes() {
+        for button in MaschineStudio::BUTTONS {
+            match button_to_led(button) {
+                Some(led) => assert_eq!(as_device_button(led), button),
+                None => assert_eq!(button, Button::MainEncoder),
+            }
+        }
+    }
+}
@@ -1,11 +1,34 @@
+mod capabilities;
+mod decoder;
+mod info;
+mod maschine_jam;
+mod maschine_mikro_mk1;
 mod maschine_mikro_mk2;
+mod maschine_mikro_mk3;
+mod maschine_mk1;
 mod maschine_mk2;
+mod maschine_mk3;
+mod maschine_studio;
+mod palette;
+#[cfg(test)]
+pub(crate) mod testing;
 
+use crate::leds::LedSnapshot;
+use crate::strip::StripLeds;
 use crate::Color;
+pub use capabilities::{Capabilities, DisplayInfo, EncoderInfo, GridInfo, LedKind, MeterInfo};
+pub use info::DeviceInfo;
+pub use maschine_jam::MaschineJam;
+pub use maschine_mikro_mk1::MaschineMikroMk1;
 pub use maschine_mikro_mk2::MaschineMikroMk2;
+pub use maschine_mikro_mk3::MaschineMikroMk3;
+pub use maschine_mk1::MaschineMk1;
 pub use maschine_mk2::MaschineMk2;
+pub use maschine_mk3::MaschineMk3;
+pub use maschine_studio::MaschineStudio;
 use raqote::DrawTarget;
 
+use crate::display::{DisplaySettings, DisplayStats, DitherMode, Framebuffer, Surface};
 use crate::{events::Button, Error, EventTask};
 
 ///
@@ -21,6 +44,16 @@ pub trait Device: EventTask {
     where
         Self: Sized;
 
+    ///
+    /// Describe the controls, displays and LEDs of the device
+    ///
+    fn capabilities(&self) -> Capabilities;
+
+    ///
+    /// Read the manufacturer, product, serial and release number of the connected unit
+    ///
+    fn device_info(&self) -> Result<DeviceInfo, Error>;
+
     ///
     /// Set the State of a Button LED
     ///
@@ -37,10 +70,231 @@ pub trait Device: EventTask {
     /// - color - Color to apply
     fn set_pad_led(&mut self, pad: u8, color: Color);
 
+    ///
+    /// Set the State of a grid cell LED
+    ///
+    /// Devices without a grid ignore this.
+    ///
+    /// **Arguments**
+    /// - column - Grid column
+    /// - row - Grid row
+    /// - color - Color to apply
+    fn set_grid_led(&mut self, _column: u8, _row: u8, _color: Color) {}
+
+    ///
+    /// Show a level on a LED meter
+    ///
+    /// Devices without meters ignore this.
+    ///
+    /// **Arguments**
+    /// - meter - Meter index
+    /// - level - Level from 0.0 (off) to 1.0 (every segment lit)
+    fn set_meter(&mut self, _meter: u8, _level: f32) {}
+
+    ///
+    /// Show a position or a level on the LEDs along a touch strip
+    ///
+    /// Devices without strip LEDs ignore this.
+    ///
+    /// **Arguments**
+    /// - strip - Strip number
+    /// - leds - Position or level to show
+    /// - color - Color of the lit LEDs
+    fn set_strip_leds(&mut self, _strip: u8, _leds: StripLeds, _color: Color) {}
+
+    ///
+    /// Get the last colours applied to the LEDs
+    ///
+    fn led_snapshot(&self) -> LedSnapshot;
+
+    ///
+    /// Apply the LED colours of a snapshot
+    ///
+    /// **Arguments**
+    /// - snapshot - Colours to apply, buttons missing from the snapshot are unchanged
+    fn restore_leds(&mut self, snapshot: &LedSnapshot) {
+        for (button, color) in snapshot.buttons.iter() {
+            self.set_button_led(*button, (*color).into());
+        }
+        for (pad, color) in snapshot.pads.iter().enumerate() {
+            self.set_pad_led(pad as u8, (*color).into());
+        }
+        if let Some(grid) = self.capabilities().grid {
+            for (cell, color) in snapshot.grid.iter().enumerate() {
+                let (column, row) = (cell as u8 % grid.columns, cell as u8 / grid.columns);
+                self.set_grid_led(column, row, (*color).into());
+            }
+        }
+    }
+
+    ///
+    /// Displays of the device, by display index
+    ///
+    /// The display methods are built on these, devices without displays have none.
+    ///
+    fn surfaces(&self) -> &[Surface] {
+        &[]
+    }
+
+    ///
+    /// Displays of the device for modification, by display index
+    ///
+    fn surfaces_mut(&mut self) -> &mut [Surface] {
+        &mut []
+    }
+
     ///
     /// Get a specific display of the Device
     ///
+    /// This is a back buffer, changes are committed on the next tick or by `commit_display`.
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    fn get_display(&mut self, display_idx: u8) -> Result<&mut DrawTarget, Error> {
+        Ok(surface_mut(self.surfaces_mut(), display_idx)?.canvas())
+    }
+
+    ///
+    /// Get the packed 1-bit framebuffer of a display
+    ///
+    /// Drawing through the framebuffer bypasses the colour canvas returned by `get_display`,
+    /// whichever of the two was accessed last is sent to the device.
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    fn get_framebuffer(&mut self, display_idx: u8) -> Result<&mut Framebuffer, Error> {
+        Ok(surface_mut(self.surfaces_mut(), display_idx)?.framebuffer())
+    }
+
+    ///
+    /// Set how a display converts its colour image into 1-bit pixels
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    /// - mode - Conversion to apply when the frame is committed
+    fn set_dither_mode(&mut self, display_idx: u8, mode: DitherMode) -> Result<(), Error> {
+        surface_mut(self.surfaces_mut(), display_idx)?.set_dither_mode(mode);
+        Ok(())
+    }
+
+    ///
+    /// Commit the drawn image of a display to be sent on a following tick
+    ///
+    /// A committed frame that has not been sent yet is replaced and counted as dropped. Only
+    /// needed when auto commit is turned off, see `set_auto_commit`.
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    fn commit_display(&mut self, display_idx: u8) -> Result<(), Error> {
+        surface_mut(self.surfaces_mut(), display_idx)?.commit();
+        Ok(())
+    }
+
+    ///
+    /// Choose whether a drawn display is committed automatically
+    ///
+    /// On by default, a display accessed through `get_display` or `get_framebuffer` is committed
+    /// on the next tick. Turn it off to send only the frames passed to `commit_display`, so
+    /// drawing can span several ticks without partial frames being sent.
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    /// - auto_commit - Commit the display on the tick after it was accessed
+    fn set_auto_commit(&mut self, display_idx: u8, auto_commit: bool) -> Result<(), Error> {
+        surface_mut(self.surfaces_mut(), display_idx)?.set_auto_commit(auto_commit);
+        Ok(())
+    }
+
+    ///
+    /// Limit how often committed frames are sent to a display
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    /// - fps - Maximum frames per second, `None` to send frames as soon as possible
+    fn set_max_fps(&mut self, display_idx: u8, fps: Option<u32>) -> Result<(), Error> {
+        surface_mut(self.surfaces_mut(), display_idx)?.set_max_fps(fps);
+        Ok(())
+    }
+
+    ///
+    /// Get the frame statistics of a display
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    fn display_stats(&self, display_idx: u8) -> Result<DisplayStats, Error> {
+        Ok(surface(self.surfaces(), display_idx)?.stats())
+    }
+
+    ///
+    /// Change the hardware settings of a display
+    ///
+    /// Not supported yet, see `DisplaySettings`. Returns `Error::Unsupported` for every display.
+    ///
     /// **Arguments**
     /// - display_idx - Display index
-    fn get_display(&mut self, display_idx: u8) -> Result<&mut DrawTarget, Error>;
+    /// - settings - Contrast, brightness, inversion and power state
+    fn set_display_settings(
+        &mut self,
+        display_idx: u8,
+        _settings: DisplaySettings,
+    ) -> Result<(), Error> {
+        surface_mut(self.surfaces_mut(), display_idx)?;
+        Err(Error::Unsupported("display settings".to_string()))
+    }
+
+    ///
+    /// Get the hardware settings of a display
+    ///
+    /// Not supported yet, see `DisplaySettings`. Returns `Error::Unsupported` for every display.
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    fn display_settings(&self, display_idx: u8) -> Result<DisplaySettings, Error> {
+        surface(self.surfaces(), display_idx)?;
+        Err(Error::Unsupported("display settings".to_string()))
+    }
+
+    ///
+    /// Re-open the HID device after it was disconnected
+    ///
+    /// LED state and display images are sent again on the following ticks.
+    fn reconnect(&mut self) -> Result<(), Error>;
+}
+
+/// Display of a device by index
+fn surface(surfaces: &[Surface], display_idx: u8) -> Result<&Surface, Error> {
+    surfaces
+        .get(display_idx as usize)
+        .ok_or(Error::InvalidDisplay(display_idx))
+}
+
+/// Display of a device by index, for modification
+fn surface_mut(surfaces: &mut [Surface], display_idx: u8) -> Result<&mut Surface, Error> {
+    surfaces
+        .get_mut(display_idx as usize)
+        .ok_or(Error::InvalidDisplay(display_idx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::TestDevice;
+    use super::*;
+
+    #[test]
+    fn display_settings_are_unsupported() {
+        let mut device = TestDevice::new().unwrap();
+        let settings = DisplaySettings::default();
+        assert!(matches!(
+            device.set_display_settings(0, settings),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            device.display_settings(1),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            device.set_display_settings(2, settings),
+            Err(Error::InvalidDisplay(2))
+        ));
+    }
 }
@@ -0,0 +1,41 @@
+use crate::leds::LedColor;
+
+// Indexed colours: 0 is off, each of the 16 hues has 4 brightness levels from index 4, followed by
+// 4 levels of white
+const HUE_BASE: u8 = 4;
+const HUE_COUNT: u16 = 16;
+const WHITE_BASE: u8 = 68;
+
+///
+/// Convert a colour into the nearest entry of the palette used by indexed colour LEDs
+///
+/// **Arguments**
+/// - color - Colour to convert
+pub(crate) fn color_index(color: LedColor) -> u8 {
+    let max = color.brightness();
+    let min = color.r.min(color.g).min(color.b);
+    if max < 0x10 {
+        return 0;
+    }
+
+    let level = max / 0x40;
+
+    // Colours with little saturation are shown as white
+    let chroma = (max - min) as i32;
+    if chroma < (max as i32) / 4 {
+        return WHITE_BASE + level;
+    }
+
+    let (r, g, b) = (color.r as i32, color.g as i32, color.b as i32);
+    let hue = if max as i32 == r {
+        60 * (g - b) / chroma
+    } else if max as i32 == g {
+        120 + 60 * (b - r) / chroma
+    } else {
+        240 + 60 * (r - g) / chroma
+    };
+    let hue = hue.rem_euclid(360) as u16;
+    let hue_idx = ((hue * HUE_COUNT + 180) / 360) % HUE_COUNT;
+
+    HUE_BASE + (hue_idx as u8) * 4 + level
+}
@@ -0,0 +1,76 @@
+use super::{Capabilities, DeviceInfo, DisplayInfo};
+use crate::display::{PixelLayout, Surface};
+use crate::events::{Button, EventContext, EventTask};
+use crate::leds::{LedColor, LedSnapshot};
+use crate::{Color, Device, Error};
+
+///
+/// Device without hardware, for testing code built on `Device`
+///
+/// Has two 256x64 displays and 16 pads like the Maschine Mk2.
+///
+pub(crate) struct TestDevice {
+    pub displays: [Surface; 2],
+    pub leds: LedSnapshot,
+}
+
+impl Device for TestDevice {
+    fn new() -> Result<Self, Error> {
+        Ok(Self {
+            displays: [
+                Surface::new(256, 64, PixelLayout::Horizontal),
+                Surface::new(256, 64, PixelLayout::Horizontal),
+            ],
+            leds: LedSnapshot::new(16),
+        })
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            displays: self
+                .displays
+                .iter()
+                .map(|display| DisplayInfo {
+                    width: display.width(),
+                    height: display.height(),
+                })
+                .collect(),
+            pad_count: 16,
+            ..Capabilities::new("Test", 0, 0)
+        }
+    }
+
+    fn device_info(&self) -> Result<DeviceInfo, Error> {
+        Err(Error::NoDevices)
+    }
+
+    fn set_button_led(&mut self, button: Button, color: Color) {
+        self.leds.buttons.insert(button, LedColor::from(color));
+    }
+
+    fn set_pad_led(&mut self, pad: u8, color: Color) {
+        self.leds.pads[pad as usize] = LedColor::from(color);
+    }
+
+    fn led_snapshot(&self) -> LedSnapshot {
+        self.leds.clone()
+    }
+
+    fn surfaces(&self) -> &[Surface] {
+        &self.displays
+    }
+
+    fn surfaces_mut(&mut self) -> &mut [Surface] {
+        &mut self.displays
+    }
+
+    fn reconnect(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl EventTask for TestDevice {
+    fn tick(&mut self, _context: &mut EventContext) -> Result<(), Error> {
+        Ok(())
+    }
+}
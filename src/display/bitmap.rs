@@ -0,0 +1,446 @@
+use super::{to_monochrome, DitherMode, Framebuffer};
+use crate::{Device, Error};
+use image::codecs::gif::GifDecoder;
+use image::imageops::{self, FilterType};
+use image::{AnimationDecoder, ImageFormat, RgbaImage};
+use std::io::Cursor;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Delay used for GIF frames that do not specify one, matching common browsers
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+///
+/// How an image is scaled to the size of a display
+///
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fit {
+    ///
+    /// Scale to the display size, ignoring the aspect ratio
+    ///
+    Stretch,
+
+    ///
+    /// Scale to fit inside the display, centred with unlit borders
+    ///
+    #[default]
+    Contain,
+
+    ///
+    /// Scale to fill the display, cropping the centre of the image
+    ///
+    Cover,
+}
+
+///
+/// Decoded PNG, BMP or GIF image
+///
+/// Animated GIFs keep every frame, still images have a single frame.
+///
+pub struct Image {
+    frames: Vec<(RgbaImage, Duration)>,
+}
+
+impl Image {
+    ///
+    /// Decode an image, the format is detected from the data
+    ///
+    /// **Arguments**
+    /// - data - Encoded PNG, BMP or GIF image
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        let format = image::guess_format(data)?;
+        let frames = if format == ImageFormat::Gif {
+            GifDecoder::new(Cursor::new(data))?
+                .into_frames()
+                .collect_frames()?
+                .into_iter()
+                .map(|frame| {
+                    let delay = Duration::from(frame.delay());
+                    let delay = if delay.is_zero() {
+                        DEFAULT_FRAME_DELAY
+                    } else {
+                        delay
+                    };
+                    (frame.into_buffer(), delay)
+                })
+                .collect()
+        } else {
+            let image = image::load_from_memory_with_format(data, format)?;
+            vec![(image.to_rgba8(), Duration::ZERO)]
+        };
+
+        Ok(Self { frames })
+    }
+
+    ///
+    /// Load and decode an image file
+    ///
+    /// **Arguments**
+    /// - path - Path of a PNG, BMP or GIF file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Width of the image in pixels
+    pub fn width(&self) -> u32 {
+        self.frames.first().map_or(0, |(frame, _)| frame.width())
+    }
+
+    /// Height of the image in pixels
+    pub fn height(&self) -> u32 {
+        self.frames.first().map_or(0, |(frame, _)| frame.height())
+    }
+
+    /// Number of frames, 1 for still images
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Time a frame is shown for, zero for still images
+    pub fn frame_delay(&self, frame: usize) -> Option<Duration> {
+        self.frames.get(frame).map(|(_, delay)| *delay)
+    }
+
+    ///
+    /// Scale a frame to a display size and convert it into 1-bit pixels
+    ///
+    /// **Arguments**
+    /// - frame - Frame index
+    /// - width - Display width in pixels
+    /// - height - Display height in pixels
+    /// - fit - How the image is scaled to the display
+    /// - mode - Conversion to 1-bit pixels
+    pub fn render(
+        &self,
+        frame: usize,
+        width: usize,
+        height: usize,
+        fit: Fit,
+        mode: DitherMode,
+    ) -> Option<Bitmap> {
+        let (image, _) = self.frames.get(frame)?;
+        let scaled = scale(image, width as u32, height as u32, fit);
+
+        // Premultiplied ARGB, as stored by raqote
+        let data: Vec<u32> = scaled
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                let premultiply = |c: u8| c as u32 * a as u32 / 0xFF;
+                (a as u32) << 24 | premultiply(r) << 16 | premultiply(g) << 8 | premultiply(b)
+            })
+            .collect();
+
+        Some(Bitmap {
+            width,
+            height,
+            pixels: to_monochrome(&data, width, mode),
+        })
+    }
+
+    ///
+    /// Convert every frame for playback on a display
+    ///
+    /// **Arguments**
+    /// - width - Display width in pixels
+    /// - height - Display height in pixels
+    /// - fit - How the image is scaled to the display
+    /// - mode - Conversion to 1-bit pixels
+    pub fn animate(&self, width: usize, height: usize, fit: Fit, mode: DitherMode) -> Animation {
+        let frames = (0..self.frames.len())
+            .filter_map(|idx| {
+                let bitmap = self.render(idx, width, height, fit, mode)?;
+                Some((bitmap, self.frames[idx].1))
+            })
+            .collect();
+
+        Animation::new(frames)
+    }
+}
+
+/// Resize an image to exactly the target size
+fn scale(image: &RgbaImage, width: u32, height: u32, fit: Fit) -> RgbaImage {
+    let (src_width, src_height) = (image.width().max(1), image.height().max(1));
+    match fit {
+        Fit::Stretch => imageops::resize(image, width, height, FilterType::Triangle),
+        Fit::Contain => {
+            let ratio = f32::min(
+                width as f32 / src_width as f32,
+                height as f32 / src_height as f32,
+            );
+            let scaled_width = ((src_width as f32 * ratio).round() as u32).clamp(1, width);
+            let scaled_height = ((src_height as f32 * ratio).round() as u32).clamp(1, height);
+            let scaled = imageops::resize(image, scaled_width, scaled_height, FilterType::Triangle);
+
+            let mut target = RgbaImage::new(width, height);
+            imageops::overlay(
+                &mut target,
+                &scaled,
+                ((width - scaled_width) / 2) as i64,
+                ((height - scaled_height) / 2) as i64,
+            );
+            target
+        }
+        Fit::Cover => {
+            let ratio = f32::max(
+                width as f32 / src_width as f32,
+                height as f32 / src_height as f32,
+            );
+            let scaled_width = ((src_width as f32 * ratio).round() as u32).max(width);
+            let scaled_height = ((src_height as f32 * ratio).round() as u32).max(height);
+            let scaled = imageops::resize(image, scaled_width, scaled_height, FilterType::Triangle);
+
+            imageops::crop_imm(
+                &scaled,
+                (scaled_width - width) / 2,
+                (scaled_height - height) / 2,
+                width,
+                height,
+            )
+            .to_image()
+        }
+    }
+}
+
+///
+/// 1-bit image converted for a display
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Bitmap {
+    /// Width in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Pixel is lit, pixels outside of the bitmap are unlit
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels[y * self.width + x]
+    }
+
+    ///
+    /// Copy the bitmap into a framebuffer, clipped to the framebuffer size
+    ///
+    /// **Arguments**
+    /// - fb - Framebuffer of the display
+    pub fn draw(&self, fb: &mut Framebuffer) {
+        for y in 0..self.height.min(fb.height()) {
+            for x in 0..self.width.min(fb.width()) {
+                fb.set_pixel(x, y, self.pixels[y * self.width + x]);
+            }
+        }
+    }
+}
+
+///
+/// Frame by frame playback of an image on a display
+///
+/// Frames are drawn into the display framebuffer and committed, so they are sent through the
+/// normal display updates of the device.
+///
+pub struct Animation {
+    frames: Vec<(Bitmap, Duration)>,
+    current: usize,
+    due: Option<Instant>,
+
+    /// Start again after the last frame
+    pub looping: bool,
+}
+
+impl Animation {
+    ///
+    /// Create an animation from converted frames
+    ///
+    /// **Arguments**
+    /// - frames - Bitmaps and the time each is shown for
+    pub fn new(frames: Vec<(Bitmap, Duration)>) -> Self {
+        Self {
+            frames,
+            current: 0,
+            due: Some(Instant::now()),
+            looping: true,
+        }
+    }
+
+    /// Number of frames
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Animation has no frames
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Index of the next frame to draw
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// All frames have been drawn and the animation is not looping
+    pub fn is_finished(&self) -> bool {
+        self.due.is_none()
+    }
+
+    /// Play from the first frame
+    pub fn restart(&mut self) {
+        self.current = 0;
+        self.due = Some(Instant::now());
+    }
+
+    ///
+    /// Draw the next frame to a display once it is due, returns true if a frame was drawn
+    ///
+    /// **Arguments**
+    /// - device - Device owning the display
+    /// - display_idx - Display index
+    pub fn update<D: Device + ?Sized>(
+        &mut self,
+        device: &mut D,
+        display_idx: u8,
+    ) -> Result<bool, Error> {
+        let now = Instant::now();
+        let due = match self.due {
+            Some(due) if due <= now && !self.frames.is_empty() => due,
+            _ => return Ok(false),
+        };
+
+        let (bitmap, delay) = &self.frames[self.current];
+        bitmap.draw(device.get_framebuffer(display_idx)?);
+        device.commit_display(display_idx)?;
+
+        self.current += 1;
+        if self.current == self.frames.len() {
+            self.current = 0;
+            if !self.looping || self.frames.len() == 1 {
+                self.due = None;
+                return Ok(true);
+            }
+        }
+        // Keep to the frame timing unless playback has fallen behind
+        self.due = Some((due + *delay).max(now));
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::testing::TestDevice;
+    use image::Rgba;
+
+    const MODE: DitherMode = DitherMode::Threshold(0x80);
+
+    /// Still image 8x2, white at both ends and black in the middle
+    fn ends() -> Image {
+        let image = RgbaImage::from_fn(8, 2, |x, _| {
+            if !(2..6).contains(&x) {
+                Rgba([0xFF, 0xFF, 0xFF, 0xFF])
+            } else {
+                Rgba([0x00, 0x00, 0x00, 0xFF])
+            }
+        });
+        Image {
+            frames: vec![(image, Duration::ZERO)],
+        }
+    }
+
+    /// Display sized bitmap with every pixel set the same
+    fn solid(lit: bool) -> Bitmap {
+        Bitmap {
+            width: 256,
+            height: 64,
+            pixels: vec![lit; 256 * 64],
+        }
+    }
+
+    #[test]
+    fn contain_keeps_the_aspect_ratio_with_unlit_borders() {
+        let bitmap = ends().render(0, 8, 8, Fit::Contain, MODE).unwrap();
+        for y in 0..8 {
+            let row_lit = (3..5).contains(&y);
+            assert_eq!(bitmap.pixel(0, y), row_lit, "row {y}");
+            assert_eq!(bitmap.pixel(7, y), row_lit, "row {y}");
+            assert!(!bitmap.pixel(4, y));
+        }
+    }
+
+    #[test]
+    fn stretch_fills_the_display() {
+        let bitmap = ends().render(0, 8, 8, Fit::Stretch, MODE).unwrap();
+        for y in 0..8 {
+            assert!(bitmap.pixel(0, y));
+            assert!(bitmap.pixel(7, y));
+            assert!(!bitmap.pixel(4, y));
+        }
+    }
+
+    #[test]
+    fn cover_crops_the_centre() {
+        let bitmap = ends().render(0, 8, 8, Fit::Cover, MODE).unwrap();
+        for y in 0..8 {
+            for x in 0..8 {
+                assert!(!bitmap.pixel(x, y), "pixel {x},{y}");
+            }
+        }
+    }
+
+    #[test]
+    fn animation_plays_each_frame_once_when_not_looping() {
+        let mut device = TestDevice::new().unwrap();
+        let mut animation = Animation::new(vec![
+            (solid(true), Duration::ZERO),
+            (solid(false), Duration::ZERO),
+        ]);
+        animation.looping = false;
+
+        assert!(animation.update(&mut device, 0).unwrap());
+        assert!(device.get_framebuffer(0).unwrap().pixel(10, 10));
+        assert_eq!(animation.current(), 1);
+
+        assert!(animation.update(&mut device, 0).unwrap());
+        assert!(!device.get_framebuffer(0).unwrap().pixel(10, 10));
+        assert!(animation.is_finished());
+        assert!(!animation.update(&mut device, 0).unwrap());
+    }
+
+    #[test]
+    fn looping_animation_starts_again() {
+        let mut device = TestDevice::new().unwrap();
+        let mut animation = Animation::new(vec![
+            (solid(true), Duration::ZERO),
+            (solid(false), Duration::ZERO),
+        ]);
+
+        for _ in 0..3 {
+            assert!(animation.update(&mut device, 0).unwrap());
+        }
+        assert_eq!(animation.current(), 1);
+        assert!(device.get_framebuffer(0).unwrap().pixel(10, 10));
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn animation_waits_for_the_frame_delay() {
+        let mut device = TestDevice::new().unwrap();
+        let delay = Duration::from_secs(3600);
+        let mut animation = Animation::new(vec![(solid(true), delay), (solid(false), delay)]);
+
+        assert!(animation.update(&mut device, 0).unwrap());
+        assert!(!animation.update(&mut device, 0).unwrap());
+        assert_eq!(animation.current(), 1);
+        assert!(device.get_framebuffer(0).unwrap().pixel(10, 10));
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::{Duration, Instant};
+
+///
+/// Frame statistics of a display
+///
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayStats {
+    /// Frames committed by the application
+    pub committed: u64,
+
+    /// Frames written to the device
+    pub sent: u64,
+
+    /// Committed frames replaced by a newer commit before they could be sent
+    pub dropped: u64,
+
+    /// Time taken to write the most recent frame
+    pub last_transfer: Duration,
+
+    /// Time spent writing all frames
+    pub total_transfer: Duration,
+}
+
+impl DisplayStats {
+    /// Average time taken to write a frame
+    pub fn average_transfer(&self) -> Duration {
+        if self.sent == 0 {
+            Duration::ZERO
+        } else {
+            self.total_transfer / self.sent as u32
+        }
+    }
+}
+
+///
+/// Front buffer of a display
+///
+/// Holds the last committed frame and decides when it is sent, limited to a maximum rate.
+///
+pub(crate) struct FrameQueue {
+    front: Option<Vec<u8>>,
+    pending: bool,
+    min_interval: Option<Duration>,
+    last_sent: Option<Instant>,
+    stats: DisplayStats,
+}
+
+impl FrameQueue {
+    pub fn new() -> Self {
+        Self {
+            front: None,
+            pending: false,
+            min_interval: None,
+            last_sent: None,
+            stats: DisplayStats::default(),
+        }
+    }
+
+    /// Replace the front buffer with a new frame
+    pub fn commit(&mut self, data: Vec<u8>) {
+        if self.pending {
+            self.stats.dropped += 1;
+        }
+        self.front = Some(data);
+        self.pending = true;
+        self.stats.committed += 1;
+    }
+
+    /// Limit how often frames are sent, `None` sends every frame as soon as possible
+    pub fn set_max_fps(&mut self, fps: Option<u32>) {
+        self.min_interval = fps
+            .filter(|fps| *fps > 0)
+            .map(|fps| Duration::from_secs(1) / fps);
+    }
+
+    /// Send the current front buffer again, eg after a reconnect
+    pub fn resend(&mut self) {
+        self.pending = self.front.is_some();
+        self.last_sent = None;
+    }
+
+    /// Frame data if a committed frame is due to be sent
+    pub fn take(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if !self.pending {
+            return None;
+        }
+        if let (Some(interval), Some(last_sent)) = (self.min_interval, self.last_sent) {
+            if now.duration_since(last_sent) < interval {
+                return None;
+            }
+        }
+        self.pending = false;
+        self.last_sent = Some(now);
+        self.front.clone()
+    }
+
+    /// Record the time taken to write a frame taken from the queue
+    pub fn record_transfer(&mut self, duration: Duration) {
+        self.stats.sent += 1;
+        self.stats.last_transfer = duration;
+        self.stats.total_transfer += duration;
+    }
+
+    pub fn stats(&self) -> DisplayStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_while_pending_replaces_the_frame() {
+        let mut queue = FrameQueue::new();
+        let now = Instant::now();
+        assert_eq!(queue.take(now), None);
+
+        queue.commit(vec![1]);
+        queue.commit(vec![2]);
+        assert_eq!(queue.take(now), Some(vec![2]));
+        assert_eq!(queue.take(now), None);
+
+        let stats = queue.stats();
+        assert_eq!((stats.committed, stats.dropped), (2, 1));
+    }
+
+    #[test]
+    fn max_fps_throttles_frames() {
+        let mut queue = FrameQueue::new();
+        queue.set_max_fps(Some(10));
+        let start = Instant::now();
+
+        queue.commit(vec![1]);
+        assert_eq!(queue.take(start), Some(vec![1]));
+
+        queue.commit(vec![2]);
+        assert_eq!(queue.take(start + Duration::from_millis(50)), None);
+        assert_eq!(
+            queue.take(start + Duration::from_millis(100)),
+            Some(vec![2])
+        );
+
+        // No limit sends every frame straight away
+        queue.set_max_fps(None);
+        queue.commit(vec![3]);
+        assert_eq!(
+            queue.take(start + Duration::from_millis(101)),
+            Some(vec![3])
+        );
+    }
+
+    #[test]
+    fn resend_sends_the_last_frame_again() {
+        let mut queue = FrameQueue::new();
+        queue.set_max_fps(Some(1));
+        let now = Instant::now();
+
+        queue.resend();
+        assert_eq!(queue.take(now), None);
+
+        queue.commit(vec![1]);
+        assert_eq!(queue.take(now), Some(vec![1]));
+
+        // Sent at once after a reconnect, ignoring the frame rate limit
+        queue.resend();
+        assert_eq!(queue.take(now), Some(vec![1]));
+    }
+
+    #[test]
+    fn transfer_times_are_averaged() {
+        let mut queue = FrameQueue::new();
+        queue.record_transfer(Duration::from_millis(2));
+        queue.record_transfer(Duration::from_millis(4));
+
+        let stats = queue.stats();
+        assert_eq!(stats.sent, 2);
+        assert_eq!(stats.last_transfer, Duration::from_millis(4));
+        assert_eq!(stats.average_transfer(), Duration::from_millis(3));
+    }
+}
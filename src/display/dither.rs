@@ -0,0 +1,180 @@
+///
+/// Conversion of 32-bit ARGB pixels into 1-bit pixels for the monochrome displays
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DitherMode {
+    ///
+    /// Pixel is lit when any colour channel reaches the threshold
+    ///
+    Threshold(u8),
+
+    ///
+    /// Pixel is lit when its perceived luminance reaches the threshold
+    ///
+    Luminance(u8),
+
+    ///
+    /// Ordered dithering using a 4x4 Bayer matrix
+    ///
+    Bayer,
+
+    ///
+    /// Floyd–Steinberg error diffusion
+    ///
+    FloydSteinberg,
+}
+
+impl Default for DitherMode {
+    /// Matches the behaviour of the original drivers, any channel at half brightness or more
+    fn default() -> Self {
+        DitherMode::Threshold(0x80)
+    }
+}
+
+/// 4x4 Bayer matrix, scaled to the 0..=255 range when used
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+///
+/// Convert a raqote ARGB buffer into a row-major set of 1-bit pixels
+///
+/// **Arguments**
+/// - data - Pixel data, as returned by `DrawTarget::get_data`
+/// - width - Width of the image in pixels
+/// - mode - Conversion to apply
+pub fn to_monochrome(data: &[u32], width: usize, mode: DitherMode) -> Vec<bool> {
+    match mode {
+        DitherMode::Threshold(threshold) => data
+            .iter()
+            .map(|&d| {
+                let (r, g, b) = channels(d);
+                r.max(g).max(b) >= threshold
+            })
+            .collect(),
+        DitherMode::Luminance(threshold) => {
+            data.iter().map(|&d| luminance(d) >= threshold).collect()
+        }
+        DitherMode::Bayer => data
+            .iter()
+            .enumerate()
+            .map(|(idx, &d)| {
+                let (x, y) = (idx % width, idx / width);
+                let level = BAYER_4X4[y % 4][x % 4] as u16 * 16 + 8;
+                luminance(d) as u16 >= level
+            })
+            .collect(),
+        DitherMode::FloydSteinberg => floyd_steinberg(data, width),
+    }
+}
+
+/// Error diffusion over the whole image, distributing the quantisation error to neighbours
+fn floyd_steinberg(data: &[u32], width: usize) -> Vec<bool> {
+    let mut levels: Vec<i16> = data.iter().map(|&d| luminance(d) as i16).collect();
+    let mut pixels = vec![false; data.len()];
+
+    for idx in 0..levels.len() {
+        let x = idx % width;
+        let old = levels[idx];
+        let lit = old >= 0x80;
+        let error = old - if lit { 0xFF } else { 0x00 };
+        pixels[idx] = lit;
+
+        let mut spread = |target: usize, weight: i16| {
+            if let Some(level) = levels.get_mut(target) {
+                *level += error * weight / 16;
+            }
+        };
+        if x + 1 < width {
+            spread(idx + 1, 7);
+            spread(idx + width + 1, 1);
+        }
+        if x > 0 {
+            spread(idx + width - 1, 3);
+        }
+        spread(idx + width, 5);
+    }
+
+    pixels
+}
+
+/// Split a (pre-multiplied) ARGB pixel into its colour channels
+fn channels(pixel: u32) -> (u8, u8, u8) {
+    ((pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8)
+}
+
+/// Perceived brightness of a pixel (ITU-R BT.601 weights)
+fn luminance(pixel: u32) -> u8 {
+    let (r, g, b) = channels(pixel);
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GREY: u32 = 0xFF808080;
+    const DARK_GREY: u32 = 0xFF404040;
+
+    /// Render a row-major set of pixels as rows of `#` and `.`
+    fn render(pixels: &[bool], width: usize) -> Vec<String> {
+        pixels
+            .chunks(width)
+            .map(|row| row.iter().map(|&lit| if lit { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn threshold_uses_the_brightest_channel() {
+        let data = [0xFF800000, 0xFF7F7F7F, 0xFF0000FF, 0xFF000000];
+        assert_eq!(
+            to_monochrome(&data, 4, DitherMode::Threshold(0x80)),
+            [true, false, true, false]
+        );
+        assert_eq!(
+            to_monochrome(&data, 4, DitherMode::Threshold(0x00)),
+            [true; 4]
+        );
+    }
+
+    #[test]
+    fn luminance_weights_the_channels() {
+        let data = [0xFF0000FF, 0xFF00FF00, GREY, 0xFF7F7F7F];
+        assert_eq!(
+            to_monochrome(&data, 4, DitherMode::Luminance(0x80)),
+            [false, true, true, false]
+        );
+    }
+
+    #[test]
+    fn bayer_dithers_half_grey_to_a_checkerboard() {
+        let pixels = to_monochrome(&[GREY; 16], 4, DitherMode::Bayer);
+        assert_eq!(render(&pixels, 4), ["#.#.", ".#.#", "#.#.", ".#.#"]);
+
+        assert_eq!(
+            to_monochrome(&[0xFF000000; 16], 4, DitherMode::Bayer),
+            [false; 16]
+        );
+        assert_eq!(
+            to_monochrome(&[0xFFFFFFFF; 16], 4, DitherMode::Bayer),
+            [true; 16]
+        );
+    }
+
+    #[test]
+    fn bayer_pattern_repeats_every_four_pixels() {
+        let pixels = to_monochrome(&[DARK_GREY; 48], 8, DitherMode::Bayer);
+        assert_eq!(
+            render(&pixels, 8),
+            ["#.#.#.#.", "........", "#.#.#.#.", "........", "#.#.#.#.", "........"]
+        );
+    }
+
+    #[test]
+    fn floyd_steinberg_diffuses_the_error() {
+        let pixels = to_monochrome(&[GREY; 16], 4, DitherMode::FloydSteinberg);
+        assert_eq!(render(&pixels, 4), ["#.#.", ".#.#", "#.#.", ".#.#"]);
+
+        let pixels = to_monochrome(&[DARK_GREY; 16], 4, DitherMode::FloydSteinberg);
+        assert_eq!(render(&pixels, 4), ["....", ".#.#", "....", ".#.."]);
+    }
+}
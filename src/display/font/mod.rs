@@ -0,0 +1,336 @@
+mod glyphs;
+
+use raqote::{DrawTarget, IntRect};
+
+const FIRST_CHAR: char = ' ';
+const LAST_CHAR: char = '\u{7F}';
+const FALLBACK_CHAR: char = '?';
+
+const PIXEL_ON: u32 = 0xFFFFFFFF;
+const PIXEL_OFF: u32 = 0xFF000000;
+
+///
+/// Fixed width bitmap font covering printable ASCII
+///
+#[derive(Debug, Copy, Clone)]
+pub struct BitmapFont {
+    width: u8,
+    height: u8,
+    glyphs: &'static [u8],
+}
+
+/// Small 5x7 font, 25 characters across the Mikro display
+pub const FONT_5X7: BitmapFont = BitmapFont {
+    width: 5,
+    height: 7,
+    glyphs: &glyphs::GLYPHS_5X7,
+};
+
+/// General purpose 6x10 font
+pub const FONT_6X10: BitmapFont = BitmapFont {
+    width: 6,
+    height: 10,
+    glyphs: &glyphs::GLYPHS_6X10,
+};
+
+/// Large 8x13 font, for headings and values
+pub const FONT_8X13: BitmapFont = BitmapFont {
+    width: 8,
+    height: 13,
+    glyphs: &glyphs::GLYPHS_8X13,
+};
+
+/// Extra large 10x20 font, three lines fit on a display
+pub const FONT_10X20: BitmapFont = BitmapFont {
+    width: 10,
+    height: 20,
+    glyphs: &glyphs::GLYPHS_10X20,
+};
+
+impl BitmapFont {
+    /// Width of a character cell in pixels
+    pub fn width(&self) -> usize {
+        self.width as usize
+    }
+
+    /// Height of a character cell in pixels
+    pub fn height(&self) -> usize {
+        self.height as usize
+    }
+
+    /// Width of a line of text in pixels
+    pub fn text_width(&self, text: &str) -> usize {
+        text.chars().count() * self.width()
+    }
+
+    /// Determine if a pixel of a character cell is lit, unsupported characters render as `?`
+    pub fn pixel(&self, c: char, x: usize, y: usize) -> bool {
+        if x >= self.width() || y >= self.height() {
+            return false;
+        }
+        let c = if (FIRST_CHAR..=LAST_CHAR).contains(&c) {
+            c
+        } else {
+            FALLBACK_CHAR
+        };
+        let row_size = self.width().div_ceil(8);
+        let glyph_size = row_size * self.height();
+        let row = (c as usize - FIRST_CHAR as usize) * glyph_size + y * row_size;
+        self.glyphs[row + x / 8] & (0x80 >> (x % 8)) != 0
+    }
+
+    /// Row the dots of `.` sit on, used to draw a compact ellipsis
+    fn dot_row(&self) -> usize {
+        (0..self.height())
+            .rev()
+            .find(|&y| (0..self.width()).any(|x| self.pixel('.', x, y)))
+            .unwrap_or(self.height() - 1)
+    }
+}
+
+///
+/// Horizontal placement of text within its bounds
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+///
+/// Handling of text that is wider than its bounds
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Overflow {
+    ///
+    /// Characters are cut off at the edge of the bounds
+    ///
+    #[default]
+    Clip,
+
+    ///
+    /// Text is truncated to whole characters followed by an ellipsis
+    ///
+    Ellipsis,
+}
+
+///
+/// Appearance of text drawn with `DrawText`
+///
+#[derive(Debug, Copy, Clone)]
+pub struct TextStyle {
+    pub font: BitmapFont,
+    pub alignment: Alignment,
+    pub overflow: Overflow,
+
+    /// Draw dark text on a lit background
+    pub inverted: bool,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font: FONT_6X10,
+            alignment: Alignment::default(),
+            overflow: Overflow::default(),
+            inverted: false,
+        }
+    }
+}
+
+///
+/// Pixel exact text rendering for the monochrome displays
+///
+pub trait DrawText {
+    ///
+    /// Draw a single line of text
+    ///
+    /// The bounds are filled with the background colour before the text is drawn, text is
+    /// centred vertically and nothing is drawn outside of the bounds.
+    ///
+    /// **Arguments**
+    /// - text - Text to draw
+    /// - bounds - Area the text is drawn within
+    /// - style - Font, alignment and overflow handling
+    fn draw_bitmap_text(&mut self, text: &str, bounds: IntRect, style: &TextStyle);
+}
+
+impl DrawText for DrawTarget {
+    fn draw_bitmap_text(&mut self, text: &str, bounds: IntRect, style: &TextStyle) {
+        let font = &style.font;
+        let (width, height) = (self.width(), self.height());
+        let bounds =
+            bounds.intersection_unchecked(&IntRect::new((0, 0).into(), (width, height).into()));
+        if bounds.is_empty() {
+            return;
+        }
+        let (on, off) = if style.inverted {
+            (PIXEL_OFF, PIXEL_ON)
+        } else {
+            (PIXEL_ON, PIXEL_OFF)
+        };
+
+        // Decide which characters fit, leaving room for the ellipsis if needed
+        let available = bounds.width() as usize / font.width();
+        let mut chars: Vec<char> = text.chars().collect();
+        let truncated = style.overflow == Overflow::Ellipsis && chars.len() > available;
+        if truncated {
+            chars.truncate(available.saturating_sub(1));
+        }
+        let text_width = (chars.len() + truncated as usize) * font.width();
+
+        let x0 = bounds.min.x
+            + match style.alignment {
+                Alignment::Left => 0,
+                Alignment::Center => (bounds.width() - text_width as i32) / 2,
+                Alignment::Right => bounds.width() - text_width as i32,
+            };
+        let y0 = bounds.min.y + (bounds.height() - font.height() as i32) / 2;
+        let dot_row = font.dot_row();
+
+        let data = self.get_data_mut();
+        for y in bounds.min.y..bounds.max.y {
+            for x in bounds.min.x..bounds.max.x {
+                let (cell_x, cell_y) = (x - x0, y - y0);
+                let lit = if cell_x < 0 || cell_y < 0 || cell_x >= text_width as i32 {
+                    false
+                } else {
+                    let (cell, x, y) = (
+                        cell_x as usize / font.width(),
+                        cell_x as usize % font.width(),
+                        cell_y as usize,
+                    );
+                    match chars.get(cell) {
+                        Some(&c) => font.pixel(c, x, y),
+                        // Ellipsis, three dots on the baseline
+                        None => y == dot_row && x % 2 == 0 && x < 5,
+                    }
+                };
+                data[(y * width + x) as usize] = if lit { on } else { off };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(width: i32, height: i32) -> IntRect {
+        IntRect::new((0, 0).into(), (width, height).into())
+    }
+
+    fn style(alignment: Alignment, overflow: Overflow) -> TextStyle {
+        TextStyle {
+            font: FONT_5X7,
+            alignment,
+            overflow,
+            inverted: false,
+        }
+    }
+
+    /// Lit columns of a row of the display
+    fn lit_columns(dt: &DrawTarget, y: i32) -> Vec<i32> {
+        (0..dt.width())
+            .filter(|&x| dt.get_data()[(y * dt.width() + x) as usize] == PIXEL_ON)
+            .collect()
+    }
+
+    #[test]
+    fn glyph_pixels() {
+        // '-' is the top four pixels of the fourth row
+        assert!((0..4).all(|x| FONT_5X7.pixel('-', x, 3)));
+        assert!(!FONT_5X7.pixel('-', 4, 3));
+        assert!(!FONT_5X7.pixel('-', 0, 2));
+        assert!(!FONT_5X7.pixel('-', 0, 7));
+
+        assert_eq!(FONT_5X7.dot_row(), 5);
+        assert_eq!(FONT_8X13.text_width("abc"), 24);
+    }
+
+    #[test]
+    fn unsupported_characters_render_as_question_mark() {
+        for font in [FONT_5X7, FONT_6X10, FONT_8X13, FONT_10X20] {
+            for y in 0..font.height() {
+                for x in 0..font.width() {
+                    assert_eq!(font.pixel('é', x, y), font.pixel('?', x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn text_is_aligned_within_the_bounds() {
+        let mut dt = DrawTarget::new(40, 10);
+        let row = 1 + 3;
+
+        dt.draw_bitmap_text("-", bounds(40, 10), &style(Alignment::Left, Overflow::Clip));
+        assert_eq!(lit_columns(&dt, row), [0, 1, 2, 3]);
+
+        dt.draw_bitmap_text(
+            "-",
+            bounds(40, 10),
+            &style(Alignment::Center, Overflow::Clip),
+        );
+        assert_eq!(lit_columns(&dt, row), [17, 18, 19, 20]);
+
+        dt.draw_bitmap_text(
+            "-",
+            bounds(40, 10),
+            &style(Alignment::Right, Overflow::Clip),
+        );
+        assert_eq!(lit_columns(&dt, row), [35, 36, 37, 38]);
+    }
+
+    #[test]
+    fn overflowing_text_is_clipped_to_the_bounds() {
+        let mut dt = DrawTarget::new(20, 10);
+        dt.draw_bitmap_text(
+            "----",
+            bounds(12, 10),
+            &style(Alignment::Left, Overflow::Clip),
+        );
+        assert_eq!(lit_columns(&dt, 4), [0, 1, 2, 3, 5, 6, 7, 8, 10, 11]);
+
+        // Nothing is drawn outside of the bounds, not even the background
+        assert!(dt.get_data()[12..20].iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    fn overflowing_text_ends_with_an_ellipsis() {
+        let mut dt = DrawTarget::new(20, 10);
+        dt.draw_bitmap_text(
+            "----",
+            bounds(15, 10),
+            &style(Alignment::Left, Overflow::Ellipsis),
+        );
+        assert_eq!(lit_columns(&dt, 4), [0, 1, 2, 3, 5, 6, 7, 8]);
+        assert_eq!(lit_columns(&dt, 6), [10, 12, 14]);
+
+        // Text that fits is drawn whole
+        dt.draw_bitmap_text(
+            "---",
+            bounds(15, 10),
+            &style(Alignment::Left, Overflow::Ellipsis),
+        );
+        assert_eq!(
+            lit_columns(&dt, 4),
+            [0, 1, 2, 3, 5, 6, 7, 8, 10, 11, 12, 13]
+        );
+        assert!(lit_columns(&dt, 6).is_empty());
+    }
+
+    #[test]
+    fn inverted_text_lights_the_background() {
+        let mut dt = DrawTarget::new(10, 10);
+        let style = TextStyle {
+            inverted: true,
+            ..style(Alignment::Left, Overflow::Clip)
+        };
+        dt.draw_bitmap_text("-", bounds(10, 10), &style);
+        assert_eq!(lit_columns(&dt, 4), [4, 5, 6, 7, 8, 9]);
+        assert_eq!(lit_columns(&dt, 0).len(), 10);
+    }
+}
@@ -0,0 +1,259 @@
+use super::PixelLayout;
+
+///
+/// Packed 1-bit image stored in the byte layout of a display
+///
+/// Drawing into a framebuffer skips the colour conversion and packing stages, the data is
+/// sent to the display as is.
+///
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    layout: PixelLayout,
+    data: Vec<u8>,
+}
+
+impl Framebuffer {
+    ///
+    /// Create a blank framebuffer
+    ///
+    /// **Arguments**
+    /// - width - Width in pixels
+    /// - height - Height in pixels
+    /// - layout - Pixel layout of the display
+    pub fn new(width: usize, height: usize, layout: PixelLayout) -> Self {
+        Self {
+            width,
+            height,
+            layout,
+            data: vec![0; layout.row_size(width) * layout.row_count(height)],
+        }
+    }
+
+    /// Width of the framebuffer in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height of the framebuffer in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Pixel layout of the packed data
+    pub fn layout(&self) -> PixelLayout {
+        self.layout
+    }
+
+    /// Packed pixel data
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Turn every pixel on or off
+    pub fn clear(&mut self, lit: bool) {
+        self.data.fill(if lit { 0xFF } else { 0x00 });
+    }
+
+    /// Determine if a pixel is lit
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        match self.locate(x, y) {
+            Some((idx, mask)) => self.data[idx] & mask != 0,
+            None => false,
+        }
+    }
+
+    /// Set a pixel, pixels outside of the framebuffer are ignored
+    pub fn set_pixel(&mut self, x: usize, y: usize, lit: bool) {
+        if let Some((idx, mask)) = self.locate(x, y) {
+            if lit {
+                self.data[idx] |= mask;
+            } else {
+                self.data[idx] &= !mask;
+            }
+        }
+    }
+
+    /// Byte index and bit mask of a pixel
+    fn locate(&self, x: usize, y: usize) -> Option<(usize, u8)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let row_size = self.layout.row_size(self.width);
+        Some(match self.layout {
+            PixelLayout::Horizontal => (y * row_size + x / 8, 0x80 >> (x % 8)),
+            PixelLayout::Paged => ((y / 8) * row_size + x, 1 << (y % 8)),
+        })
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+mod embedded {
+    use super::Framebuffer;
+    use embedded_graphics_core::{
+        draw_target::DrawTarget,
+        geometry::{OriginDimensions, Size},
+        pixelcolor::BinaryColor,
+        Pixel,
+    };
+
+    impl OriginDimensions for Framebuffer {
+        fn size(&self) -> Size {
+            Size::new(self.width as u32, self.height as u32)
+        }
+    }
+
+    impl DrawTarget for Framebuffer {
+        type Color = BinaryColor;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                if point.x >= 0 && point.y >= 0 {
+                    self.set_pixel(point.x as usize, point.y as usize, color.is_on());
+                }
+            }
+            Ok(())
+        }
+
+        fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+            Framebuffer::clear(self, color.is_on());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::pack;
+
+    /// Diagonal line with a lit corner, crossing byte and page boundaries
+    fn fixture_pixels(width: usize, height: usize) -> Vec<bool> {
+        (0..width * height)
+            .map(|idx| {
+                let (x, y) = (idx % width, idx / width);
+                x == y || (x == width - 1 && y == 0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pixels_match_pack() {
+        for layout in [PixelLayout::Horizontal, PixelLayout::Paged] {
+            let (width, height) = (24, 16);
+            let pixels = fixture_pixels(width, height);
+
+            let mut framebuffer = Framebuffer::new(width, height, layout);
+            for (idx, lit) in pixels.iter().enumerate() {
+                framebuffer.set_pixel(idx % width, idx / width, *lit);
+            }
+            assert_eq!(framebuffer.data(), pack(&pixels, width, layout));
+
+            for (idx, lit) in pixels.iter().enumerate() {
+                assert_eq!(framebuffer.pixel(idx % width, idx / width), *lit);
+            }
+        }
+    }
+
+    #[test]
+    fn set_pixel_only_changes_its_bit() {
+        let mut framebuffer = Framebuffer::new(16, 16, PixelLayout::Horizontal);
+        framebuffer.clear(true);
+        framebuffer.set_pixel(9, 1, false);
+        assert_eq!(framebuffer.data()[3], 0b1011_1111);
+        assert_eq!(
+            framebuffer
+                .data()
+                .iter()
+                .filter(|&&byte| byte != 0xFF)
+                .count(),
+            1
+        );
+
+        let mut framebuffer = Framebuffer::new(16, 16, PixelLayout::Paged);
+        framebuffer.set_pixel(2, 9, true);
+        assert_eq!(framebuffer.data()[16 + 2], 0b0000_0010);
+        framebuffer.set_pixel(2, 9, false);
+        assert!(framebuffer.data().iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn pixels_outside_are_ignored() {
+        let mut framebuffer = Framebuffer::new(8, 8, PixelLayout::Paged);
+        framebuffer.set_pixel(8, 0, true);
+        framebuffer.set_pixel(0, 8, true);
+        assert!(framebuffer.data().iter().all(|&byte| byte == 0));
+        assert!(!framebuffer.pixel(8, 0));
+    }
+
+    #[cfg(feature = "embedded-graphics")]
+    mod embedded {
+        use super::*;
+        use embedded_graphics::{
+            pixelcolor::BinaryColor,
+            prelude::*,
+            primitives::{Line, PrimitiveStyle, Rectangle},
+        };
+
+        /// Lit pixels as (x, y), in row order
+        fn lit(framebuffer: &Framebuffer) -> Vec<(usize, usize)> {
+            (0..framebuffer.height())
+                .flat_map(|y| (0..framebuffer.width()).map(move |x| (x, y)))
+                .filter(|&(x, y)| framebuffer.pixel(x, y))
+                .collect()
+        }
+
+        #[test]
+        fn primitives_are_clipped_to_the_framebuffer() {
+            for layout in [PixelLayout::Horizontal, PixelLayout::Paged] {
+                let mut framebuffer = Framebuffer::new(8, 8, layout);
+                Rectangle::new(Point::new(6, 5), Size::new(10, 10))
+                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                    .draw(&mut framebuffer)
+                    .unwrap();
+                assert_eq!(
+                    lit(&framebuffer),
+                    vec![(6, 5), (7, 5), (6, 6), (7, 6), (6, 7), (7, 7)]
+                );
+            }
+        }
+
+        #[test]
+        fn negative_coordinates_are_clipped() {
+            for layout in [PixelLayout::Horizontal, PixelLayout::Paged] {
+                let mut framebuffer = Framebuffer::new(8, 8, layout);
+                Rectangle::new(Point::new(-3, -2), Size::new(5, 4))
+                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                    .draw(&mut framebuffer)
+                    .unwrap();
+                assert_eq!(lit(&framebuffer), vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+
+                // A line from off screen only lights the part inside
+                let mut framebuffer = Framebuffer::new(8, 8, layout);
+                Line::new(Point::new(-4, 3), Point::new(2, 3))
+                    .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                    .draw(&mut framebuffer)
+                    .unwrap();
+                assert_eq!(lit(&framebuffer), vec![(0, 3), (1, 3), (2, 3)]);
+            }
+        }
+
+        #[test]
+        fn clear_fills_every_pixel() {
+            for layout in [PixelLayout::Horizontal, PixelLayout::Paged] {
+                let mut framebuffer = Framebuffer::new(16, 16, layout);
+                framebuffer.set_pixel(3, 4, true);
+                DrawTarget::clear(&mut framebuffer, BinaryColor::On).unwrap();
+                assert!(framebuffer.data().iter().all(|&byte| byte == 0xFF));
+
+                DrawTarget::clear(&mut framebuffer, BinaryColor::Off).unwrap();
+                assert!(framebuffer.data().iter().all(|&byte| byte == 0));
+            }
+        }
+    }
+}
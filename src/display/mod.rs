@@ -0,0 +1,23 @@
+#[cfg(feature = "image")]
+mod bitmap;
+mod compositor;
+mod dither;
+pub mod font;
+mod framebuffer;
+mod packing;
+mod settings;
+mod simulator;
+mod span;
+mod surface;
+
+#[cfg(feature = "image")]
+pub use bitmap::{Animation, Bitmap, Fit, Image};
+pub use compositor::DisplayStats;
+pub use dither::{to_monochrome, DitherMode};
+pub use font::{Alignment, BitmapFont, DrawText, Overflow, TextStyle};
+pub use framebuffer::Framebuffer;
+pub use packing::{encode_reports, pack, unpack, PixelLayout};
+pub use settings::DisplaySettings;
+pub use simulator::{SimulatedDisplay, Simulator};
+pub use span::SpanCanvas;
+pub use surface::Surface;
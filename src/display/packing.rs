@@ -0,0 +1,305 @@
+/// Maximum number of pixel data bytes carried by a single display report
+const MAX_REPORT_DATA: usize = 256;
+
+///
+/// Arrangement of pixels within the bytes of a display report
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PixelLayout {
+    ///
+    /// Each byte holds 8 horizontally adjacent pixels, most significant bit on the left.
+    /// The report row is a pixel line and the column count is in bytes (Maschine Mk2).
+    ///
+    Horizontal,
+
+    ///
+    /// Each byte holds 8 vertically adjacent pixels, least significant bit at the top.
+    /// The report row is an 8 pixel high page and the column count is in pixels
+    /// (Maschine Mikro Mk2).
+    ///
+    Paged,
+}
+
+impl PixelLayout {
+    /// Number of bytes making up one report row of an image
+    pub fn row_size(&self, width: usize) -> usize {
+        match self {
+            PixelLayout::Horizontal => width / 8,
+            PixelLayout::Paged => width,
+        }
+    }
+
+    /// Number of report rows making up an image
+    pub fn row_count(&self, height: usize) -> usize {
+        match self {
+            PixelLayout::Horizontal => height,
+            PixelLayout::Paged => height / 8,
+        }
+    }
+}
+
+///
+/// Pack row-major 1-bit pixels into the byte layout expected by a display
+///
+/// **Arguments**
+/// - pixels - Row-major pixels, `width * height` in length
+/// - width - Width of the image in pixels (multiple of 8 for `Horizontal`)
+/// - layout - Layout of the target display
+pub fn pack(pixels: &[bool], width: usize, layout: PixelLayout) -> Vec<u8> {
+    let height = pixels.len() / width;
+    let row_size = layout.row_size(width);
+    let mut data = vec![0u8; row_size * layout.row_count(height)];
+
+    for (idx, _) in pixels.iter().enumerate().filter(|(_, &lit)| lit) {
+        let (x, y) = (idx % width, idx / width);
+        match layout {
+            PixelLayout::Horizontal => data[y * row_size + x / 8] |= 0x80 >> (x % 8),
+            PixelLayout::Paged => data[(y / 8) * row_size + x] |= 1 << (y % 8),
+        }
+    }
+
+    data
+}
+
+///
+/// Unpack display bytes back into row-major 1-bit pixels
+///
+/// **Arguments**
+/// - data - Packed pixel data
+/// - width - Width of the image in pixels
+/// - layout - Layout of the packed data
+pub fn unpack(data: &[u8], width: usize, layout: PixelLayout) -> Vec<bool> {
+    let row_size = layout.row_size(width);
+    let height = match layout {
+        PixelLayout::Horizontal => data.len() / row_size,
+        PixelLayout::Paged => data.len() / row_size * 8,
+    };
+
+    (0..width * height)
+        .map(|idx| {
+            let (x, y) = (idx % width, idx / width);
+            match layout {
+                PixelLayout::Horizontal => data[y * row_size + x / 8] & (0x80 >> (x % 8)) != 0,
+                PixelLayout::Paged => data[(y / 8) * row_size + x] & (1 << (y % 8)) != 0,
+            }
+        })
+        .collect()
+}
+
+///
+/// Split packed display data into the HID reports written to the device
+///
+/// Each report addresses as many complete rows as fit within 256 bytes of pixel data.
+///
+/// **Arguments**
+/// - display_addr - Report address of the display
+/// - data - Packed pixel data, as produced by `pack`
+/// - width - Width of the image in pixels
+/// - layout - Layout of the packed data
+pub fn encode_reports(
+    display_addr: u8,
+    data: &[u8],
+    width: usize,
+    layout: PixelLayout,
+) -> Vec<Vec<u8>> {
+    let row_size = layout.row_size(width);
+    let rows_per_report = MAX_REPORT_DATA / row_size;
+
+    data.chunks(row_size * rows_per_report)
+        .enumerate()
+        .map(|(chunk, chunk_data)| {
+            let mut buffer: Vec<u8> = vec![
+                display_addr,
+                0x00,                                // Column offset
+                0x00,                                // ?
+                (chunk * rows_per_report) as u8,     // First row
+                0x00,                                // ?
+                row_size as u8,                      // Columns per row
+                0x00,                                // ?
+                (chunk_data.len() / row_size) as u8, // Number of rows
+                0x00,                                // ?
+            ];
+            buffer.extend_from_slice(chunk_data);
+            buffer
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{to_monochrome, DitherMode, Simulator};
+    use crate::{MaschineMikroMk2, MaschineMk2};
+    use raqote::{DrawOptions, DrawTarget, PathBuilder, SolidSource, Source};
+
+    /// Display image with a filled rectangle that crosses page boundaries
+    fn fixture_display(width: i32) -> DrawTarget {
+        let mut dt = DrawTarget::new(width, 64);
+        let mut pb = PathBuilder::new();
+        pb.rect(3., 5., 37., 21.);
+        dt.fill(
+            &pb.finish(),
+            &Source::Solid(SolidSource::from_unpremultiplied_argb(
+                0xFF, 0xFF, 0xFF, 0xFF,
+            )),
+            &DrawOptions::new(),
+        );
+        dt
+    }
+
+    /// Decode frame reports through the simulator and compare against the source pixels
+    fn assert_simulated(mut simulator: Simulator, display: &DrawTarget, reports: Vec<Vec<u8>>) {
+        for report in reports {
+            simulator.write_report(&report).unwrap();
+        }
+
+        let width = display.width() as usize;
+        let expected = to_monochrome(display.get_data(), width, DitherMode::default());
+        let simulated = simulator.display(0).unwrap();
+        for (idx, lit) in expected.iter().enumerate() {
+            assert_eq!(simulated.pixel(idx % width, idx / width), *lit);
+        }
+    }
+
+    /// 16x16 image with the top-left 8x8 block lit and the bottom-right pixel lit
+    fn fixture_image() -> Vec<bool> {
+        (0..16 * 16)
+            .map(|idx| {
+                let (x, y) = (idx % 16, idx / 16);
+                (x < 8 && y < 8) || (x == 15 && y == 15)
+            })
+            .collect()
+    }
+
+    #[rustfmt::skip]
+    const FIXTURE_HORIZONTAL: [u8; 32] = [
+        0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00,
+        0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    ];
+
+    #[rustfmt::skip]
+    const FIXTURE_PAGED: [u8; 32] = [
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+    ];
+
+    #[test]
+    fn pack_horizontal_matches_fixture() {
+        assert_eq!(
+            pack(&fixture_image(), 16, PixelLayout::Horizontal),
+            FIXTURE_HORIZONTAL
+        );
+    }
+
+    #[test]
+    fn pack_paged_matches_fixture() {
+        assert_eq!(
+            pack(&fixture_image(), 16, PixelLayout::Paged),
+            FIXTURE_PAGED
+        );
+    }
+
+    #[test]
+    fn pack_bit_order() {
+        let mut pixels = vec![false; 16 * 8];
+        pixels[0] = true; // (0, 0)
+        pixels[7 * 16 + 1] = true; // (1, 7)
+
+        let horizontal = pack(&pixels, 16, PixelLayout::Horizontal);
+        assert_eq!(horizontal[0], 0x80);
+        assert_eq!(horizontal[7 * 2], 0x40);
+
+        let paged = pack(&pixels, 16, PixelLayout::Paged);
+        assert_eq!(paged[0], 0x01);
+        assert_eq!(paged[1], 0x80);
+    }
+
+    #[test]
+    fn unpack_round_trip() {
+        for layout in [PixelLayout::Horizontal, PixelLayout::Paged] {
+            let pixels = fixture_image();
+            assert_eq!(unpack(&pack(&pixels, 16, layout), 16, layout), pixels);
+        }
+    }
+
+    #[test]
+    fn reports_mk2_layout() {
+        let data = pack(&vec![false; 256 * 64], 256, PixelLayout::Horizontal);
+        let reports = encode_reports(0xE1, &data, 256, PixelLayout::Horizontal);
+
+        assert_eq!(reports.len(), 8);
+        for (chunk, report) in reports.iter().enumerate() {
+            assert_eq!(
+                report[..9],
+                [
+                    0xE1,
+                    0x00,
+                    0x00,
+                    (chunk * 8) as u8,
+                    0x00,
+                    0x20,
+                    0x00,
+                    0x08,
+                    0x00
+                ]
+            );
+            assert_eq!(report.len(), 9 + 256);
+        }
+    }
+
+    #[test]
+    fn reports_mikro_mk2_layout() {
+        let data = pack(&vec![false; 128 * 64], 128, PixelLayout::Paged);
+        let reports = encode_reports(0xE0, &data, 128, PixelLayout::Paged);
+
+        assert_eq!(reports.len(), 4);
+        for (chunk, report) in reports.iter().enumerate() {
+            assert_eq!(
+                report[..9],
+                [
+                    0xE0,
+                    0x00,
+                    0x00,
+                    (chunk * 2) as u8,
+                    0x00,
+                    0x80,
+                    0x00,
+                    0x02,
+                    0x00
+                ]
+            );
+            assert_eq!(report.len(), 9 + 256);
+        }
+    }
+
+    #[test]
+    fn reports_carry_packed_data() {
+        let mut pixels = vec![false; 128 * 64];
+        pixels[17 * 128 + 5] = true; // (5, 17): page 2, bit 1
+        let data = pack(&pixels, 128, PixelLayout::Paged);
+        let reports = encode_reports(0xE0, &data, 128, PixelLayout::Paged);
+
+        // Page 2 is the first row of the second report
+        assert_eq!(reports[1][9 + 5], 0x02);
+        let lit_bytes = reports.iter().flat_map(|report| &report[9..]);
+        assert_eq!(lit_bytes.filter(|&&b| b != 0).count(), 1);
+    }
+
+    #[test]
+    fn mk2_frame_decodes_in_simulator() {
+        let display = fixture_display(256);
+        let reports = MaschineMk2::encode_frame(0, &display, DitherMode::default());
+        assert_simulated(Simulator::mk2(), &display, reports);
+    }
+
+    #[test]
+    fn mikro_mk2_frame_decodes_in_simulator() {
+        let display = fixture_display(128);
+        let reports = MaschineMikroMk2::encode_frame(&display, DitherMode::default());
+        assert_simulated(Simulator::mikro_mk2(), &display, reports);
+    }
+}
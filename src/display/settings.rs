@@ -0,0 +1,32 @@
+///
+/// Hardware settings of a display
+///
+/// Not supported yet: the display command report is not documented by Native Instruments and
+/// has not been captured from its software, so no device accepts these settings.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplaySettings {
+    /// LCD contrast (electronic volume)
+    pub contrast: u8,
+
+    /// Backlight brightness, 0 is off
+    pub brightness: u8,
+
+    /// Swap lit and unlit pixels in the controller
+    pub inverted: bool,
+
+    /// Display is powered on
+    pub powered: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            contrast: 0x80,
+            brightness: 0xFF,
+            inverted: false,
+            powered: true,
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use super::PixelLayout;
+use crate::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const DISPLAY_ADDR: u8 = 0xE0;
+const REPORT_HEADER_SIZE: usize = 9;
+
+///
+/// Simulated monochrome display
+///
+/// Decodes the display reports written to a device back into an image
+///
+pub struct SimulatedDisplay {
+    width: usize,
+    height: usize,
+    layout: PixelLayout,
+    pixels: Vec<bool>,
+}
+
+impl SimulatedDisplay {
+    ///
+    /// Create a blank display
+    ///
+    /// **Arguments**
+    /// - width - Width in pixels
+    /// - height - Height in pixels
+    /// - layout - Pixel layout expected by the display controller
+    pub fn new(width: usize, height: usize, layout: PixelLayout) -> Self {
+        Self {
+            width,
+            height,
+            layout,
+            pixels: vec![false; width * height],
+        }
+    }
+
+    /// Width of the display in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height of the display in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Determine if a pixel is lit
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels[y * self.width + x]
+    }
+
+    ///
+    /// Apply a display report as it would be written to the device
+    ///
+    /// **Arguments**
+    /// - report - Complete report, including the address and header
+    pub fn write_report(&mut self, report: &[u8]) -> Result<(), Error> {
+        if report.len() < REPORT_HEADER_SIZE || (report[0] & 0xF0) != DISPLAY_ADDR {
+            return Err(Error::InvalidReport);
+        }
+
+        let column_offset = report[1] as usize;
+        let first_row = report[3] as usize;
+        let columns = report[5] as usize;
+        let rows = report[7] as usize;
+        let data = &report[REPORT_HEADER_SIZE..];
+        if data.len() < columns * rows {
+            return Err(Error::InvalidReport);
+        }
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let byte = data[row * columns + column];
+                for bit in 0..8 {
+                    let (x, y, lit) = match self.layout {
+                        PixelLayout::Horizontal => (
+                            (column_offset + column) * 8 + bit,
+                            first_row + row,
+                            byte & (0x80 >> bit) != 0,
+                        ),
+                        PixelLayout::Paged => (
+                            column_offset + column,
+                            (first_row + row) * 8 + bit,
+                            byte & (1 << bit) != 0,
+                        ),
+                    };
+                    if x >= self.width || y >= self.height {
+                        return Err(Error::InvalidReport);
+                    }
+                    self.pixels[y * self.width + x] = lit;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Render the display into an RGBA buffer (4 bytes per pixel, row-major)
+    ///
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|&lit| {
+                if lit {
+                    [0xFF, 0xFF, 0xFF, 0xFF]
+                } else {
+                    [0x00, 0x00, 0x00, 0xFF]
+                }
+            })
+            .collect()
+    }
+
+    ///
+    /// Encode the display as a PNG image
+    ///
+    /// **Arguments**
+    /// - writer - Destination of the encoded image
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.to_rgba())?;
+
+        Ok(())
+    }
+
+    ///
+    /// Save the display as a PNG file
+    ///
+    /// **Arguments**
+    /// - path - File to write
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.write_png(BufWriter::new(File::create(path)?))
+    }
+}
+
+///
+/// Simulated set of device displays
+///
+/// Routes display reports to the display addressed in the report
+///
+pub struct Simulator {
+    displays: Vec<SimulatedDisplay>,
+}
+
+impl Simulator {
+    /// Displays of a Maschine Mk2, two 256x64 panels
+    pub fn mk2() -> Self {
+        Self {
+            displays: vec![
+                SimulatedDisplay::new(256, 64, PixelLayout::Horizontal),
+                SimulatedDisplay::new(256, 64, PixelLayout::Horizontal),
+            ],
+        }
+    }
+
+    /// Display of a Maschine Mikro Mk2, a single 128x64 panel
+    pub fn mikro_mk2() -> Self {
+        Self {
+            displays: vec![SimulatedDisplay::new(128, 64, PixelLayout::Paged)],
+        }
+    }
+
+    ///
+    /// Apply a display report as it would be written to the device
+    ///
+    /// **Arguments**
+    /// - report - Complete report, including the address and header
+    pub fn write_report(&mut self, report: &[u8]) -> Result<(), Error> {
+        let display_idx = report.first().ok_or(Error::InvalidReport)? & 0x0F;
+        self.displays
+            .get_mut(display_idx as usize)
+            .ok_or(Error::InvalidDisplay(display_idx))?
+            .write_report(report)
+    }
+
+    ///
+    /// Get a specific simulated display
+    ///
+    /// **Arguments**
+    /// - display_idx - Display index
+    pub fn display(&self, display_idx: u8) -> Result<&SimulatedDisplay, Error> {
+        self.displays
+            .get(display_idx as usize)
+            .ok_or(Error::InvalidDisplay(display_idx))
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{Device, Error};
+use raqote::DrawTarget;
+
+///
+/// Single canvas spanning all displays of a device side by side
+///
+/// On the Mk2 this is a 512x64 canvas, the left half is shown on display 0 and the right half
+/// on display 1. Devices with one display get a canvas the size of that display.
+///
+pub struct SpanCanvas {
+    canvas: DrawTarget,
+    panels: Vec<(u8, i32)>,
+}
+
+impl SpanCanvas {
+    ///
+    /// Create a blank canvas sized to the displays of a device
+    ///
+    /// **Arguments**
+    /// - device - Device owning the displays
+    pub fn new<D: Device + ?Sized>(device: &D) -> Self {
+        let displays = device.capabilities().displays;
+        let panels: Vec<(u8, i32)> = displays
+            .iter()
+            .enumerate()
+            .map(|(display_idx, display)| (display_idx as u8, display.width as i32))
+            .collect();
+        let width = panels.iter().map(|(_, width)| width).sum();
+        let height = displays.iter().map(|display| display.height).min();
+
+        Self {
+            canvas: DrawTarget::new(width, height.unwrap_or(0) as i32),
+            panels,
+        }
+    }
+
+    /// Width of the canvas in pixels
+    pub fn width(&self) -> i32 {
+        self.canvas.width()
+    }
+
+    /// Height of the canvas in pixels
+    pub fn height(&self) -> i32 {
+        self.canvas.height()
+    }
+
+    /// Canvas to draw on
+    pub fn canvas(&mut self) -> &mut DrawTarget {
+        &mut self.canvas
+    }
+
+    ///
+    /// Copy each part of the canvas to its display and commit them
+    ///
+    /// **Arguments**
+    /// - device - Device owning the displays
+    pub fn flush<D: Device + ?Sized>(&self, device: &mut D) -> Result<(), Error> {
+        let src = self.canvas.get_data();
+        let src_width = self.canvas.width() as usize;
+
+        let mut offset = 0;
+        for &(display_idx, width) in self.panels.iter() {
+            let width = width as usize;
+            let dt = device.get_display(display_idx)?;
+            let dst = dt.get_data_mut();
+            for y in 0..self.canvas.height() as usize {
+                let row = y * src_width + offset;
+                dst[y * width..(y + 1) * width].copy_from_slice(&src[row..row + width]);
+            }
+            device.commit_display(display_idx)?;
+            offset += width;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::testing::TestDevice;
+    use std::time::Instant;
+
+    const LIT: u32 = 0xFFFFFFFF;
+
+    #[test]
+    fn canvas_spans_the_displays() {
+        let device = TestDevice::new().unwrap();
+        let span = SpanCanvas::new(&device);
+        assert_eq!((span.width(), span.height()), (512, 64));
+    }
+
+    #[test]
+    fn flush_copies_each_half_to_its_display() {
+        let mut device = TestDevice::new().unwrap();
+        let mut span = SpanCanvas::new(&device);
+        let data = span.canvas().get_data_mut();
+        data[2 * 512 + 10] = LIT;
+        data[3 * 512 + 300] = LIT;
+        data[63 * 512 + 511] = LIT;
+        span.flush(&mut device).unwrap();
+
+        let left = device.get_display(0).unwrap().get_data();
+        assert_eq!(left[2 * 256 + 10], LIT);
+        assert_eq!(left.iter().filter(|&&pixel| pixel == LIT).count(), 1);
+
+        let right = device.get_display(1).unwrap().get_data();
+        assert_eq!(right[3 * 256 + 44], LIT);
+        assert_eq!(right[63 * 256 + 255], LIT);
+        assert_eq!(right.iter().filter(|&&pixel| pixel == LIT).count(), 2);
+    }
+
+    #[test]
+    fn flush_commits_every_display() {
+        let mut device = TestDevice::new().unwrap();
+        for display_idx in 0..2 {
+            device.set_auto_commit(display_idx, false).unwrap();
+        }
+        SpanCanvas::new(&device).flush(&mut device).unwrap();
+
+        let now = Instant::now();
+        for surface in device.surfaces_mut() {
+            assert!(surface.take_frame(now).is_some());
+        }
+    }
+}
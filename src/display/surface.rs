@@ -0,0 +1,165 @@
+use super::compositor::FrameQueue;
+use super::{pack, to_monochrome, DisplayStats, DitherMode, Framebuffer, PixelLayout};
+use raqote::DrawTarget;
+use std::time::{Duration, Instant};
+
+///
+/// Image state of a single device display
+///
+/// A display is drawn through either its raqote canvas or its packed framebuffer, these are
+/// back buffers. Committing copies whichever was accessed last into the front buffer that is
+/// sent to the device, so a frame is never sent half drawn. With auto commit a back buffer that
+/// was accessed is committed when the next frame is taken.
+///
+pub struct Surface {
+    canvas: DrawTarget,
+    framebuffer: Framebuffer,
+    use_framebuffer: bool,
+    dither_mode: DitherMode,
+    auto_commit: bool,
+    drawn: bool,
+    queue: FrameQueue,
+}
+
+impl Surface {
+    ///
+    /// Create a blank display
+    ///
+    /// **Arguments**
+    /// - width - Width in pixels
+    /// - height - Height in pixels
+    /// - layout - Pixel layout of the display reports
+    pub fn new(width: usize, height: usize, layout: PixelLayout) -> Self {
+        Self {
+            canvas: DrawTarget::new(width as i32, height as i32),
+            framebuffer: Framebuffer::new(width, height, layout),
+            use_framebuffer: false,
+            dither_mode: DitherMode::default(),
+            auto_commit: true,
+            drawn: true,
+            queue: FrameQueue::new(),
+        }
+    }
+
+    /// Width of the display in pixels
+    pub fn width(&self) -> usize {
+        self.framebuffer.width()
+    }
+
+    /// Height of the display in pixels
+    pub fn height(&self) -> usize {
+        self.framebuffer.height()
+    }
+
+    /// Access the colour canvas, marking it as the image to commit
+    pub fn canvas(&mut self) -> &mut DrawTarget {
+        self.use_framebuffer = false;
+        self.drawn = true;
+        &mut self.canvas
+    }
+
+    /// Access the packed framebuffer, marking it as the image to commit
+    pub fn framebuffer(&mut self) -> &mut Framebuffer {
+        self.use_framebuffer = true;
+        self.drawn = true;
+        &mut self.framebuffer
+    }
+
+    /// Change how the canvas is converted into 1-bit pixels on commit
+    pub fn set_dither_mode(&mut self, mode: DitherMode) {
+        self.dither_mode = mode;
+        if !self.use_framebuffer {
+            self.drawn = true;
+        }
+    }
+
+    /// Commit the back buffer automatically after it was accessed, or only on `commit`
+    pub fn set_auto_commit(&mut self, auto_commit: bool) {
+        self.auto_commit = auto_commit;
+    }
+
+    /// Copy the back buffer into the front buffer to be sent
+    pub fn commit(&mut self) {
+        let data = if self.use_framebuffer {
+            self.framebuffer.data().to_vec()
+        } else {
+            let width = self.width();
+            let pixels = to_monochrome(self.canvas.get_data(), width, self.dither_mode);
+            pack(&pixels, width, self.framebuffer.layout())
+        };
+        self.queue.commit(data);
+        self.drawn = false;
+    }
+
+    /// Limit how often committed frames are sent
+    pub fn set_max_fps(&mut self, fps: Option<u32>) {
+        self.queue.set_max_fps(fps);
+    }
+
+    /// Frame statistics
+    pub fn stats(&self) -> DisplayStats {
+        self.queue.stats()
+    }
+
+    /// Mark the front buffer to be sent again, eg after a reconnect
+    pub fn invalidate(&mut self) {
+        self.queue.resend();
+    }
+
+    /// Packed image data if a committed frame is due to be sent
+    pub fn take_frame(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if self.auto_commit && self.drawn {
+            self.commit();
+        }
+        self.queue.take(now)
+    }
+
+    /// Record the time taken to write a frame to the device
+    pub fn record_transfer(&mut self, duration: Duration) {
+        self.queue.record_transfer(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drawing_is_committed_automatically() {
+        let mut surface = Surface::new(16, 8, PixelLayout::Paged);
+        let now = Instant::now();
+
+        // The blank display is sent at startup
+        assert_eq!(surface.take_frame(now), Some(vec![0; 16]));
+        assert_eq!(surface.take_frame(now), None);
+
+        surface.framebuffer().set_pixel(1, 0, true);
+        let mut expected = vec![0; 16];
+        expected[1] = 0x01;
+        assert_eq!(surface.take_frame(now), Some(expected.clone()));
+        assert_eq!(surface.take_frame(now), None);
+
+        // Changing the dither mode converts the canvas again
+        surface.canvas();
+        surface.set_dither_mode(DitherMode::Bayer);
+        assert_eq!(surface.take_frame(now), Some(vec![0; 16]));
+        assert_eq!(surface.stats().committed, 3);
+    }
+
+    #[test]
+    fn manual_commit_only_sends_committed_frames() {
+        let mut surface = Surface::new(16, 8, PixelLayout::Paged);
+        surface.set_auto_commit(false);
+        let now = Instant::now();
+        assert_eq!(surface.take_frame(now), None);
+
+        surface.framebuffer().set_pixel(0, 0, true);
+        assert_eq!(surface.take_frame(now), None);
+
+        surface.commit();
+        surface.framebuffer().set_pixel(0, 0, false);
+        let mut expected = vec![0; 16];
+        expected[0] = 0x01;
+        assert_eq!(surface.take_frame(now), Some(expected));
+    }
+}
@@ -8,6 +8,16 @@ use hidapi::HidError;
 pub enum Error {
     HidAPI(HidError),
 
+    /// File or stream access failed
+    Io(std::io::Error),
+
+    /// Image could not be encoded
+    Png(png::EncodingError),
+
+    /// Image could not be decoded
+    #[cfg(feature = "image")]
+    Image(image::ImageError),
+
     /// Input buffer does not container the expected amount of data.
     InvalidReport,
 
@@ -19,12 +29,25 @@ pub enum Error {
 
     /// No devices found
     NoDevices,
+
+    /// Button name is not recognised (name)
+    UnknownButton(String),
+
+    /// Mapping profile could not be parsed or does not suit the device (reason)
+    InvalidMapping(String),
+
+    /// Feature is not supported by the device (feature)
+    Unsupported(String),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Error::HidAPI(e) => e.fmt(fmt), // Pass on to HIDAPI interface
+            Error::Io(e) => e.fmt(fmt),
+            Error::Png(e) => e.fmt(fmt),
+            #[cfg(feature = "image")]
+            Error::Image(e) => e.fmt(fmt),
             Error::InvalidReport => {
                 write!(fmt, "Report is either two small or not parsable")
             }
@@ -37,6 +60,15 @@ impl std::fmt::Display for Error {
             Error::NoDevices => {
                 write!(fmt, "No compatible device detected")
             }
+            Error::UnknownButton(name) => {
+                write!(fmt, "Unknown button name {name:?}")
+            }
+            Error::InvalidMapping(reason) => {
+                write!(fmt, "Invalid mapping profile: {reason}")
+            }
+            Error::Unsupported(feature) => {
+                write!(fmt, "Device does not support {feature}")
+            }
         }
     }
 }
@@ -46,3 +78,22 @@ impl From<HidError> for Error {
         Error::HidAPI(err)
     }
 }
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<png::EncodingError> for Error {
+    fn from(err: png::EncodingError) -> Error {
+        Error::Png(err)
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Error {
+        Error::Image(err)
+    }
+}
@@ -1,10 +1,12 @@
 use crate::error::Error;
 use std::collections::VecDeque;
+use std::str::FromStr;
 
 ///
 /// System Events
 ///
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(dead_code)]
 pub enum Event {
     ///
@@ -17,16 +19,40 @@ pub enum Event {
     ///
     Encoder(u8, u16, i16, bool),
 
+    ///
+    /// Encoder touch change (Encoder Number, Touched, Shift)
+    ///
+    /// Only reported by the Mk3 drivers, whose report layouts are not verified on hardware yet.
+    ///
+    EncoderTouch(u8, bool, bool),
+
     ///
     /// Pad change (Pad Number, Velocity, Shift)
     ///
     Pad(u8, u8, bool),
+
+    ///
+    /// Touch strip change (Strip Number, Position, Pressure, Touches, Shift)
+    ///
+    /// Position and pressure are 0 - 0x3FF, a touch count of 0 is a release.
+    ///
+    Strip(u8, u16, u16, u8, bool),
+
+    ///
+    /// Grid button change (Column, Row, Pressed, Shift)
+    ///
+    Grid(u8, u8, bool, bool),
+
+    ///
+    /// Jog wheel turn (Delta, Shift)
+    ///
+    Jog(i16, bool),
 }
 
 ///
 /// Button Identifiers
 ///
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 pub enum Button {
     Display1,
@@ -82,9 +108,430 @@ pub enum Button {
     PadMode,
     Pattern,
     Scene,
+    Channel,
+    Plugin,
+    Arranger,
+    Mixer,
+    Settings,
+    Macro,
+    File,
+    Lock,
+    Pitch,
+    Mod,
+    Perform,
+    Notes,
+    Tap,
+    Follow,
+    Stop,
+    FixedVelocity,
+    Keyboard,
+    Chords,
+    Events,
+    Variation,
+    EncoderUp,
+    EncoderRight,
+    EncoderDown,
+    EncoderLeft,
+    Back,
+    Metro,
+    Copy,
+    Paste,
+    Note,
+    Nudge,
+    Undo,
+    Redo,
+    Quantize,
+    Clear,
+    ClearAuto,
+    Master,
+    Cue,
+    Input,
+    Song,
+    Level,
+    Aux,
+    Tune,
+    Scene1,
+    Scene2,
+    Scene3,
+    Scene4,
+    Scene5,
+    Scene6,
+    Scene7,
+    Scene8,
+    ArrowUp,
+    ArrowRight,
+    ArrowDown,
+    ArrowLeft,
     Unknown,
 }
 
+impl Button {
+    /// Every known button, excluding `Unknown`
+    pub const ALL: [Button; 107] = [
+        Button::Display1,
+        Button::Display2,
+        Button::Display3,
+        Button::Display4,
+        Button::Display5,
+        Button::Display6,
+        Button::Display7,
+        Button::Display8,
+        Button::Erase,
+        Button::Rec,
+        Button::Play,
+        Button::Grid,
+        Button::TransportRight,
+        Button::TransportLeft,
+        Button::Restart,
+        Button::MainEncoder,
+        Button::NoteRepeat,
+        Button::Sampling,
+        Button::Browse,
+        Button::All,
+        Button::Autowrite,
+        Button::Volume,
+        Button::Swing,
+        Button::Tempo,
+        Button::Enter,
+        Button::Group,
+        Button::GroupA,
+        Button::GroupB,
+        Button::GroupC,
+        Button::GroupD,
+        Button::GroupE,
+        Button::GroupF,
+        Button::GroupG,
+        Button::GroupH,
+        Button::Main,
+        Button::BrowseRight,
+        Button::BrowseLeft,
+        Button::MainRight,
+        Button::MainLeft,
+        Button::Nav,
+        Button::Control,
+        Button::Step,
+        Button::F3,
+        Button::F2,
+        Button::F1,
+        Button::Mute,
+        Button::Solo,
+        Button::Select,
+        Button::Duplicate,
+        Button::View,
+        Button::PadMode,
+        Button::Pattern,
+        Button::Scene,
+        Button::Channel,
+        Button::Plugin,
+        Button::Arranger,
+        Button::Mixer,
+        Button::Settings,
+        Button::Macro,
+        Button::File,
+        Button::Lock,
+        Button::Pitch,
+        Button::Mod,
+        Button::Perform,
+        Button::Notes,
+        Button::Tap,
+        Button::Follow,
+        Button::Stop,
+        Button::FixedVelocity,
+        Button::Keyboard,
+        Button::Chords,
+        Button::Events,
+        Button::Variation,
+        Button::EncoderUp,
+        Button::EncoderRight,
+        Button::EncoderDown,
+        Button::EncoderLeft,
+        Button::Back,
+        Button::Metro,
+        Button::Copy,
+        Button::Paste,
+        Button::Note,
+        Button::Nudge,
+        Button::Undo,
+        Button::Redo,
+        Button::Quantize,
+        Button::Clear,
+        Button::ClearAuto,
+        Button::Master,
+        Button::Cue,
+        Button::Input,
+        Button::Song,
+        Button::Level,
+        Button::Aux,
+        Button::Tune,
+        Button::Scene1,
+        Button::Scene2,
+        Button::Scene3,
+        Button::Scene4,
+        Button::Scene5,
+        Button::Scene6,
+        Button::Scene7,
+        Button::Scene8,
+        Button::ArrowUp,
+        Button::ArrowRight,
+        Button::ArrowDown,
+        Button::ArrowLeft,
+    ];
+
+    /// Stable name of the button, as used in configuration files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Button::Display1 => "display1",
+            Button::Display2 => "display2",
+            Button::Display3 => "display3",
+            Button::Display4 => "display4",
+            Button::Display5 => "display5",
+            Button::Display6 => "display6",
+            Button::Display7 => "display7",
+            Button::Display8 => "display8",
+            Button::Erase => "erase",
+            Button::Rec => "rec",
+            Button::Play => "play",
+            Button::Grid => "grid",
+            Button::TransportRight => "transport_right",
+            Button::TransportLeft => "transport_left",
+            Button::Restart => "restart",
+            Button::MainEncoder => "main_encoder",
+            Button::NoteRepeat => "note_repeat",
+            Button::Sampling => "sampling",
+            Button::Browse => "browse",
+            Button::All => "all",
+            Button::Autowrite => "autowrite",
+            Button::Volume => "volume",
+            Button::Swing => "swing",
+            Button::Tempo => "tempo",
+            Button::Enter => "enter",
+            Button::Group => "group",
+            Button::GroupA => "group_a",
+            Button::GroupB => "group_b",
+            Button::GroupC => "group_c",
+            Button::GroupD => "group_d",
+            Button::GroupE => "group_e",
+            Button::GroupF => "group_f",
+            Button::GroupG => "group_g",
+            Button::GroupH => "group_h",
+            Button::Main => "main",
+            Button::BrowseRight => "browse_right",
+            Button::BrowseLeft => "browse_left",
+            Button::MainRight => "main_right",
+            Button::MainLeft => "main_left",
+            Button::Nav => "nav",
+            Button::Control => "control",
+            Button::Step => "step",
+            Button::F3 => "f3",
+            Button::F2 => "f2",
+            Button::F1 => "f1",
+            Button::Mute => "mute",
+            Button::Solo => "solo",
+            Button::Select => "select",
+            Button::Duplicate => "duplicate",
+            Button::View => "view",
+            Button::PadMode => "pad_mode",
+            Button::Pattern => "pattern",
+            Button::Scene => "scene",
+            Button::Channel => "channel",
+            Button::Plugin => "plugin",
+            Button::Arranger => "arranger",
+            Button::Mixer => "mixer",
+            Button::Settings => "settings",
+            Button::Macro => "macro",
+            Button::File => "file",
+            Button::Lock => "lock",
+            Button::Pitch => "pitch",
+            Button::Mod => "mod",
+            Button::Perform => "perform",
+            Button::Notes => "notes",
+            Button::Tap => "tap",
+            Button::Follow => "follow",
+            Button::Stop => "stop",
+            Button::FixedVelocity => "fixed_velocity",
+            Button::Keyboard => "keyboard",
+            Button::Chords => "chords",
+            Button::Events => "events",
+            Button::Variation => "variation",
+            Button::EncoderUp => "encoder_up",
+            Button::EncoderRight => "encoder_right",
+            Button::EncoderDown => "encoder_down",
+            Button::EncoderLeft => "encoder_left",
+            Button::Back => "back",
+            Button::Metro => "metro",
+            Button::Copy => "copy",
+            Button::Paste => "paste",
+            Button::Note => "note",
+            Button::Nudge => "nudge",
+            Button::Undo => "undo",
+            Button::Redo => "redo",
+            Button::Quantize => "quantize",
+            Button::Clear => "clear",
+            Button::ClearAuto => "clear_auto",
+            Button::Master => "master",
+            Button::Cue => "cue",
+            Button::Input => "input",
+            Button::Song => "song",
+            Button::Level => "level",
+            Button::Aux => "aux",
+            Button::Tune => "tune",
+            Button::Scene1 => "scene1",
+            Button::Scene2 => "scene2",
+            Button::Scene3 => "scene3",
+            Button::Scene4 => "scene4",
+            Button::Scene5 => "scene5",
+            Button::Scene6 => "scene6",
+            Button::Scene7 => "scene7",
+            Button::Scene8 => "scene8",
+            Button::ArrowUp => "arrow_up",
+            Button::ArrowRight => "arrow_right",
+            Button::ArrowDown => "arrow_down",
+            Button::ArrowLeft => "arrow_left",
+            Button::Unknown => "unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for Button {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.write_str(self.name())
+    }
+}
+
+impl FromStr for Button {
+    type Err = Error;
+
+    /// Parse a button from its stable name
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "display1" => Button::Display1,
+            "display2" => Button::Display2,
+            "display3" => Button::Display3,
+            "display4" => Button::Display4,
+            "display5" => Button::Display5,
+            "display6" => Button::Display6,
+            "display7" => Button::Display7,
+            "display8" => Button::Display8,
+            "erase" => Button::Erase,
+            "rec" => Button::Rec,
+            "play" => Button::Play,
+            "grid" => Button::Grid,
+            "transport_right" => Button::TransportRight,
+            "transport_left" => Button::TransportLeft,
+            "restart" => Button::Restart,
+            "main_encoder" => Button::MainEncoder,
+            "note_repeat" => Button::NoteRepeat,
+            "sampling" => Button::Sampling,
+            "browse" => Button::Browse,
+            "all" => Button::All,
+            "autowrite" => Button::Autowrite,
+            "volume" => Button::Volume,
+            "swing" => Button::Swing,
+            "tempo" => Button::Tempo,
+            "enter" => Button::Enter,
+            "group" => Button::Group,
+            "group_a" => Button::GroupA,
+            "group_b" => Button::GroupB,
+            "group_c" => Button::GroupC,
+            "group_d" => Button::GroupD,
+            "group_e" => Button::GroupE,
+            "group_f" => Button::GroupF,
+            "group_g" => Button::GroupG,
+            "group_h" => Button::GroupH,
+            "main" => Button::Main,
+            "browse_right" => Button::BrowseRight,
+            "browse_left" => Button::BrowseLeft,
+            "main_right" => Button::MainRight,
+            "main_left" => Button::MainLeft,
+            "nav" => Button::Nav,
+            "control" => Button::Control,
+            "step" => Button::Step,
+            "f3" => Button::F3,
+            "f2" => Button::F2,
+            "f1" => Button::F1,
+            "mute" => Button::Mute,
+            "solo" => Button::Solo,
+            "select" => Button::Select,
+            "duplicate" => Button::Duplicate,
+            "view" => Button::View,
+            "pad_mode" => Button::PadMode,
+            "pattern" => Button::Pattern,
+            "scene" => Button::Scene,
+            "channel" => Button::Channel,
+            "plugin" => Button::Plugin,
+            "arranger" => Button::Arranger,
+            "mixer" => Button::Mixer,
+            "settings" => Button::Settings,
+            "macro" => Button::Macro,
+            "file" => Button::File,
+            "lock" => Button::Lock,
+            "pitch" => Button::Pitch,
+            "mod" => Button::Mod,
+            "perform" => Button::Perform,
+            "notes" => Button::Notes,
+            "tap" => Button::Tap,
+            "follow" => Button::Follow,
+            "stop" => Button::Stop,
+            "fixed_velocity" => Button::FixedVelocity,
+            "keyboard" => Button::Keyboard,
+            "chords" => Button::Chords,
+            "events" => Button::Events,
+            "variation" => Button::Variation,
+            "encoder_up" => Button::EncoderUp,
+            "encoder_right" => Button::EncoderRight,
+            "encoder_down" => Button::EncoderDown,
+            "encoder_left" => Button::EncoderLeft,
+            "back" => Button::Back,
+            "metro" => Button::Metro,
+            "copy" => Button::Copy,
+            "paste" => Button::Paste,
+            "note" => Button::Note,
+            "nudge" => Button::Nudge,
+            "undo" => Button::Undo,
+            "redo" => Button::Redo,
+            "quantize" => Button::Quantize,
+            "clear" => Button::Clear,
+            "clear_auto" => Button::ClearAuto,
+            "master" => Button::Master,
+            "cue" => Button::Cue,
+            "input" => Button::Input,
+            "song" => Button::Song,
+            "level" => Button::Level,
+            "aux" => Button::Aux,
+            "tune" => Button::Tune,
+            "scene1" => Button::Scene1,
+            "scene2" => Button::Scene2,
+            "scene3" => Button::Scene3,
+            "scene4" => Button::Scene4,
+            "scene5" => Button::Scene5,
+            "scene6" => Button::Scene6,
+            "scene7" => Button::Scene7,
+            "scene8" => Button::Scene8,
+            "arrow_up" => Button::ArrowUp,
+            "arrow_right" => Button::ArrowRight,
+            "arrow_down" => Button::ArrowDown,
+            "arrow_left" => Button::ArrowLeft,
+            "unknown" => Button::Unknown,
+            _ => return Err(Error::UnknownButton(name.to_string())),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Button {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Button {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 ///
 /// Context object for adding events
 ///
@@ -115,3 +562,61 @@ pub trait EventTask {
     ///
     fn tick(&mut self, context: &mut EventContext) -> Result<(), Error>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_names_round_trip() {
+        for button in Button::ALL {
+            assert_eq!(button.name().parse::<Button>().unwrap(), button);
+        }
+    }
+
+    #[test]
+    fn button_names_are_unique() {
+        let mut names: Vec<&str> = Button::ALL.iter().map(Button::name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), Button::ALL.len());
+    }
+
+    #[test]
+    fn unknown_button_names_are_rejected() {
+        assert!(matches!(
+            "Play".parse::<Button>(),
+            Err(Error::UnknownButton(name)) if name == "Play"
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn buttons_serialise_by_name() {
+        for button in Button::ALL {
+            let json = serde_json::to_string(&button).unwrap();
+            assert_eq!(json, format!("\"{}\"", button.name()));
+            assert_eq!(serde_json::from_str::<Button>(&json).unwrap(), button);
+        }
+        assert!(serde_json::from_str::<Button>("\"no_such_button\"").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn events_round_trip() {
+        let events = [
+            Event::Button(Button::Play, true, false),
+            Event::Encoder(2, 0x0F, -1, true),
+            Event::EncoderTouch(3, true, false),
+            Event::Pad(15, 0x7F, false),
+            Event::Strip(1, 0x3FF, 0x200, 2, false),
+            Event::Grid(7, 0, true, true),
+            Event::Jog(-3, false),
+        ];
+        for event in events {
+            let json = serde_json::to_string(&event).unwrap();
+            let decoded: Event = serde_json::from_str(&json).unwrap();
+            assert_eq!(format!("{decoded:?}"), format!("{event:?}"));
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{Device, Event, LedColor};
+
+/// Number of pads played, a 4x4 grid with pad 0 at the bottom left
+const PAD_COUNT: usize = 16;
+const PAD_COLUMNS: usize = 4;
+
+const ROOT_COLOR: LedColor = LedColor::new(0x00, 0x40, 0xFF);
+const SCALE_COLOR: LedColor = LedColor::new(0x00, 0x10, 0x40);
+const HELD_COLOR: LedColor = LedColor::new(0xFF, 0xFF, 0xFF);
+
+///
+/// Musical scale
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Scale {
+    Chromatic,
+    Major,
+    Minor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+    MajorPentatonic,
+    MinorPentatonic,
+}
+
+impl Scale {
+    pub const ALL: [Scale; 10] = [
+        Scale::Chromatic,
+        Scale::Major,
+        Scale::Minor,
+        Scale::Dorian,
+        Scale::Phrygian,
+        Scale::Lydian,
+        Scale::Mixolydian,
+        Scale::Locrian,
+        Scale::MajorPentatonic,
+        Scale::MinorPentatonic,
+    ];
+
+    /// Semitones of each degree above the root
+    pub fn intervals(&self) -> &'static [u8] {
+        match self {
+            Scale::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+            Scale::Major => &[0, 2, 4, 5, 7, 9, 11],
+            Scale::Minor => &[0, 2, 3, 5, 7, 8, 10],
+            Scale::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+            Scale::Phrygian => &[0, 1, 3, 5, 7, 8, 10],
+            Scale::Lydian => &[0, 2, 4, 6, 7, 9, 11],
+            Scale::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+            Scale::Locrian => &[0, 1, 3, 5, 6, 8, 10],
+            Scale::MajorPentatonic => &[0, 2, 4, 7, 9],
+            Scale::MinorPentatonic => &[0, 3, 5, 7, 10],
+        }
+    }
+
+    /// Name of the scale
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scale::Chromatic => "Chromatic",
+            Scale::Major => "Major",
+            Scale::Minor => "Minor",
+            Scale::Dorian => "Dorian",
+            Scale::Phrygian => "Phrygian",
+            Scale::Lydian => "Lydian",
+            Scale::Mixolydian => "Mixolydian",
+            Scale::Locrian => "Locrian",
+            Scale::MajorPentatonic => "Major Pentatonic",
+            Scale::MinorPentatonic => "Minor Pentatonic",
+        }
+    }
+
+    ///
+    /// Determine if a note is part of the scale
+    ///
+    /// **Arguments**
+    /// - root - Root of the scale, 0 (C) to 11 (B)
+    /// - note - MIDI note
+    pub fn contains(&self, root: u8, note: u8) -> bool {
+        let semitone = (note + 12 - root % 12) % 12;
+        self.intervals().contains(&semitone)
+    }
+}
+
+///
+/// Note produced by playing a pad
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoteEvent {
+    ///
+    /// Pad pressed (MIDI Note, Velocity 1 - 127)
+    ///
+    On(u8, u8),
+
+    ///
+    /// Pad released (MIDI Note)
+    ///
+    Off(u8),
+
+    ///
+    /// Pressure change of a held pad (MIDI Note, Pressure 1 - 127)
+    ///
+    Pressure(u8, u8),
+}
+
+///
+/// Plays the pads as a melodic keyboard
+///
+/// Pads are laid out from the bottom left, each row starting `row_offset` notes above the row
+/// below. With `in_key` every pad plays a note of the scale, otherwise the pads are chromatic and
+/// notes outside the scale are left dark.
+///
+#[derive(Debug, Clone)]
+pub struct Keyboard {
+    root: u8,
+    scale: Scale,
+    octave: i8,
+    row_offset: u8,
+    in_key: bool,
+
+    held: [Option<u8>; PAD_COUNT],
+    pad_leds: [Option<LedColor>; PAD_COUNT],
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new(0, Scale::Major)
+    }
+}
+
+impl Keyboard {
+    ///
+    /// Create an in key keyboard starting from the root in octave 3 (C3 is MIDI note 48)
+    ///
+    /// **Arguments**
+    /// - root - Root of the scale, 0 (C) to 11 (B)
+    /// - scale - Scale of the keyboard
+    pub fn new(root: u8, scale: Scale) -> Self {
+        Self {
+            root: root % 12,
+            scale,
+            octave: 3,
+            row_offset: PAD_COLUMNS as u8,
+            in_key: true,
+
+            held: [None; PAD_COUNT],
+            pad_leds: [None; PAD_COUNT],
+        }
+    }
+
+    /// Root of the scale, 0 (C) to 11 (B)
+    pub fn root(&self) -> u8 {
+        self.root
+    }
+
+    ///
+    /// Set the root of the scale
+    ///
+    /// **Arguments**
+    /// - root - Root of the scale, 0 (C) to 11 (B)
+    pub fn set_root(&mut self, root: u8) {
+        self.root = root % 12;
+    }
+
+    /// Scale of the keyboard
+    pub fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    ///
+    /// Set the scale of the keyboard
+    ///
+    /// **Arguments**
+    /// - scale - Scale of the keyboard
+    pub fn set_scale(&mut self, scale: Scale) {
+        self.scale = scale;
+    }
+
+    /// Octave of the bottom left pad
+    pub fn octave(&self) -> i8 {
+        self.octave
+    }
+
+    ///
+    /// Set the octave of the bottom left pad
+    ///
+    /// **Arguments**
+    /// - octave - Octave from -1 to 9
+    pub fn set_octave(&mut self, octave: i8) {
+        self.octave = octave.clamp(-1, 9);
+    }
+
+    /// Notes, scale degrees or semitones, between the start of a row and the row below
+    pub fn row_offset(&self) -> u8 {
+        self.row_offset
+    }
+
+    ///
+    /// Set the notes between the start of a row and the row below
+    ///
+    /// **Arguments**
+    /// - row_offset - 4 continues from the end of the row below, smaller values overlap rows
+    pub fn set_row_offset(&mut self, row_offset: u8) {
+        self.row_offset = row_offset;
+    }
+
+    /// Pads only play notes of the scale
+    pub fn in_key(&self) -> bool {
+        self.in_key
+    }
+
+    ///
+    /// Choose between pads playing only notes of the scale or every semitone
+    ///
+    /// **Arguments**
+    /// - in_key - Pads only play notes of the scale
+    pub fn set_in_key(&mut self, in_key: bool) {
+        self.in_key = in_key;
+    }
+
+    ///
+    /// Note played by a pad, `None` when it is outside the MIDI range
+    ///
+    /// **Arguments**
+    /// - pad - Pad number
+    pub fn note(&self, pad: u8) -> Option<u8> {
+        let pad = pad as usize;
+        if pad >= PAD_COUNT {
+            return None;
+        }
+        let step = (pad / PAD_COLUMNS) as i32 * self.row_offset as i32 + (pad % PAD_COLUMNS) as i32;
+        let base = (self.octave as i32 + 1) * 12 + self.root as i32;
+
+        let note = if self.in_key {
+            let intervals = self.scale.intervals();
+            let len = intervals.len() as i32;
+            base + step / len * 12 + intervals[(step % len) as usize] as i32
+        } else {
+            base + step
+        };
+        u8::try_from(note).ok().filter(|note| *note < 128)
+    }
+
+    ///
+    /// Turn a pad event into a note
+    ///
+    /// Releases play off the note that was pressed, even if the layout changed while held.
+    ///
+    /// **Arguments**
+    /// - event - Event from the device
+    pub fn process(&mut self, event: &Event) -> Option<NoteEvent> {
+        let Event::Pad(pad, velocity, _) = *event else {
+            return None;
+        };
+        let held = self.held.get_mut(pad as usize)?;
+        let pressed = velocity > 0;
+        let velocity = (velocity >> 1).max(1);
+
+        match *held {
+            Some(note) if !pressed => {
+                *held = None;
+                Some(NoteEvent::Off(note))
+            }
+            Some(note) => Some(NoteEvent::Pressure(note, velocity)),
+            None if pressed => {
+                let note = self.note(pad)?;
+                self.held[pad as usize] = Some(note);
+                Some(NoteEvent::On(note, velocity))
+            }
+            None => None,
+        }
+    }
+
+    ///
+    /// Light root notes, notes of the scale and held pads
+    ///
+    /// Only LEDs that have changed since the last call are updated.
+    ///
+    /// **Arguments**
+    /// - device - Device to update
+    pub fn render_leds<D: Device + ?Sized>(&mut self, device: &mut D) {
+        for pad in 0..PAD_COUNT {
+            let color = match self.note(pad as u8) {
+                _ if self.held[pad].is_some() => HELD_COLOR,
+                Some(note) if note % 12 == self.root => ROOT_COLOR,
+                Some(note) if self.scale.contains(self.root, note) => SCALE_COLOR,
+                _ => LedColor::OFF,
+            };
+            if self.pad_leds[pad] != Some(color) {
+                self.pad_leds[pad] = Some(color);
+                device.set_pad_led(pad as u8, color.into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes(keyboard: &Keyboard) -> Vec<Option<u8>> {
+        (0..PAD_COUNT as u8).map(|pad| keyboard.note(pad)).collect()
+    }
+
+    #[test]
+    fn in_key_pads_continue_across_rows() {
+        let keyboard = Keyboard::new(0, Scale::Major);
+        let notes = notes(&keyboard);
+        assert_eq!(notes[..8], [48, 50, 52, 53, 55, 57, 59, 60].map(Some));
+        assert_eq!(notes[15], Some(74));
+    }
+
+    #[test]
+    fn chromatic_layout_with_row_offset() {
+        let mut keyboard = Keyboard::new(9, Scale::MinorPentatonic);
+        keyboard.set_in_key(false);
+        keyboard.set_row_offset(5);
+        keyboard.set_octave(4);
+        let notes = notes(&keyboard);
+        assert_eq!(notes[..6], [69, 70, 71, 72, 74, 75].map(Some));
+        assert!(keyboard.scale().contains(9, 72));
+        assert!(!keyboard.scale().contains(9, 70));
+    }
+
+    #[test]
+    fn notes_outside_the_midi_range_are_silent() {
+        let mut keyboard = Keyboard::new(0, Scale::Chromatic);
+        keyboard.set_octave(9);
+        assert_eq!(keyboard.note(7), Some(127));
+        assert_eq!(keyboard.note(8), None);
+        assert_eq!(keyboard.process(&Event::Pad(8, 0xFF, false)), None);
+    }
+
+    #[test]
+    fn release_plays_off_the_pressed_note() {
+        let mut keyboard = Keyboard::new(2, Scale::Dorian);
+        assert_eq!(
+            keyboard.process(&Event::Pad(1, 0x80, false)),
+            Some(NoteEvent::On(52, 64))
+        );
+        assert_eq!(
+            keyboard.process(&Event::Pad(1, 0xA0, false)),
+            Some(NoteEvent::Pressure(52, 80))
+        );
+
+        keyboard.set_octave(5);
+        assert_eq!(
+            keyboard.process(&Event::Pad(1, 0, false)),
+            Some(NoteEvent::Off(52))
+        );
+        assert_eq!(keyboard.process(&Event::Pad(1, 0, false)), None);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{Button, Color};
+use std::collections::HashMap;
+
+///
+/// Colour of a LED
+///
+/// Mono LEDs on the Mk2 devices are lit when any channel is above half brightness, the Mk1
+/// devices dim them to `brightness()`.
+///
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LedColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl LedColor {
+    pub const OFF: LedColor = LedColor::new(0x00, 0x00, 0x00);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Level of a single colour LED showing this colour, the brightest channel
+    pub fn brightness(&self) -> u8 {
+        self.r.max(self.g).max(self.b)
+    }
+}
+
+impl From<Color> for LedColor {
+    fn from(color: Color) -> Self {
+        Self::new(color.r(), color.g(), color.b())
+    }
+}
+
+impl From<LedColor> for Color {
+    fn from(color: LedColor) -> Self {
+        Color::new(0xFF, color.r, color.g, color.b)
+    }
+}
+
+///
+/// Last colours applied to the LEDs of a device
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LedSnapshot {
+    /// Button LEDs that have been set
+    pub buttons: HashMap<Button, LedColor>,
+
+    /// Pad LEDs by pad number
+    pub pads: Vec<LedColor>,
+
+    /// Grid LEDs row by row, empty on devices without a grid
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub grid: Vec<LedColor>,
+}
+
+impl LedSnapshot {
+    ///
+    /// Create a snapshot with every LED off
+    ///
+    /// **Arguments**
+    /// - pad_count - Number of pads on the device
+    pub fn new(pad_count: usize) -> Self {
+        Self {
+            buttons: HashMap::new(),
+            pads: vec![LedColor::OFF; pad_count],
+            grid: Vec::new(),
+        }
+    }
+}
+
+///
+/// Number of segments of a LED meter lit to show a level
+///
+/// **Arguments**
+/// - level - Level from 0.0 to 1.0, values outside are clamped
+/// - segments - Number of LEDs in the meter
+pub(crate) fn lit_segments(level: f32, segments: usize) -> usize {
+    (level.clamp(0.0, 1.0) * segments as f32).round() as usize
+}
+
+///
+/// Light the LEDs of one meter in a report holding several meters one after the other, returns
+/// false if there is no such meter
+///
+/// **Arguments**
+/// - leds - Brightness of every meter segment, meter by meter from the first segment
+/// - segments - Number of LEDs in each meter
+/// - meter - Meter number
+/// - level - Level from 0.0 to 1.0, values outside are clamped
+pub(crate) fn set_meter_leds(leds: &mut [u8], segments: usize, meter: u8, level: f32) -> bool {
+    let start = meter as usize * segments;
+    let Some(meter_leds) = leds.get_mut(start..start + segments) else {
+        return false;
+    };
+    let lit = lit_segments(level, segments);
+    for (segment, led) in meter_leds.iter_mut().enumerate() {
+        *led = if segment < lit { 0xFF } else { 0x00 };
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colours_map_to_brightness() {
+        assert_eq!(LedColor::new(0x00, 0x80, 0x20).brightness(), 0x80);
+        assert_eq!(LedColor::OFF.brightness(), 0x00);
+    }
+
+    #[test]
+    fn meters_light_from_the_first_segment() {
+        let mut leds = [0x00; 8];
+        assert!(set_meter_leds(&mut leds, 4, 1, 0.5));
+        assert_eq!(leds, [0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00]);
+
+        assert!(set_meter_leds(&mut leds, 4, 1, 2.0));
+        assert!(set_meter_leds(&mut leds, 4, 0, -1.0));
+        assert_eq!(leds, [0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+        assert!(!set_meter_leds(&mut leds, 4, 2, 1.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn colours_round_trip() {
+        let color = LedColor::new(0x12, 0x80, 0xFF);
+        let json = serde_json::to_string(&color).unwrap();
+        assert_eq!(json, r#"{"r":18,"g":128,"b":255}"#);
+        assert_eq!(serde_json::from_str::<LedColor>(&json).unwrap(), color);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshots_round_trip() {
+        let mut snapshot = LedSnapshot::new(16);
+        snapshot
+            .buttons
+            .insert(Button::Play, LedColor::new(0, 0xFF, 0));
+        snapshot
+            .buttons
+            .insert(Button::Rec, LedColor::new(0xFF, 0, 0));
+        snapshot.pads[3] = LedColor::new(0, 0, 0x80);
+        snapshot.grid = vec![LedColor::new(1, 2, 3); 64];
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            serde_json::from_str::<LedSnapshot>(&json).unwrap(),
+            snapshot
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshots_without_a_grid_parse() {
+        let json = r#"{"buttons":{"play":{"r":0,"g":255,"b":0}},"pads":[]}"#;
+        let snapshot: LedSnapshot = serde_json::from_str(json).unwrap();
+        assert_eq!(snapshot.buttons[&Button::Play], LedColor::new(0, 0xFF, 0));
+        assert!(snapshot.grid.is_empty());
+    }
+}
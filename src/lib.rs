@@ -1,14 +1,38 @@
-#![feature(slice_as_chunks)]
-
+pub mod clock;
 pub mod devices;
+pub mod display;
 mod error;
 mod events;
+pub mod keyboard;
+mod leds;
+#[cfg(feature = "mapping")]
+pub mod mapping;
+pub mod note_repeat;
+#[cfg(feature = "sequencer")]
+pub mod sequencer;
+mod settings;
+mod strip;
+pub mod widgets;
 
-pub use devices::{Device, MaschineMikroMk2, MaschineMk2};
+pub use devices::{
+    Capabilities, Device, DeviceInfo, MaschineJam, MaschineMikroMk1, MaschineMikroMk2,
+    MaschineMikroMk3, MaschineMk1, MaschineMk2, MaschineMk3, MaschineStudio,
+};
+pub use display::{DisplaySettings, DisplayStats, DitherMode};
 pub use error::Error;
-pub use events::{Event, EventContext, EventTask};
+pub use events::{Button, Event, EventContext, EventTask};
+pub use leds::{LedColor, LedSnapshot};
 pub use raqote::Color;
+pub use settings::{EncoderSettings, PadSettings, VelocityCurve};
+pub use strip::{SmartStrip, StripLeds, StripMode, StripValue};
 
+///
+/// Open the first attached controller
+///
+/// Only drivers verified on hardware are tried. The unverified `MaschineMk1`, `MaschineMikroMk1`,
+/// `MaschineMk3`, `MaschineMikroMk3`, `MaschineStudio` and `MaschineJam` have to be created
+/// directly.
+///
 pub fn get_device() -> Result<Box<dyn Device>, Error> {
     if let Ok(device) = MaschineMikroMk2::new() {
         Ok(Box::new(device))
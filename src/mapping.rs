@@ -0,0 +1,531 @@
+use crate::{Button, Capabilities, Error, Event};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+///
+/// Physical control of a device
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Control {
+    Button(Button),
+    Pad(u8),
+    Encoder(u8),
+    Strip(u8),
+    Grid {
+        column: u8,
+        row: u8,
+    },
+
+    /// Jog wheel, written as `jog = true`
+    #[serde(serialize_with = "serialize_jog", deserialize_with = "deserialize_jog")]
+    Jog,
+}
+
+impl Control {
+    /// Control that generated an event, `None` for encoder touches which are not mapped
+    pub fn of(event: &Event) -> Option<Self> {
+        Some(match *event {
+            Event::Button(button, _, _) => Control::Button(button),
+            Event::Pad(pad, _, _) => Control::Pad(pad),
+            Event::Encoder(encoder, _, _, _) => Control::Encoder(encoder),
+            Event::EncoderTouch(_, _, _) => return None,
+            Event::Strip(strip, _, _, _, _) => Control::Strip(strip),
+            Event::Grid(column, row, _, _) => Control::Grid { column, row },
+            Event::Jog(_, _) => Control::Jog,
+        })
+    }
+}
+
+/// Write the jog wheel control as `jog = true`, a unit variant can not be flattened
+fn serialize_jog<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bool(true)
+}
+
+/// Read the jog wheel control written as `jog = true`
+fn deserialize_jog<'de, D: Deserializer<'de>>(deserializer: D) -> Result<(), D::Error> {
+    if bool::deserialize(deserializer)? {
+        Ok(())
+    } else {
+        Err(serde::de::Error::custom("jog must be true"))
+    }
+}
+
+///
+/// Output produced when a mapped control is used
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    ///
+    /// MIDI note, channel 1-16
+    ///
+    MidiNote { channel: u8, note: u8 },
+
+    ///
+    /// MIDI control change, channel 1-16
+    ///
+    MidiCc { channel: u8, controller: u8 },
+
+    ///
+    /// OSC message address
+    ///
+    Osc(String),
+
+    ///
+    /// Keyboard shortcut name, eg "ctrl+space"
+    ///
+    Key(String),
+
+    ///
+    /// Action identifier defined by the application
+    ///
+    App(String),
+}
+
+///
+/// Single control to action assignment
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mapping {
+    #[serde(flatten)]
+    pub control: Control,
+
+    /// Only applies while Shift is held
+    #[serde(default)]
+    pub shift: bool,
+
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+///
+/// Set of mappings loaded from a TOML file
+///
+/// ```toml
+/// name = "Transport"
+///
+/// [[mapping]]
+/// button = "play"
+/// midi_note = { channel = 1, note = 60 }
+///
+/// [[mapping]]
+/// encoder = 1
+/// shift = true
+/// osc = "/mixer/volume"
+/// ```
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub name: String,
+
+    #[serde(default, rename = "mapping")]
+    pub mappings: Vec<Mapping>,
+}
+
+impl Profile {
+    ///
+    /// Parse a profile from TOML
+    ///
+    /// **Arguments**
+    /// - text - Profile contents
+    pub fn from_toml(text: &str) -> Result<Self, Error> {
+        toml::from_str(text).map_err(|e| Error::InvalidMapping(e.to_string()))
+    }
+
+    ///
+    /// Load a profile from a TOML file
+    ///
+    /// **Arguments**
+    /// - path - Path of the profile
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Encode the profile as TOML
+    pub fn to_toml(&self) -> Result<String, Error> {
+        toml::to_string(self).map_err(|e| Error::InvalidMapping(e.to_string()))
+    }
+
+    ///
+    /// Find the action assigned to a control
+    ///
+    /// A mapping without `shift` also applies while Shift is held, unless a shifted mapping
+    /// exists for the same control.
+    ///
+    /// **Arguments**
+    /// - control - Control that was used
+    /// - shift - Shift is held
+    pub fn action(&self, control: Control, shift: bool) -> Option<&Action> {
+        let mut fallback = None;
+        for mapping in self.mappings.iter().filter(|m| m.control == control) {
+            if mapping.shift == shift {
+                return Some(&mapping.action);
+            } else if !mapping.shift {
+                fallback = Some(&mapping.action);
+            }
+        }
+        fallback
+    }
+
+    ///
+    /// Find the action assigned to the control that generated an event
+    ///
+    /// Encoder touches never resolve to an action, only turning the encoder does.
+    ///
+    /// **Arguments**
+    /// - event - Event from the device
+    pub fn resolve(&self, event: &Event) -> Option<&Action> {
+        let shift = match *event {
+            Event::Button(_, _, shift)
+            | Event::Pad(_, _, shift)
+            | Event::Encoder(_, _, _, shift)
+            | Event::EncoderTouch(_, _, shift)
+            | Event::Strip(_, _, _, _, shift)
+            | Event::Grid(_, _, _, shift)
+            | Event::Jog(_, shift) => shift,
+        };
+        self.action(Control::of(event)?, shift)
+    }
+
+    ///
+    /// Check every mapping refers to a control of the device and has a valid action
+    ///
+    /// **Arguments**
+    /// - capabilities - Capabilities of the connected device
+    pub fn validate(&self, capabilities: &Capabilities) -> Result<(), Error> {
+        for mapping in self.mappings.iter() {
+            match mapping.control {
+                Control::Button(button) if !capabilities.has_button(button) => {
+                    return Err(Error::InvalidMapping(format!(
+                        "button {button} does not exist on this model"
+                    )));
+                }
+                Control::Pad(pad) if pad as usize >= capabilities.pad_count => {
+                    return Err(Error::InvalidMapping(format!(
+                        "pad {pad} does not exist on this model"
+                    )));
+                }
+                Control::Encoder(encoder) if encoder as usize >= capabilities.encoders.len() => {
+                    return Err(Error::InvalidMapping(format!(
+                        "encoder {encoder} does not exist on this model"
+                    )));
+                }
+                Control::Strip(strip) if strip as usize >= capabilities.strip_count => {
+                    return Err(Error::InvalidMapping(format!(
+                        "touch strip {strip} does not exist on this model"
+                    )));
+                }
+                Control::Grid { column, row }
+                    if !capabilities
+                        .grid
+                        .is_some_and(|grid| column < grid.columns && row < grid.rows) =>
+                {
+                    return Err(Error::InvalidMapping(format!(
+                        "grid cell {column},{row} does not exist on this model"
+                    )));
+                }
+                Control::Jog if !capabilities.jog_wheel => {
+                    return Err(Error::InvalidMapping(
+                        "this model has no jog wheel".to_string(),
+                    ));
+                }
+                _ => {}
+            }
+
+            match &mapping.action {
+                Action::MidiNote {
+                    channel,
+                    note: value,
+                }
+                | Action::MidiCc {
+                    channel,
+                    controller: value,
+                } => {
+                    if !(1..=16).contains(channel) {
+                        return Err(Error::InvalidMapping(format!(
+                            "MIDI channel {channel} is outside 1-16"
+                        )));
+                    }
+                    if *value > 127 {
+                        return Err(Error::InvalidMapping(format!(
+                            "MIDI value {value} is outside 0-127"
+                        )));
+                    }
+                }
+                Action::Osc(address) if !address.starts_with('/') => {
+                    return Err(Error::InvalidMapping(format!(
+                        "OSC address {address:?} must start with '/'"
+                    )));
+                }
+                Action::Key(name) | Action::App(name) if name.is_empty() => {
+                    return Err(Error::InvalidMapping("empty action name".to_string()));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// Profile file that is reloaded when it changes on disk
+///
+pub struct ProfileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    profile: Profile,
+    capabilities: Capabilities,
+}
+
+impl ProfileWatcher {
+    ///
+    /// Load a profile and watch it for changes
+    ///
+    /// **Arguments**
+    /// - path - Path of the profile
+    /// - capabilities - Capabilities of the connected device, every loaded profile is validated
+    ///   against them
+    pub fn new<P: AsRef<Path>>(path: P, capabilities: &Capabilities) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let modified = std::fs::metadata(&path)?.modified().ok();
+        let profile = Profile::open(&path)?;
+        profile.validate(capabilities)?;
+        Ok(Self {
+            path,
+            modified,
+            profile,
+            capabilities: capabilities.clone(),
+        })
+    }
+
+    /// Currently loaded profile
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    ///
+    /// Reload the profile if the file has been modified, returns true if it was reloaded
+    ///
+    /// The previous profile is kept if the file can not be read, parsed or validated.
+    ///
+    pub fn poll(&mut self) -> Result<bool, Error> {
+        let modified = std::fs::metadata(&self.path)?.modified().ok();
+        if modified == self.modified {
+            return Ok(false);
+        }
+        // Only retry after a failed load once the file changes again
+        self.modified = modified;
+        let profile = Profile::open(&self.path)?;
+        profile.validate(&self.capabilities)?;
+        self.profile = profile;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::{EncoderInfo, GridInfo};
+
+    const TRANSPORT: &str = include_str!("../examples/profiles/transport.toml");
+
+    fn capabilities() -> Capabilities {
+        Capabilities {
+            pad_count: 16,
+            encoders: vec![
+                EncoderInfo {
+                    range: 0x10,
+                    touch: false,
+                };
+                2
+            ],
+            buttons: vec![Button::Play, Button::Rec],
+            ..Capabilities::new("Test", 0, 0)
+        }
+    }
+
+    fn mapping(text: &str) -> Result<(), Error> {
+        Profile::from_toml(&format!("[[mapping]]\n{text}"))?.validate(&capabilities())
+    }
+
+    #[test]
+    fn control_and_action_are_flattened_into_the_mapping() {
+        let profile = Profile::from_toml(TRANSPORT).unwrap();
+        assert_eq!(profile.name, "Transport");
+        assert_eq!(profile.mappings.len(), 6);
+        assert_eq!(
+            profile.mappings[0],
+            Mapping {
+                control: Control::Button(Button::Play),
+                shift: false,
+                action: Action::MidiNote {
+                    channel: 1,
+                    note: 60
+                },
+            }
+        );
+        assert_eq!(
+            profile.mappings[5],
+            Mapping {
+                control: Control::Encoder(0),
+                shift: true,
+                action: Action::Osc("/tempo".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn profiles_round_trip() {
+        let text = "[[mapping]]\ngrid = { column = 2, row = 3 }\nkey = \"space\"\n\n\
+                    [[mapping]]\njog = true\nshift = true\nmidi_cc = { channel = 2, controller = 10 }\n";
+        let profile = Profile::from_toml(text).unwrap();
+        assert_eq!(
+            profile.mappings[0].control,
+            Control::Grid { column: 2, row: 3 }
+        );
+        assert_eq!(profile.mappings[1].control, Control::Jog);
+        assert_eq!(
+            Profile::from_toml(&profile.to_toml().unwrap()).unwrap(),
+            profile
+        );
+
+        let profile = Profile::from_toml(TRANSPORT).unwrap();
+        assert_eq!(
+            Profile::from_toml(&profile.to_toml().unwrap()).unwrap(),
+            profile
+        );
+    }
+
+    #[test]
+    fn malformed_mappings_are_rejected() {
+        for text in [
+            "jog = false\napp = \"a\"",
+            "button = \"not_a_button\"\napp = \"a\"",
+            "button = \"play\"",
+            "app = \"a\"",
+        ] {
+            assert!(
+                matches!(mapping(text), Err(Error::InvalidMapping(_))),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_checks_the_control_exists() {
+        assert!(mapping("button = \"play\"\napp = \"a\"").is_ok());
+        assert!(mapping("pad = 15\napp = \"a\"").is_ok());
+        for text in [
+            "button = \"scene\"\napp = \"a\"",
+            "pad = 16\napp = \"a\"",
+            "encoder = 2\napp = \"a\"",
+            "strip = 0\napp = \"a\"",
+            "grid = { column = 0, row = 0 }\napp = \"a\"",
+            "jog = true\napp = \"a\"",
+        ] {
+            assert!(
+                matches!(mapping(text), Err(Error::InvalidMapping(_))),
+                "{text}"
+            );
+        }
+
+        let mut capabilities = capabilities();
+        capabilities.grid = Some(GridInfo {
+            columns: 8,
+            rows: 8,
+        });
+        capabilities.jog_wheel = true;
+        let profile = Profile::from_toml(
+            "[[mapping]]\ngrid = { column = 7, row = 7 }\napp = \"a\"\n\
+             [[mapping]]\njog = true\napp = \"a\"\n",
+        )
+        .unwrap();
+        assert!(profile.validate(&capabilities).is_ok());
+    }
+
+    #[test]
+    fn validate_checks_the_action() {
+        for text in [
+            "pad = 0\nmidi_note = { channel = 0, note = 60 }",
+            "pad = 0\nmidi_note = { channel = 17, note = 60 }",
+            "pad = 0\nmidi_cc = { channel = 1, controller = 128 }",
+            "pad = 0\nosc = \"tempo\"",
+            "pad = 0\nkey = \"\"",
+            "pad = 0\napp = \"\"",
+        ] {
+            assert!(
+                matches!(mapping(text), Err(Error::InvalidMapping(_))),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn shifted_mappings_take_priority_while_shift_is_held() {
+        let profile = Profile::from_toml(TRANSPORT).unwrap();
+        let play = Control::Button(Button::Play);
+        assert_eq!(
+            profile.action(play, false),
+            Some(&Action::MidiNote {
+                channel: 1,
+                note: 60
+            })
+        );
+        assert_eq!(
+            profile.action(play, true),
+            Some(&Action::App("stop-all".to_string()))
+        );
+
+        // Unshifted mappings still apply while Shift is held
+        let rec = Control::Button(Button::Rec);
+        assert_eq!(profile.action(rec, true), profile.action(rec, false));
+        assert!(profile.action(rec, false).is_some());
+
+        assert_eq!(profile.action(Control::Pad(1), false), None);
+    }
+
+    #[test]
+    fn events_resolve_with_their_shift_state() {
+        let profile = Profile::from_toml(TRANSPORT).unwrap();
+        assert_eq!(
+            profile.resolve(&Event::Button(Button::Play, true, true)),
+            Some(&Action::App("stop-all".to_string()))
+        );
+        assert_eq!(
+            profile.resolve(&Event::Encoder(0, 3, 1, false)),
+            Some(&Action::MidiCc {
+                channel: 1,
+                controller: 7
+            })
+        );
+        assert_eq!(
+            profile.resolve(&Event::Encoder(0, 3, 1, true)),
+            Some(&Action::Osc("/tempo".to_string()))
+        );
+        assert_eq!(
+            profile.resolve(&Event::Pad(0, 0x40, false)),
+            profile.resolve(&Event::Pad(0, 0x40, true))
+        );
+    }
+
+    #[test]
+    fn watcher_keeps_the_profile_when_validation_fails() {
+        let path =
+            std::env::temp_dir().join(format!("maschine-watcher-{}.toml", std::process::id()));
+        std::fs::write(&path, TRANSPORT).unwrap();
+        let mut watcher = ProfileWatcher::new(&path, &capabilities()).unwrap();
+
+        // Mapping a pad the device does not have
+        std::fs::write(&path, "[[mapping]]\npad = 40\napp = \"a\"\n").unwrap();
+        watcher.modified = None;
+        let result = watcher.poll();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(Error::InvalidMapping(_))));
+        assert_eq!(watcher.profile(), &Profile::from_toml(TRANSPORT).unwrap());
+    }
+}
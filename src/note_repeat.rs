@@ -0,0 +1,519 @@
+use crate::clock::{Clock, PPQN};
+use crate::{Button, Event};
+use rand::Rng;
+use std::ops::Range;
+use std::time::Instant;
+
+///
+/// Note length between repeats
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RepeatRate {
+    Quarter,
+    QuarterTriplet,
+    Eighth,
+    EighthTriplet,
+    Sixteenth,
+    SixteenthTriplet,
+    ThirtySecond,
+    ThirtySecondTriplet,
+}
+
+impl RepeatRate {
+    /// Every rate from the slowest to the fastest, selected by display buttons 1 to 8
+    pub const ALL: [RepeatRate; 8] = [
+        RepeatRate::Quarter,
+        RepeatRate::QuarterTriplet,
+        RepeatRate::Eighth,
+        RepeatRate::EighthTriplet,
+        RepeatRate::Sixteenth,
+        RepeatRate::SixteenthTriplet,
+        RepeatRate::ThirtySecond,
+        RepeatRate::ThirtySecondTriplet,
+    ];
+
+    /// Length of the note in beats (quarter notes)
+    pub fn beats(&self) -> f32 {
+        match self {
+            RepeatRate::Quarter => 1.0,
+            RepeatRate::QuarterTriplet => 2.0 / 3.0,
+            RepeatRate::Eighth => 1.0 / 2.0,
+            RepeatRate::EighthTriplet => 1.0 / 3.0,
+            RepeatRate::Sixteenth => 1.0 / 4.0,
+            RepeatRate::SixteenthTriplet => 1.0 / 6.0,
+            RepeatRate::ThirtySecond => 1.0 / 8.0,
+            RepeatRate::ThirtySecondTriplet => 1.0 / 12.0,
+        }
+    }
+
+    /// Length of the note in clock ticks
+    pub fn ticks(&self) -> u64 {
+        match self {
+            RepeatRate::Quarter => PPQN,
+            RepeatRate::QuarterTriplet => PPQN * 2 / 3,
+            RepeatRate::Eighth => PPQN / 2,
+            RepeatRate::EighthTriplet => PPQN / 3,
+            RepeatRate::Sixteenth => PPQN / 4,
+            RepeatRate::SixteenthTriplet => PPQN / 6,
+            RepeatRate::ThirtySecond => PPQN / 8,
+            RepeatRate::ThirtySecondTriplet => PPQN / 12,
+        }
+    }
+
+    /// Label of the rate, eg "1/16T"
+    pub fn name(&self) -> &'static str {
+        match self {
+            RepeatRate::Quarter => "1/4",
+            RepeatRate::QuarterTriplet => "1/4T",
+            RepeatRate::Eighth => "1/8",
+            RepeatRate::EighthTriplet => "1/8T",
+            RepeatRate::Sixteenth => "1/16",
+            RepeatRate::SixteenthTriplet => "1/16T",
+            RepeatRate::ThirtySecond => "1/32",
+            RepeatRate::ThirtySecondTriplet => "1/32T",
+        }
+    }
+
+    /// Rate a number of positions faster (positive) or slower (negative), stopping at either end
+    fn offset(&self, delta: i16) -> RepeatRate {
+        let idx = RepeatRate::ALL
+            .iter()
+            .position(|rate| rate == self)
+            .unwrap_or(0) as i16;
+        RepeatRate::ALL[(idx + delta).clamp(0, RepeatRate::ALL.len() as i16 - 1) as usize]
+    }
+}
+
+///
+/// Order the arpeggiator plays held pads in
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArpOrder {
+    /// Lowest pad number to highest
+    Up,
+
+    /// Highest pad number to lowest
+    Down,
+
+    /// Any held pad
+    Random,
+}
+
+///
+/// What held pads play while repeating
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RepeatMode {
+    ///
+    /// Every held pad retriggers together
+    ///
+    Repeat,
+
+    ///
+    /// One held pad at a time, cycling through them
+    ///
+    Arpeggio(ArpOrder),
+}
+
+///
+/// Pad hit produced by note repeat
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RepeatNote {
+    /// Time the note falls on, may be slightly before the call that produced it
+    pub time: Instant,
+
+    /// Pad number
+    pub pad: u8,
+
+    /// Pad velocity at the time of the note
+    pub velocity: u8,
+}
+
+///
+/// Note repeat and arpeggiator
+///
+/// While NoteRepeat is held, held pads retrigger at the selected rate with the velocity of the
+/// latest pad pressure. The rate is picked with display buttons 1 to 8 or by turning the first
+/// encoder while NoteRepeat is held. Repeats are counted in the ticks of a `Clock`, so they follow
+/// its tempo, tap tempo and MIDI clock.
+///
+pub struct NoteRepeat {
+    rate: RepeatRate,
+    mode: RepeatMode,
+
+    active: bool,
+    held: Vec<(u8, u8)>,
+    next_tick: Option<u64>,
+    last_pad: Option<u8>,
+}
+
+impl Default for NoteRepeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NoteRepeat {
+    /// Create note repeat repeating 1/16 notes
+    pub fn new() -> Self {
+        Self {
+            rate: RepeatRate::Sixteenth,
+            mode: RepeatMode::Repeat,
+
+            active: false,
+            held: Vec::new(),
+            next_tick: None,
+            last_pad: None,
+        }
+    }
+
+    /// Note length between repeats
+    pub fn rate(&self) -> RepeatRate {
+        self.rate
+    }
+
+    ///
+    /// Set the note length between repeats
+    ///
+    /// **Arguments**
+    /// - rate - Note length
+    pub fn set_rate(&mut self, rate: RepeatRate) {
+        self.rate = rate;
+    }
+
+    /// What held pads play
+    pub fn mode(&self) -> RepeatMode {
+        self.mode
+    }
+
+    ///
+    /// Choose between repeating every held pad or arpeggiating them
+    ///
+    /// **Arguments**
+    /// - mode - What held pads play
+    pub fn set_mode(&mut self, mode: RepeatMode) {
+        self.mode = mode;
+        self.last_pad = None;
+    }
+
+    /// NoteRepeat is held
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    ///
+    /// Update held pads, the rate and NoteRepeat from a device event
+    ///
+    /// **Arguments**
+    /// - event - Event from the device
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::Button(Button::NoteRepeat, pressed, _) => {
+                self.active = pressed;
+                self.next_tick = None;
+                self.last_pad = None;
+            }
+            Event::Button(button, true, _) if self.active => {
+                if let Some(rate) = display_rate(button) {
+                    self.rate = rate;
+                }
+            }
+            Event::Encoder(0, _, delta, _) if self.active => {
+                self.rate = self.rate.offset(delta.signum());
+            }
+            Event::Pad(pad, velocity, _) => {
+                match self.held.iter_mut().find(|(held, _)| *held == pad) {
+                    // Pressure changes of a held pad carry into the next repeat
+                    Some(held) if velocity > 0 => held.1 = velocity,
+                    Some(_) => self.held.retain(|(held, _)| *held != pad),
+                    None if velocity > 0 => {
+                        self.held.push((pad, velocity));
+                        // The first pad starts repeating straight away
+                        if self.held.len() == 1 {
+                            self.next_tick = None;
+                        }
+                    }
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ///
+    /// Produce the notes falling on clock ticks
+    ///
+    /// Pass the ticks returned by `Clock::advance` or `Clock::handle_midi`. Repeating starts on the
+    /// first tick after a pad is pressed, and notes are stamped with the time of their tick so they
+    /// can be scheduled accurately downstream.
+    ///
+    /// **Arguments**
+    /// - clock - Clock the ticks came from
+    /// - ticks - Ticks that passed
+    pub fn play_ticks(&mut self, clock: &Clock, ticks: Range<u64>) -> Vec<RepeatNote> {
+        let mut notes = Vec::new();
+        if !self.active || self.held.is_empty() {
+            self.next_tick = None;
+            return notes;
+        }
+
+        let now = Instant::now();
+        let rate = self.rate.ticks();
+        for tick in ticks {
+            // Start over when the clock jumped, eg after a restart or a faster rate
+            let due = self
+                .next_tick
+                .is_none_or(|next| tick >= next || next > tick + rate);
+            if due {
+                let time = clock.tick_time(tick).unwrap_or(now);
+                self.play(time, &mut notes);
+                self.next_tick = Some(tick + rate);
+            }
+        }
+
+        notes
+    }
+
+    /// Add the notes of a single repeat
+    fn play(&mut self, time: Instant, notes: &mut Vec<RepeatNote>) {
+        let note = |(pad, velocity): (u8, u8)| RepeatNote {
+            time,
+            pad,
+            velocity,
+        };
+
+        let mut sorted = self.held.clone();
+        sorted.sort_unstable();
+        let next = match self.mode {
+            RepeatMode::Repeat => {
+                notes.extend(self.held.iter().copied().map(note));
+                return;
+            }
+            RepeatMode::Arpeggio(ArpOrder::Up) => sorted
+                .iter()
+                .find(|(pad, _)| self.last_pad.is_none_or(|last| *pad > last))
+                .or(sorted.first()),
+            RepeatMode::Arpeggio(ArpOrder::Down) => sorted
+                .iter()
+                .rev()
+                .find(|(pad, _)| self.last_pad.is_none_or(|last| *pad < last))
+                .or(sorted.last()),
+            RepeatMode::Arpeggio(ArpOrder::Random) => {
+                sorted.get(rand::thread_rng().gen_range(0..sorted.len()))
+            }
+        };
+
+        if let Some(&held) = next {
+            self.last_pad = Some(held.0);
+            notes.push(note(held));
+        }
+    }
+}
+
+/// Rate selected by a display button
+fn display_rate(button: Button) -> Option<RepeatRate> {
+    let idx = match button {
+        Button::Display1 => 0,
+        Button::Display2 => 1,
+        Button::Display3 => 2,
+        Button::Display4 => 3,
+        Button::Display5 => 4,
+        Button::Display6 => 5,
+        Button::Display7 => 6,
+        Button::Display8 => 7,
+        _ => return None,
+    };
+    Some(RepeatRate::ALL[idx])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{ClockSource, MIDI_CLOCK, MIDI_START};
+    use std::time::Duration;
+
+    /// Pads played up to a time
+    fn pads(repeat: &mut NoteRepeat, clock: &mut Clock, now: Instant) -> Vec<u8> {
+        let ticks = clock.advance(now);
+        repeat
+            .play_ticks(clock, ticks)
+            .iter()
+            .map(|note| note.pad)
+            .collect()
+    }
+
+    /// Pads played by each of a number of repeats, starting at `start`
+    fn repeats(
+        repeat: &mut NoteRepeat,
+        clock: &mut Clock,
+        start: Instant,
+        count: u32,
+    ) -> Vec<Vec<u8>> {
+        // 1/16 notes at 120 BPM are 125 ms apart
+        (0..count)
+            .map(|idx| pads(repeat, clock, start + Duration::from_millis(125) * idx))
+            .collect()
+    }
+
+    /// Running clock at 120 BPM
+    fn running_clock() -> Clock {
+        let mut clock = Clock::new(120.0);
+        clock.start();
+        clock
+    }
+
+    fn hold(repeat: &mut NoteRepeat, pads: &[u8]) {
+        repeat.handle_event(&Event::Button(Button::NoteRepeat, true, false));
+        for &pad in pads {
+            repeat.handle_event(&Event::Pad(pad, 0x80, false));
+        }
+    }
+
+    #[test]
+    fn held_pads_repeat_only_while_note_repeat_is_held() {
+        let mut clock = running_clock();
+        let mut repeat = NoteRepeat::new();
+        let start = Instant::now();
+        repeat.handle_event(&Event::Pad(3, 0x80, false));
+        assert!(pads(&mut repeat, &mut clock, start).is_empty());
+
+        // Repeats start on the next tick
+        hold(&mut repeat, &[5]);
+        assert_eq!(
+            repeats(
+                &mut repeat,
+                &mut clock,
+                start + Duration::from_millis(125),
+                3
+            ),
+            vec![vec![3, 5], vec![3, 5], vec![3, 5]]
+        );
+
+        repeat.handle_event(&Event::Button(Button::NoteRepeat, false, false));
+        assert!(pads(&mut repeat, &mut clock, start + Duration::from_millis(500)).is_empty());
+    }
+
+    #[test]
+    fn notes_are_stamped_on_the_grid() {
+        let mut clock = running_clock();
+        let mut repeat = NoteRepeat::new();
+        hold(&mut repeat, &[0]);
+        let start = Instant::now();
+        pads(&mut repeat, &mut clock, start);
+
+        // A late call catches up with the notes it missed
+        let ticks = clock.advance(start + Duration::from_millis(260));
+        let notes = repeat.play_ticks(&clock, ticks);
+        let times: Vec<Duration> = notes.iter().map(|note| note.time - start).collect();
+        assert_eq!(
+            times,
+            vec![Duration::from_millis(125), Duration::from_millis(250)]
+        );
+    }
+
+    #[test]
+    fn velocity_follows_pad_pressure() {
+        let mut clock = running_clock();
+        let mut repeat = NoteRepeat::new();
+        hold(&mut repeat, &[2]);
+        let start = Instant::now();
+        let ticks = clock.advance(start);
+        assert_eq!(repeat.play_ticks(&clock, ticks)[0].velocity, 0x80);
+
+        repeat.handle_event(&Event::Pad(2, 0x20, false));
+        let ticks = clock.advance(start + Duration::from_millis(125));
+        assert_eq!(repeat.play_ticks(&clock, ticks)[0].velocity, 0x20);
+    }
+
+    #[test]
+    fn display_buttons_and_encoder_select_the_rate() {
+        let mut repeat = NoteRepeat::new();
+        repeat.handle_event(&Event::Button(Button::Display8, true, false));
+        assert_eq!(repeat.rate(), RepeatRate::Sixteenth);
+
+        hold(&mut repeat, &[]);
+        repeat.handle_event(&Event::Button(Button::Display8, true, false));
+        assert_eq!(repeat.rate().name(), "1/32T");
+        repeat.handle_event(&Event::Encoder(0, 3, 1, false));
+        assert_eq!(repeat.rate(), RepeatRate::ThirtySecondTriplet);
+        repeat.handle_event(&Event::Encoder(0, 1, -2, false));
+        assert_eq!(repeat.rate(), RepeatRate::ThirtySecond);
+    }
+
+    #[test]
+    fn arpeggio_cycles_through_held_pads() {
+        let mut clock = running_clock();
+        let mut repeat = NoteRepeat::new();
+        repeat.set_mode(RepeatMode::Arpeggio(ArpOrder::Up));
+        hold(&mut repeat, &[9, 1, 4]);
+        let start = Instant::now();
+        assert_eq!(
+            repeats(&mut repeat, &mut clock, start, 4),
+            vec![vec![1], vec![4], vec![9], vec![1]]
+        );
+
+        // Pressing NoteRepeat again starts from the new mode's first pad
+        repeat.set_mode(RepeatMode::Arpeggio(ArpOrder::Down));
+        repeat.handle_event(&Event::Button(Button::NoteRepeat, true, false));
+        let start = start + Duration::from_secs(1);
+        let notes = repeats(&mut repeat, &mut clock, start, 4);
+        assert_eq!(notes, vec![vec![9], vec![4], vec![1], vec![9]]);
+    }
+
+    #[test]
+    fn repeats_follow_the_clock_tempo() {
+        // 20 ms per tick, 1/8 notes are 240 ms apart
+        let mut clock = Clock::new(125.0);
+        let mut repeat = NoteRepeat::new();
+        repeat.set_rate(RepeatRate::Eighth);
+        hold(&mut repeat, &[7]);
+
+        let start = Instant::now();
+        clock.start();
+        let mut times = Vec::new();
+        for step in 0..=10 {
+            let ticks = clock.advance(start + Duration::from_millis(100) * step);
+            times.extend(
+                repeat
+                    .play_ticks(&clock, ticks)
+                    .iter()
+                    .map(|note| note.time - start),
+            );
+            if step == 5 {
+                // A tapped tempo reaches the repeats from the next tick
+                clock.set_tempo(62.5);
+            }
+        }
+        assert_eq!(
+            times,
+            vec![
+                Duration::from_millis(0),
+                Duration::from_millis(240),
+                Duration::from_millis(480),
+                Duration::from_millis(920),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeats_follow_midi_clock() {
+        let mut clock = Clock::new(120.0);
+        clock.set_source(ClockSource::Midi);
+        let mut repeat = NoteRepeat::new();
+        repeat.set_rate(RepeatRate::ThirtySecondTriplet);
+        hold(&mut repeat, &[1]);
+
+        let start = Instant::now();
+        let mut count = 0;
+        for idx in 0..=PPQN as u32 {
+            let message = if idx == 0 { MIDI_START } else { MIDI_CLOCK };
+            let ticks = clock.handle_midi(message, start + Duration::from_millis(20) * idx);
+            count += repeat.play_ticks(&clock, ticks).len();
+        }
+        assert_eq!(count, 12);
+    }
+}
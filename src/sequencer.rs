@@ -0,0 +1,569 @@
+use crate::clock::{Clock, ClockSource, MIDI_START, PPQN};
+use crate::{Button, Color, Device, Event, LedColor};
+use rand::Rng;
+use std::ops::Range;
+use std::time::Instant;
+
+/// Number of steps in a pattern, one per pad
+pub const STEPS: usize = 16;
+
+/// Clock ticks per sixteenth note step
+const TICKS_PER_STEP: u64 = PPQN / 4;
+
+/// Note of the first track, further tracks count up from here (General MIDI bass drum)
+const FIRST_NOTE: u8 = 36;
+
+const PLAYHEAD_COLOR: LedColor = LedColor::new(0xFF, 0xFF, 0xFF);
+const LED_OFF: LedColor = LedColor::OFF;
+
+///
+/// Single step of a track
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    /// Step plays a note
+    pub active: bool,
+
+    /// Note velocity, 1 - 127
+    pub velocity: u8,
+
+    /// Chance of the step playing each time round, 0 - 100 percent
+    pub probability: u8,
+}
+
+impl Default for Step {
+    fn default() -> Self {
+        Self {
+            active: false,
+            velocity: 100,
+            probability: 100,
+        }
+    }
+}
+
+///
+/// Steps of a single note
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Track {
+    /// MIDI note played by the track
+    pub note: u8,
+
+    /// Steps in playing order
+    pub steps: [Step; STEPS],
+}
+
+impl Track {
+    ///
+    /// Create a track with every step off
+    ///
+    /// **Arguments**
+    /// - note - MIDI note played by the track
+    pub fn new(note: u8) -> Self {
+        Self {
+            note,
+            steps: [Step::default(); STEPS],
+        }
+    }
+
+    /// Turn every step off
+    pub fn clear(&mut self) {
+        self.steps = [Step::default(); STEPS];
+    }
+}
+
+///
+/// Pattern of 16 steps by a number of tracks
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pattern {
+    pub tracks: Vec<Track>,
+}
+
+impl Pattern {
+    ///
+    /// Create an empty pattern, tracks play consecutive notes starting from the bass drum
+    ///
+    /// **Arguments**
+    /// - tracks - Number of tracks
+    pub fn new(tracks: usize) -> Self {
+        Self {
+            tracks: (0..tracks)
+                .map(|track| Track::new(FIRST_NOTE.saturating_add(track as u8)))
+                .collect(),
+        }
+    }
+
+    /// Turn every step of every track off
+    pub fn clear(&mut self) {
+        for track in self.tracks.iter_mut() {
+            track.clear();
+        }
+    }
+}
+
+///
+/// Note played by the sequencer
+///
+/// Notes are drum hits, there is no matching note off.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Note {
+    /// Track that played the note
+    pub track: usize,
+
+    /// MIDI note
+    pub note: u8,
+
+    /// Note velocity, 1 - 127
+    pub velocity: u8,
+}
+
+///
+/// 16 step sequencer played from the pads
+///
+/// The pads show the steps of the selected track and the running playhead. Pressing a pad toggles
+/// its step, or while recording, plays the track of the pad and records it on the current step.
+/// Shift with a pad selects the track.
+///
+/// Play starts and stops the sequencer, Rec toggles recording and Erase clears the selected track
+/// (every track with Shift held). Steps are sixteenth notes of the sequencer's `Clock`.
+///
+pub struct Sequencer {
+    pattern: Pattern,
+    clock: Clock,
+    on_note: Box<dyn FnMut(Note)>,
+
+    recording: bool,
+    selected_track: usize,
+    position: Option<usize>,
+    pads_held: [bool; STEPS],
+
+    pad_leds: [Option<LedColor>; STEPS],
+    button_leds: [Option<bool>; 2],
+}
+
+impl Sequencer {
+    ///
+    /// Create a stopped sequencer with an empty pattern at 120 BPM
+    ///
+    /// **Arguments**
+    /// - tracks - Number of tracks in the pattern
+    /// - on_note - Called with each note played
+    pub fn new(tracks: usize, on_note: impl FnMut(Note) + 'static) -> Self {
+        Self {
+            pattern: Pattern::new(tracks),
+            clock: Clock::new(120.0),
+            on_note: Box::new(on_note),
+
+            recording: false,
+            selected_track: 0,
+            position: None,
+            pads_held: [false; STEPS],
+
+            pad_leds: [None; STEPS],
+            button_leds: [None; 2],
+        }
+    }
+
+    /// Pattern being played
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// Pattern being played, for editing
+    pub fn pattern_mut(&mut self) -> &mut Pattern {
+        &mut self.pattern
+    }
+
+    ///
+    /// Replace the pattern being played
+    ///
+    /// **Arguments**
+    /// - pattern - New pattern
+    pub fn set_pattern(&mut self, pattern: Pattern) {
+        self.pattern = pattern;
+        self.selected_track = self
+            .selected_track
+            .min(self.pattern.tracks.len().saturating_sub(1));
+    }
+
+    /// Clock the steps follow
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Clock the steps follow, to change its tempo or source
+    pub fn clock_mut(&mut self) -> &mut Clock {
+        &mut self.clock
+    }
+
+    /// Start playing from the first step
+    pub fn play(&mut self) {
+        self.clock.start();
+        self.position = None;
+    }
+
+    /// Stop playing
+    pub fn stop(&mut self) {
+        self.clock.stop();
+        self.position = None;
+    }
+
+    /// Sequencer is playing
+    pub fn is_playing(&self) -> bool {
+        self.clock.is_running()
+    }
+
+    ///
+    /// Turn recording from the pads on or off
+    ///
+    /// **Arguments**
+    /// - recording - Record pads onto the current step
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+    }
+
+    /// Pads are recorded onto the current step
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Track shown and edited on the pads
+    pub fn selected_track(&self) -> usize {
+        self.selected_track
+    }
+
+    ///
+    /// Select the track shown and edited on the pads
+    ///
+    /// **Arguments**
+    /// - track - Track index, ignored when out of range
+    pub fn select_track(&mut self, track: usize) {
+        if track < self.pattern.tracks.len() {
+            self.selected_track = track;
+        }
+    }
+
+    /// Step under the playhead, `None` when stopped
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    ///
+    /// Update the sequencer from a device event
+    ///
+    /// **Arguments**
+    /// - event - Event from the device
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::Button(Button::Play, true, _) => {
+                if self.is_playing() {
+                    self.stop();
+                } else {
+                    self.play();
+                }
+            }
+            Event::Button(Button::Rec, true, _) => self.recording = !self.recording,
+            Event::Button(Button::Erase, true, shift) => {
+                if shift {
+                    self.pattern.clear();
+                } else if let Some(track) = self.pattern.tracks.get_mut(self.selected_track) {
+                    track.clear();
+                }
+            }
+            Event::Pad(pad, velocity, shift) => {
+                let pad = pad as usize;
+                let Some(held) = self.pads_held.get_mut(pad) else {
+                    return;
+                };
+                // Only act on the press, a held pad keeps reporting its pressure
+                let pressed = velocity > 0;
+                let was_held = std::mem::replace(held, pressed);
+                if !pressed || was_held {
+                    return;
+                }
+
+                if shift {
+                    self.select_track(pad);
+                } else if self.recording && self.is_playing() {
+                    self.record(pad, velocity);
+                } else if let Some(track) = self.pattern.tracks.get_mut(self.selected_track) {
+                    if let Some(step) = track.steps.get_mut(pad) {
+                        step.active = !step.active;
+                        step.velocity = midi_velocity(velocity);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ///
+    /// Play any steps that are due on the internal clock
+    ///
+    /// Call this regularly, eg after each device tick. Does nothing when following MIDI clock.
+    ///
+    /// **Arguments**
+    /// - now - Current time
+    pub fn advance(&mut self, now: Instant) {
+        let ticks = self.clock.advance(now);
+        self.play_ticks(ticks);
+    }
+
+    ///
+    /// Follow an incoming MIDI real time message, when the clock follows MIDI clock
+    ///
+    /// MIDI stop clears the playhead and MIDI start plays from the first step.
+    ///
+    /// **Arguments**
+    /// - message - MIDI status byte
+    /// - now - Time the message arrived
+    pub fn handle_midi(&mut self, message: u8, now: Instant) {
+        let restarted = message == MIDI_START && self.clock.source() == ClockSource::Midi;
+        let ticks = self.clock.handle_midi(message, now);
+        if restarted || !self.clock.is_running() {
+            self.position = None;
+        }
+        self.play_ticks(ticks);
+    }
+
+    ///
+    /// Show the selected track, the playhead and the transport on the device LEDs
+    ///
+    /// Only LEDs that have changed since the last call are updated.
+    ///
+    /// **Arguments**
+    /// - device - Device to update
+    pub fn render_leds<D: Device + ?Sized>(&mut self, device: &mut D) {
+        let track = self.pattern.tracks.get(self.selected_track);
+        for pad in 0..STEPS {
+            let step = track.map(|track| track.steps[pad]).unwrap_or_default();
+            let color = if self.position == Some(pad) {
+                PLAYHEAD_COLOR
+            } else if step.active {
+                // Dim quiet steps
+                let level = 0x40 + step.velocity.min(127) as u16 * 0xBF / 127;
+                LedColor::new(level as u8, (level / 4) as u8, 0x00)
+            } else {
+                LED_OFF
+            };
+            if self.pad_leds[pad] != Some(color) {
+                self.pad_leds[pad] = Some(color);
+                device.set_pad_led(pad as u8, color.into());
+            }
+        }
+
+        let playing = self.is_playing();
+        for (idx, (button, lit)) in [(Button::Play, playing), (Button::Rec, self.recording)]
+            .into_iter()
+            .enumerate()
+        {
+            if self.button_leds[idx] != Some(lit) {
+                self.button_leds[idx] = Some(lit);
+                let color = if lit {
+                    Color::new(0xFF, 0xFF, 0xFF, 0xFF)
+                } else {
+                    LED_OFF.into()
+                };
+                device.set_button_led(button, color);
+            }
+        }
+    }
+
+    /// Play the steps falling on clock ticks
+    fn play_ticks(&mut self, ticks: Range<u64>) {
+        for tick in ticks.filter(|tick| tick.is_multiple_of(TICKS_PER_STEP)) {
+            self.step((tick / TICKS_PER_STEP) as usize % STEPS);
+        }
+    }
+
+    /// Move the playhead to a step and play the notes on it
+    fn step(&mut self, position: usize) {
+        self.position = Some(position);
+
+        let mut rng = rand::thread_rng();
+        for (idx, track) in self.pattern.tracks.iter().enumerate() {
+            let step = track.steps[position];
+            if step.active && rng.gen_range(0..100) < step.probability {
+                (self.on_note)(Note {
+                    track: idx,
+                    note: track.note,
+                    velocity: step.velocity,
+                });
+            }
+        }
+    }
+
+    /// Play the track of a pad and record it on the current step
+    fn record(&mut self, track: usize, velocity: u8) {
+        let Some(position) = self.position else {
+            return;
+        };
+        if let Some(pattern_track) = self.pattern.tracks.get_mut(track) {
+            let velocity = midi_velocity(velocity);
+            pattern_track.steps[position] = Step {
+                active: true,
+                velocity,
+                probability: 100,
+            };
+            (self.on_note)(Note {
+                track,
+                note: pattern_track.note,
+                velocity,
+            });
+        }
+    }
+}
+
+/// Scale a pad velocity to a MIDI velocity
+fn midi_velocity(velocity: u8) -> u8 {
+    (velocity >> 1).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{MIDI_CLOCK, MIDI_CONTINUE, MIDI_STOP};
+    use crate::devices::testing::TestDevice;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    /// Sequencer recording the notes it plays
+    fn sequencer(tracks: usize) -> (Sequencer, Rc<RefCell<Vec<Note>>>) {
+        let notes = Rc::new(RefCell::new(Vec::new()));
+        let played = notes.clone();
+        let sequencer = Sequencer::new(tracks, move |note| played.borrow_mut().push(note));
+        (sequencer, notes)
+    }
+
+    #[test]
+    fn pads_toggle_steps_of_the_selected_track() {
+        let (mut sequencer, _) = sequencer(4);
+        sequencer.handle_event(&Event::Pad(2, 0xFF, true));
+        sequencer.handle_event(&Event::Pad(5, 0x80, false));
+        assert_eq!(sequencer.selected_track(), 2);
+        assert!(sequencer.pattern().tracks[2].steps[5].active);
+        assert_eq!(sequencer.pattern().tracks[2].steps[5].velocity, 64);
+
+        sequencer.handle_event(&Event::Pad(5, 0, false));
+        sequencer.handle_event(&Event::Pad(5, 0x80, false));
+        assert!(!sequencer.pattern().tracks[2].steps[5].active);
+    }
+
+    #[test]
+    fn held_pads_toggle_their_step_once() {
+        let (mut sequencer, _) = sequencer(1);
+        sequencer.handle_event(&Event::Pad(4, 0x40, false));
+        sequencer.handle_event(&Event::Pad(4, 0x90, false));
+        sequencer.handle_event(&Event::Pad(4, 0, false));
+        assert!(sequencer.pattern().tracks[0].steps[4].active);
+        assert_eq!(sequencer.pattern().tracks[0].steps[4].velocity, 32);
+    }
+
+    #[test]
+    fn internal_clock_plays_sixteenth_notes() {
+        let (mut sequencer, notes) = sequencer(2);
+        sequencer.pattern_mut().tracks[0].steps[0].active = true;
+        sequencer.pattern_mut().tracks[1].steps[2].active = true;
+        sequencer.handle_event(&Event::Button(Button::Play, true, false));
+
+        // 125 ms per step at 120 BPM
+        let start = Instant::now();
+        sequencer.advance(start);
+        assert_eq!(sequencer.position(), Some(0));
+        sequencer.advance(start + Duration::from_millis(300));
+        assert_eq!(sequencer.position(), Some(2));
+
+        let played: Vec<(usize, u8)> = notes.borrow().iter().map(|n| (n.track, n.note)).collect();
+        assert_eq!(played, vec![(0, 36), (1, 37)]);
+    }
+
+    #[test]
+    fn probability_zero_never_plays() {
+        let (mut sequencer, notes) = sequencer(1);
+        sequencer.pattern_mut().tracks[0].steps = [Step {
+            active: true,
+            velocity: 100,
+            probability: 0,
+        }; STEPS];
+        sequencer.play();
+        sequencer.advance(Instant::now() + Duration::from_secs(10));
+        assert!(notes.borrow().is_empty());
+    }
+
+    #[test]
+    fn midi_clock_steps_every_six_clocks() {
+        let (mut sequencer, _) = sequencer(1);
+        sequencer.clock_mut().set_source(ClockSource::Midi);
+        let start = Instant::now();
+        sequencer.handle_midi(MIDI_START, start);
+        sequencer.advance(start + Duration::from_secs(10));
+        assert_eq!(sequencer.position(), None);
+
+        for idx in 0..13 {
+            sequencer.handle_midi(MIDI_CLOCK, start + Duration::from_millis(20) * idx);
+        }
+        assert_eq!(sequencer.position(), Some(2));
+
+        sequencer.handle_midi(MIDI_STOP, start);
+        sequencer.handle_midi(MIDI_CLOCK, start);
+        sequencer.handle_midi(MIDI_CONTINUE, start);
+        assert!(sequencer.is_playing());
+
+        // Playing on from tick 13, the next step is on tick 18
+        for _ in 0..6 {
+            sequencer.handle_midi(MIDI_CLOCK, start);
+        }
+        assert_eq!(sequencer.position(), Some(3));
+    }
+
+    #[test]
+    fn midi_stop_and_start_clear_the_playhead() {
+        let (mut sequencer, _) = sequencer(1);
+        sequencer.clock_mut().set_source(ClockSource::Midi);
+        let start = Instant::now();
+        sequencer.handle_midi(MIDI_START, start);
+        for _ in 0..7 {
+            sequencer.handle_midi(MIDI_CLOCK, start);
+        }
+        assert_eq!(sequencer.position(), Some(1));
+
+        sequencer.handle_midi(MIDI_STOP, start);
+        assert!(!sequencer.is_playing());
+        assert_eq!(sequencer.position(), None);
+
+        // The stopped transport shows no playhead
+        let mut device = TestDevice::new().unwrap();
+        sequencer.render_leds(&mut device);
+        assert_eq!(device.leds.pads[1], LedColor::OFF);
+
+        sequencer.handle_midi(MIDI_CONTINUE, start);
+        sequencer.handle_midi(MIDI_START, start);
+        assert_eq!(sequencer.position(), None);
+        sequencer.handle_midi(MIDI_CLOCK, start);
+        assert_eq!(sequencer.position(), Some(0));
+    }
+
+    #[test]
+    fn recording_captures_pads_on_the_current_step() {
+        let (mut sequencer, notes) = sequencer(4);
+        sequencer.handle_event(&Event::Button(Button::Rec, true, false));
+        sequencer.handle_event(&Event::Button(Button::Play, true, false));
+        let start = Instant::now();
+        sequencer.advance(start);
+        sequencer.advance(start + Duration::from_millis(130));
+        sequencer.handle_event(&Event::Pad(3, 0xFF, false));
+        sequencer.handle_event(&Event::Pad(3, 0xC0, false));
+
+        assert!(sequencer.pattern().tracks[3].steps[1].active);
+        assert_eq!(notes.borrow().len(), 1);
+
+        sequencer.handle_event(&Event::Button(Button::Erase, true, true));
+        assert!(!sequencer.pattern().tracks[3].steps[1].active);
+    }
+}
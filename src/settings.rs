@@ -0,0 +1,207 @@
+use crate::events::Event;
+
+/// Highest pad velocity reported in `Event::Pad`
+const VELOCITY_MAX: f32 = 0xFF as f32;
+
+///
+/// Shape of the pad velocity response
+///
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VelocityCurve {
+    ///
+    /// Velocity follows the pressure
+    ///
+    #[default]
+    Linear,
+
+    ///
+    /// Light hits play louder
+    ///
+    Soft,
+
+    ///
+    /// Light hits play quieter
+    ///
+    Hard,
+}
+
+///
+/// User settings for the pads
+///
+/// Applied to the pad events of any device with `apply`.
+///
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PadSettings {
+    /// Shape of the velocity response
+    pub curve: VelocityCurve,
+
+    /// Play every hit at this velocity regardless of the pressure
+    pub fixed_velocity: Option<u8>,
+}
+
+impl PadSettings {
+    ///
+    /// Adjust the velocity of a pad event, other events are returned unchanged
+    ///
+    /// Releases keep a velocity of 0 and pressed pads never drop to 0.
+    ///
+    /// **Arguments**
+    /// - event - Event from the device
+    pub fn apply(&self, event: Event) -> Event {
+        let Event::Pad(pad, velocity, shift) = event else {
+            return event;
+        };
+        if velocity == 0 {
+            return event;
+        }
+
+        let velocity = self.fixed_velocity.unwrap_or_else(|| {
+            let level = velocity as f32 / VELOCITY_MAX;
+            let level = match self.curve {
+                VelocityCurve::Linear => level,
+                VelocityCurve::Soft => level.sqrt(),
+                VelocityCurve::Hard => level * level,
+            };
+            (level * VELOCITY_MAX).round() as u8
+        });
+        Event::Pad(pad, velocity.max(1), shift)
+    }
+}
+
+///
+/// User settings for the encoders
+///
+/// Applied to the encoder events of any device with `apply`.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct EncoderSettings {
+    /// Steps reported for each step turned, at least 1
+    pub speed: u8,
+
+    /// Turn the direction around
+    pub reversed: bool,
+}
+
+impl Default for EncoderSettings {
+    fn default() -> Self {
+        Self {
+            speed: 1,
+            reversed: false,
+        }
+    }
+}
+
+impl EncoderSettings {
+    ///
+    /// Adjust the movement of an encoder event, other events are returned unchanged
+    ///
+    /// Only the reported movement changes, the encoder position is left as read from the device.
+    ///
+    /// **Arguments**
+    /// - event - Event from the device
+    pub fn apply(&self, event: Event) -> Event {
+        let Event::Encoder(encoder, value, delta, shift) = event else {
+            return event;
+        };
+
+        let delta = delta.saturating_mul(self.speed.max(1) as i16);
+        let delta = if self.reversed { -delta } else { delta };
+        Event::Encoder(encoder, value, delta, shift)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Button;
+
+    #[test]
+    fn pad_curves_shape_the_velocity() {
+        let hit = Event::Pad(3, 0x40, true);
+        let velocity = |curve| match (PadSettings {
+            curve,
+            ..PadSettings::default()
+        })
+        .apply(hit)
+        {
+            Event::Pad(3, velocity, true) => velocity,
+            event => panic!("unexpected {event:?}"),
+        };
+        assert_eq!(velocity(VelocityCurve::Linear), 0x40);
+        assert_eq!(velocity(VelocityCurve::Soft), 0x80);
+        assert_eq!(velocity(VelocityCurve::Hard), 0x10);
+    }
+
+    #[test]
+    fn fixed_velocity_keeps_releases() {
+        let settings = PadSettings {
+            fixed_velocity: Some(100),
+            ..PadSettings::default()
+        };
+        assert_eq!(
+            format!("{:?}", settings.apply(Event::Pad(1, 0x08, false))),
+            "Pad(1, 100, false)"
+        );
+        assert_eq!(
+            format!("{:?}", settings.apply(Event::Pad(1, 0, false))),
+            "Pad(1, 0, false)"
+        );
+
+        // Light hits stay pressed on a hard curve
+        let hard = PadSettings {
+            curve: VelocityCurve::Hard,
+            ..PadSettings::default()
+        };
+        assert_eq!(
+            format!("{:?}", hard.apply(Event::Pad(0, 1, false))),
+            "Pad(0, 1, false)"
+        );
+    }
+
+    #[test]
+    fn encoder_settings_scale_and_reverse_the_movement() {
+        let settings = EncoderSettings {
+            speed: 4,
+            reversed: true,
+        };
+        assert_eq!(
+            format!("{:?}", settings.apply(Event::Encoder(2, 0x0A, -2, false))),
+            "Encoder(2, 10, 8, false)"
+        );
+
+        let play = Event::Button(Button::Play, true, false);
+        assert_eq!(
+            format!("{:?}", settings.apply(play)),
+            "Button(Play, true, false)"
+        );
+        assert_eq!(
+            format!("{:?}", PadSettings::default().apply(play)),
+            "Button(Play, true, false)"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn settings_round_trip() {
+        let pads = PadSettings {
+            curve: VelocityCurve::Soft,
+            fixed_velocity: Some(0x7F),
+        };
+        let json = serde_json::to_string(&pads).unwrap();
+        assert_eq!(serde_json::from_str::<PadSettings>(&json).unwrap(), pads);
+
+        let encoders: EncoderSettings = serde_json::from_str(r#"{"reversed":true}"#).unwrap();
+        assert_eq!(
+            encoders,
+            EncoderSettings {
+                speed: 1,
+                reversed: true
+            }
+        );
+    }
+}
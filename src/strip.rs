@@ -0,0 +1,290 @@
+use crate::events::Event;
+
+/// Highest position and pressure reported by a touch strip
+const STRIP_MAX: f32 = 0x3FF as f32;
+
+///
+/// Interpretation of a touch strip
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StripMode {
+    ///
+    /// Bend around the centre of the strip, springs back to the centre on release
+    ///
+    PitchBend,
+
+    ///
+    /// Absolute position, stays where it was left on release
+    ///
+    ModWheel,
+
+    ///
+    /// Movement while touched, in steps of `sensitivity` position units
+    ///
+    Scroll { sensitivity: u16 },
+}
+
+///
+/// Value derived from a touch strip event
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StripValue {
+    ///
+    /// Bend from -1.0 (bottom) to 1.0 (top), 0.0 is the centre
+    ///
+    PitchBend(f32),
+
+    ///
+    /// Position from 0.0 (bottom) to 1.0 (top)
+    ///
+    ModWheel(f32),
+
+    ///
+    /// Steps moved since the last value, positive towards the top
+    ///
+    Scroll(i16),
+}
+
+///
+/// What the LEDs along a touch strip show
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StripLeds {
+    ///
+    /// Every LED off
+    ///
+    Off,
+
+    ///
+    /// Single LED at a position from 0.0 (bottom) to 1.0 (top)
+    ///
+    Position(f32),
+
+    ///
+    /// LEDs lit from the bottom up to a level from 0.0 to 1.0
+    ///
+    Meter(f32),
+}
+
+impl StripLeds {
+    ///
+    /// Determine if a LED of the strip is lit
+    ///
+    /// **Arguments**
+    /// - led - LED index, 0 is the bottom
+    /// - count - Number of LEDs along the strip
+    pub fn is_lit(&self, led: usize, count: usize) -> bool {
+        match *self {
+            StripLeds::Off => false,
+            StripLeds::Position(position) => {
+                let lit = (position.clamp(0.0, 1.0) * (count.max(1) - 1) as f32).round();
+                led == lit as usize
+            }
+            StripLeds::Meter(level) => led < crate::leds::lit_segments(level, count),
+        }
+    }
+
+    /// Write the LED values of a strip, `on` for lit LEDs and 0 for the others
+    pub(crate) fn write(&self, leds: &mut [u8], on: u8) {
+        let count = leds.len();
+        for (led, value) in leds.iter_mut().enumerate() {
+            *value = if self.is_lit(led, count) { on } else { 0x00 };
+        }
+    }
+}
+
+///
+/// Touch strip with a derived mode
+///
+/// Feed it the events of a device, it picks out the events of its strip and turns them into
+/// pitch bend, mod wheel or scroll values.
+///
+#[derive(Debug, Clone)]
+pub struct SmartStrip {
+    strip: u8,
+    mode: StripMode,
+    value: f32,
+    last_position: Option<u16>,
+    remainder: i32,
+}
+
+impl SmartStrip {
+    ///
+    /// Create a strip in the given mode, centred for pitch bend and at 0.0 otherwise
+    ///
+    /// **Arguments**
+    /// - strip - Strip number, as in `Event::Strip`
+    /// - mode - Interpretation of the strip
+    pub fn new(strip: u8, mode: StripMode) -> Self {
+        Self {
+            strip,
+            mode,
+            value: 0.0,
+            last_position: None,
+            remainder: 0,
+        }
+    }
+
+    /// Interpretation of the strip
+    pub fn mode(&self) -> StripMode {
+        self.mode
+    }
+
+    ///
+    /// Change the interpretation of the strip, resetting its value
+    ///
+    /// **Arguments**
+    /// - mode - Interpretation of the strip
+    pub fn set_mode(&mut self, mode: StripMode) {
+        *self = Self::new(self.strip, mode);
+    }
+
+    ///
+    /// Derive a value from an event
+    ///
+    /// Returns `None` for events of other controls and for changes that do not move the value.
+    ///
+    /// **Arguments**
+    /// - event - Event from the device
+    pub fn process(&mut self, event: &Event) -> Option<StripValue> {
+        let Event::Strip(strip, position, _pressure, touches, _shift) = *event else {
+            return None;
+        };
+        if strip != self.strip {
+            return None;
+        }
+
+        let released = touches == 0;
+        match self.mode {
+            StripMode::PitchBend => {
+                let bend = if released {
+                    0.0
+                } else {
+                    (position as f32 * 2.0 / STRIP_MAX - 1.0).clamp(-1.0, 1.0)
+                };
+                self.update(bend).map(StripValue::PitchBend)
+            }
+            StripMode::ModWheel => {
+                if released {
+                    return None;
+                }
+                self.update(position as f32 / STRIP_MAX)
+                    .map(StripValue::ModWheel)
+            }
+            StripMode::Scroll { sensitivity } => {
+                let previous = self.last_position;
+                self.last_position = (!released).then_some(position);
+                self.value = position as f32 / STRIP_MAX;
+
+                // Touching down only sets the starting point, remaining movement is kept
+                // until it makes up a whole step
+                let Some(previous) = previous.filter(|_| !released) else {
+                    self.remainder = 0;
+                    return None;
+                };
+                let sensitivity = sensitivity.max(1) as i32;
+                self.remainder += position as i32 - previous as i32;
+                let steps = self.remainder / sensitivity;
+                self.remainder %= sensitivity;
+                (steps != 0).then_some(StripValue::Scroll(steps as i16))
+            }
+        }
+    }
+
+    ///
+    /// LEDs showing the current value
+    ///
+    /// Pitch bend and scrolling show their position, the mod wheel shows a meter.
+    ///
+    pub fn leds(&self) -> StripLeds {
+        match self.mode {
+            StripMode::PitchBend => StripLeds::Position((self.value + 1.0) / 2.0),
+            StripMode::ModWheel => StripLeds::Meter(self.value),
+            StripMode::Scroll { .. } if self.last_position.is_some() => {
+                StripLeds::Position(self.value)
+            }
+            StripMode::Scroll { .. } => StripLeds::Off,
+        }
+    }
+
+    /// Store a new value, returning it when it changed
+    fn update(&mut self, value: f32) -> Option<f32> {
+        if value == self.value {
+            return None;
+        }
+        self.value = value;
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(strip: u8, position: u16) -> Event {
+        Event::Strip(strip, position, 0x100, 1, false)
+    }
+
+    fn release(strip: u8) -> Event {
+        Event::Strip(strip, 0, 0, 0, false)
+    }
+
+    #[test]
+    fn pitch_bend_springs_back_on_release() {
+        let mut strip = SmartStrip::new(0, StripMode::PitchBend);
+        assert_eq!(
+            strip.process(&touch(0, 0x3FF)),
+            Some(StripValue::PitchBend(1.0))
+        );
+        assert_eq!(
+            strip.process(&touch(0, 0)),
+            Some(StripValue::PitchBend(-1.0))
+        );
+        assert_eq!(strip.process(&release(0)), Some(StripValue::PitchBend(0.0)));
+        assert_eq!(strip.leds(), StripLeds::Position(0.5));
+    }
+
+    #[test]
+    fn mod_wheel_holds_its_value() {
+        let mut strip = SmartStrip::new(1, StripMode::ModWheel);
+        assert_eq!(strip.process(&touch(0, 0x3FF)), None);
+        assert_eq!(
+            strip.process(&touch(1, 0x3FF)),
+            Some(StripValue::ModWheel(1.0))
+        );
+        assert_eq!(strip.process(&release(1)), None);
+        assert_eq!(strip.leds(), StripLeds::Meter(1.0));
+    }
+
+    #[test]
+    fn scroll_counts_steps_while_touched() {
+        let mut strip = SmartStrip::new(0, StripMode::Scroll { sensitivity: 0x40 });
+        assert_eq!(strip.process(&touch(0, 0x200)), None);
+        assert_eq!(strip.process(&touch(0, 0x230)), None);
+        assert_eq!(strip.process(&touch(0, 0x2A0)), Some(StripValue::Scroll(2)));
+        assert_eq!(
+            strip.process(&touch(0, 0x1A0)),
+            Some(StripValue::Scroll(-3))
+        );
+        assert_eq!(strip.process(&release(0)), None);
+        assert_eq!(strip.leds(), StripLeds::Off);
+
+        // A new touch starts from where the finger lands
+        assert_eq!(strip.process(&touch(0, 0x3FF)), None);
+    }
+
+    #[test]
+    fn leds_by_position_and_meter() {
+        let lit = |leds: StripLeds| {
+            (0..5)
+                .filter(|&led| leds.is_lit(led, 5))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lit(StripLeds::Off), Vec::<usize>::new());
+        assert_eq!(lit(StripLeds::Position(0.0)), vec![0]);
+        assert_eq!(lit(StripLeds::Position(0.5)), vec![2]);
+        assert_eq!(lit(StripLeds::Meter(0.6)), vec![0, 1, 2]);
+        assert_eq!(lit(StripLeds::Meter(1.0)), vec![0, 1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,518 @@
+use super::{fill, outline, Widget};
+use crate::display::{Alignment, DrawText, Overflow, TextStyle};
+use raqote::{
+    AntialiasMode, DrawOptions, DrawTarget, IntRect, LineCap, PathBuilder, SolidSource, Source,
+    StrokeStyle,
+};
+use std::f32::consts::PI;
+
+/// Sweep of a knob arc, leaving a gap at the bottom
+const KNOB_EXTENT: f32 = PI * 1.5;
+const KNOB_START: f32 = PI * 1.5 - KNOB_EXTENT / 2.;
+
+/// Split the top line from an area for a caption
+fn split_caption(bounds: IntRect, style: &TextStyle) -> (IntRect, IntRect) {
+    let split = bounds.min.y + style.font.height() as i32 + 1;
+    (
+        IntRect::new(bounds.min, (bounds.max.x, split).into()),
+        IntRect::new((bounds.min.x, split).into(), bounds.max),
+    )
+}
+
+///
+/// Single line of text
+///
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub bounds: IntRect,
+    pub text: String,
+    pub style: TextStyle,
+}
+
+impl Label {
+    pub fn new(bounds: IntRect, text: &str) -> Self {
+        Self {
+            bounds,
+            text: text.to_string(),
+            style: TextStyle {
+                alignment: Alignment::Center,
+                overflow: Overflow::Ellipsis,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Widget for Label {
+    fn bounds(&self) -> IntRect {
+        self.bounds
+    }
+
+    fn draw(&self, dt: &mut DrawTarget) {
+        dt.draw_bitmap_text(&self.text, self.bounds, &self.style);
+    }
+}
+
+///
+/// Parameter knob, an arc showing a value with its name and value text underneath
+///
+#[derive(Debug, Clone)]
+pub struct Knob {
+    pub bounds: IntRect,
+    pub name: String,
+    pub value_text: String,
+
+    /// Position of the knob, 0.0 to 1.0
+    pub value: f32,
+}
+
+impl Knob {
+    pub fn new(bounds: IntRect, name: &str) -> Self {
+        Self {
+            bounds,
+            name: name.to_string(),
+            value_text: String::new(),
+            value: 0.,
+        }
+    }
+}
+
+impl Widget for Knob {
+    fn bounds(&self) -> IntRect {
+        self.bounds
+    }
+
+    fn draw(&self, dt: &mut DrawTarget) {
+        let style = TextStyle {
+            alignment: Alignment::Center,
+            overflow: Overflow::Ellipsis,
+            ..Default::default()
+        };
+        let line_height = style.font.height() as i32;
+        let (min, max) = (self.bounds.min, self.bounds.max);
+
+        let arc_height = self.bounds.height() - 2 * line_height;
+        let radius = (arc_height.min(self.bounds.width()) as f32 / 2. - 3.).max(1.);
+        let (x, y) = (
+            (min.x + max.x) as f32 / 2.,
+            min.y as f32 + arc_height as f32 / 2.,
+        );
+
+        let stroke = |dt: &mut DrawTarget, extent: f32, width: f32| {
+            let mut pb = PathBuilder::new();
+            pb.arc(x, y, radius, KNOB_START, extent);
+            dt.stroke(
+                &pb.finish(),
+                &Source::Solid(SolidSource {
+                    r: 0xFF,
+                    g: 0xFF,
+                    b: 0xFF,
+                    a: 0xFF,
+                }),
+                &StrokeStyle {
+                    cap: LineCap::Butt,
+                    width,
+                    ..Default::default()
+                },
+                &DrawOptions {
+                    antialias: AntialiasMode::None,
+                    ..Default::default()
+                },
+            );
+        };
+        stroke(dt, KNOB_EXTENT, 1.);
+        stroke(dt, KNOB_EXTENT * self.value.clamp(0., 1.), 4.);
+
+        let name_top = max.y - 2 * line_height;
+        dt.draw_bitmap_text(
+            &self.name,
+            IntRect::new(
+                (min.x, name_top).into(),
+                (max.x, name_top + line_height).into(),
+            ),
+            &style,
+        );
+        dt.draw_bitmap_text(
+            &self.value_text,
+            IntRect::new((min.x, max.y - line_height).into(), max),
+            &style,
+        );
+    }
+}
+
+///
+/// Scrolling list of items with a highlighted selection
+///
+#[derive(Debug, Clone)]
+pub struct List {
+    pub bounds: IntRect,
+    pub items: Vec<String>,
+    pub selected: usize,
+    pub style: TextStyle,
+}
+
+impl List {
+    pub fn new(bounds: IntRect, items: Vec<String>) -> Self {
+        Self {
+            bounds,
+            items,
+            selected: 0,
+            style: TextStyle {
+                overflow: Overflow::Ellipsis,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Move the selection by a number of items, eg from an encoder delta
+    pub fn scroll(&mut self, delta: i16) {
+        let last = self.items.len().saturating_sub(1) as i64;
+        self.selected = (self.selected as i64 + delta as i64).clamp(0, last) as usize;
+    }
+}
+
+impl Widget for List {
+    fn bounds(&self) -> IntRect {
+        self.bounds
+    }
+
+    fn draw(&self, dt: &mut DrawTarget) {
+        let line_height = self.style.font.height() as i32;
+        let visible = (self.bounds.height() / line_height).max(1) as usize;
+        // Keep the selection in view, centred where possible
+        let first = self
+            .selected
+            .saturating_sub(visible / 2)
+            .min(self.items.len().saturating_sub(visible));
+
+        for (row, (idx, item)) in self
+            .items
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(visible)
+            .enumerate()
+        {
+            let top = self.bounds.min.y + row as i32 * line_height;
+            let bounds = IntRect::new(
+                (self.bounds.min.x, top).into(),
+                (self.bounds.max.x, top + line_height).into(),
+            );
+            let style = TextStyle {
+                inverted: idx == self.selected,
+                ..self.style
+            };
+            dt.draw_bitmap_text(item, bounds, &style);
+        }
+    }
+}
+
+///
+/// Direction a meter or bar fills in
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Orientation {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+///
+/// Segmented level meter with an optional peak marker
+///
+#[derive(Debug, Clone)]
+pub struct Meter {
+    pub bounds: IntRect,
+    pub orientation: Orientation,
+
+    /// Current level, 0.0 to 1.0
+    pub level: f32,
+
+    /// Peak hold level, 0.0 to 1.0
+    pub peak: Option<f32>,
+}
+
+impl Meter {
+    pub fn new(bounds: IntRect, orientation: Orientation) -> Self {
+        Self {
+            bounds,
+            orientation,
+            level: 0.,
+            peak: None,
+        }
+    }
+}
+
+impl Widget for Meter {
+    fn bounds(&self) -> IntRect {
+        self.bounds
+    }
+
+    fn draw(&self, dt: &mut DrawTarget) {
+        let (min, max) = (self.bounds.min, self.bounds.max);
+        let length = match self.orientation {
+            Orientation::Vertical => self.bounds.height(),
+            Orientation::Horizontal => self.bounds.width(),
+        };
+        let to_pixels = |level: f32| (level.clamp(0., 1.) * length as f32).round() as i32;
+        let level = to_pixels(self.level);
+
+        // Segments are 2 pixels long with a 1 pixel gap
+        for offset in (0..level).filter(|offset| offset % 3 != 2) {
+            let segment = match self.orientation {
+                Orientation::Vertical => IntRect::new(
+                    (min.x, max.y - offset - 1).into(),
+                    (max.x, max.y - offset).into(),
+                ),
+                Orientation::Horizontal => IntRect::new(
+                    (min.x + offset, min.y).into(),
+                    (min.x + offset + 1, max.y).into(),
+                ),
+            };
+            fill(dt, segment, true);
+        }
+
+        if let Some(peak) = self.peak {
+            let offset = to_pixels(peak).clamp(1, length);
+            let marker = match self.orientation {
+                Orientation::Vertical => IntRect::new(
+                    (min.x, max.y - offset).into(),
+                    (max.x, max.y - offset + 1).into(),
+                ),
+                Orientation::Horizontal => IntRect::new(
+                    (min.x + offset - 1, min.y).into(),
+                    (min.x + offset, max.y).into(),
+                ),
+            };
+            fill(dt, marker, true);
+        }
+    }
+}
+
+///
+/// Outlined horizontal progress bar
+///
+#[derive(Debug, Clone)]
+pub struct ProgressBar {
+    pub bounds: IntRect,
+
+    /// Progress, 0.0 to 1.0
+    pub progress: f32,
+}
+
+impl ProgressBar {
+    pub fn new(bounds: IntRect) -> Self {
+        Self {
+            bounds,
+            progress: 0.,
+        }
+    }
+}
+
+impl Widget for ProgressBar {
+    fn bounds(&self) -> IntRect {
+        self.bounds
+    }
+
+    fn draw(&self, dt: &mut DrawTarget) {
+        outline(dt, self.bounds);
+        let inner = self.bounds.inflate(-2, -2);
+        let filled = (self.progress.clamp(0., 1.) * inner.width() as f32).round() as i32;
+        fill(
+            dt,
+            IntRect::new(inner.min, (inner.min.x + filled, inner.max.y).into()),
+            true,
+        );
+    }
+}
+
+///
+/// Modal box with a title bar, message and a row of options
+///
+/// Add the dialog after the widgets it covers so it is drawn on top, removing it redraws them.
+///
+#[derive(Debug, Clone)]
+pub struct Dialog {
+    pub bounds: IntRect,
+    pub title: String,
+    pub message: String,
+    pub options: Vec<String>,
+    pub selected: usize,
+}
+
+impl Dialog {
+    pub fn new(bounds: IntRect, title: &str, message: &str) -> Self {
+        Self {
+            bounds,
+            title: title.to_string(),
+            message: message.to_string(),
+            options: Vec::new(),
+            selected: 0,
+        }
+    }
+}
+
+impl Widget for Dialog {
+    fn bounds(&self) -> IntRect {
+        self.bounds
+    }
+
+    fn draw(&self, dt: &mut DrawTarget) {
+        let style = TextStyle {
+            alignment: Alignment::Center,
+            overflow: Overflow::Ellipsis,
+            ..Default::default()
+        };
+        outline(dt, self.bounds);
+        let inner = self.bounds.inflate(-1, -1);
+
+        let (title, body) = split_caption(inner, &style);
+        dt.draw_bitmap_text(
+            &self.title,
+            title,
+            &TextStyle {
+                inverted: true,
+                ..style
+            },
+        );
+
+        let line_height = style.font.height() as i32;
+        let options_top = body.max.y - line_height - 1;
+        dt.draw_bitmap_text(
+            &self.message,
+            IntRect::new(body.min, (body.max.x, options_top).into()),
+            &style,
+        );
+
+        if !self.options.is_empty() {
+            let width = body.width() / self.options.len() as i32;
+            for (idx, option) in self.options.iter().enumerate() {
+                let x = body.min.x + idx as i32 * width;
+                let bounds = IntRect::new(
+                    (x, options_top).into(),
+                    (x + width, options_top + line_height).into(),
+                );
+                let style = TextStyle {
+                    inverted: idx == self.selected,
+                    ..style
+                };
+                dt.draw_bitmap_text(option, bounds, &style);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widgets::PIXEL_ON;
+
+    fn rect(width: i32, height: i32) -> IntRect {
+        IntRect::new((0, 0).into(), (width, height).into())
+    }
+
+    fn render(widget: &dyn Widget) -> DrawTarget {
+        let bounds = widget.bounds();
+        let mut dt = DrawTarget::new(bounds.max.x, bounds.max.y);
+        widget.draw(&mut dt);
+        dt
+    }
+
+    fn lit(dt: &DrawTarget, x: i32, y: i32) -> bool {
+        dt.get_data()[(y * dt.width() + x) as usize] == PIXEL_ON
+    }
+
+    #[test]
+    fn knob_arc_thickens_with_the_value() {
+        // 7 pixel radius around (16, 10), the value arc sweeps clockwise from the bottom left
+        let mut knob = Knob::new(rect(32, 40), "Cut");
+        let dt = render(&knob);
+        assert!(lit(&dt, 8, 11) && !lit(&dt, 10, 11));
+        assert!(lit(&dt, 22, 11) && !lit(&dt, 21, 11));
+
+        knob.value = 0.5;
+        let dt = render(&knob);
+        assert!(lit(&dt, 10, 11));
+        assert!(!lit(&dt, 21, 11));
+
+        knob.value = 1.;
+        let dt = render(&knob);
+        assert!(lit(&dt, 10, 11));
+        assert!(lit(&dt, 21, 11));
+
+        // Name and value text below the arc
+        assert!((20..40).any(|y| (0..32).any(|x| lit(&dt, x, y))));
+        assert!(!(16..20).any(|y| (0..32).any(|x| lit(&dt, x, y))));
+    }
+
+    #[test]
+    fn list_inverts_the_selection_and_keeps_it_visible() {
+        let items = ["A", "B", "C", "D"].map(String::from).to_vec();
+        let mut list = List::new(rect(24, 20), items);
+        let dt = render(&list);
+        assert!(lit(&dt, 23, 0));
+        assert!(!lit(&dt, 23, 10));
+
+        // Scrolled to the last item, which is the second of the two visible rows
+        list.scroll(5);
+        assert_eq!(list.selected, 3);
+        let dt = render(&list);
+        assert!(!lit(&dt, 23, 0));
+        assert!(lit(&dt, 23, 10));
+    }
+
+    #[test]
+    fn meter_segments_and_peak() {
+        let mut meter = Meter::new(rect(4, 10), Orientation::Vertical);
+        meter.level = 0.5;
+        meter.peak = Some(0.8);
+        let dt = render(&meter);
+        let column: Vec<bool> = (0..10).map(|y| lit(&dt, 0, y)).collect();
+        assert_eq!(
+            column,
+            [false, false, true, false, false, true, true, false, true, true]
+        );
+        assert!((0..4).all(|x| lit(&dt, x, 9)));
+
+        let mut meter = Meter::new(rect(10, 3), Orientation::Horizontal);
+        meter.level = 0.5;
+        meter.peak = Some(0.8);
+        let dt = render(&meter);
+        let row: Vec<bool> = (0..10).map(|x| lit(&dt, x, 1)).collect();
+        assert_eq!(
+            row,
+            [true, true, false, true, true, false, false, true, false, false]
+        );
+    }
+
+    #[test]
+    fn progress_bar_fills_inside_its_outline() {
+        let mut bar = ProgressBar::new(rect(14, 6));
+        bar.progress = 0.5;
+        let dt = render(&bar);
+        assert!(lit(&dt, 0, 2) && lit(&dt, 13, 2) && lit(&dt, 5, 0) && lit(&dt, 5, 5));
+        assert!(!lit(&dt, 1, 2));
+        assert!(lit(&dt, 2, 2) && lit(&dt, 6, 3));
+        assert!(!lit(&dt, 7, 2) && !lit(&dt, 12, 2));
+        assert!(!(2..12).any(|x| lit(&dt, x, 1) || lit(&dt, x, 4)));
+    }
+
+    #[test]
+    fn dialog_draws_title_bar_and_selected_option() {
+        let mut dialog = Dialog::new(rect(40, 30), "T", "M");
+        dialog.options = vec!["Y".to_string(), "N".to_string()];
+        dialog.selected = 1;
+        let dt = render(&dialog);
+
+        // Outline
+        assert!(lit(&dt, 0, 15) && lit(&dt, 39, 15) && lit(&dt, 20, 0) && lit(&dt, 20, 29));
+
+        // Inverted title bar across the top
+        assert!(lit(&dt, 1, 1) && lit(&dt, 38, 11));
+        assert!(!lit(&dt, 1, 12));
+
+        // Only the selected second option is inverted
+        assert!(!lit(&dt, 2, 26));
+        assert!(lit(&dt, 37, 26));
+    }
+}
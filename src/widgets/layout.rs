@@ -0,0 +1,110 @@
+use crate::events::Button;
+use raqote::IntRect;
+
+/// Height of the label strip along the top of a display
+const LABEL_HEIGHT: i32 = 12;
+
+///
+/// Division of the displays into columns that line up with the soft-keys of a device
+///
+/// The Mk2 has eight display buttons above its two displays, four per display. The Mikro Mk2
+/// has the F1 to F3 buttons above its single display.
+///
+#[derive(Debug, Clone)]
+pub struct SoftKeyLayout {
+    columns: Vec<(Button, u8, IntRect)>,
+}
+
+impl SoftKeyLayout {
+    /// Layout for the Maschine Mk2, eight 64 pixel columns across both displays
+    pub fn mk2() -> Self {
+        let buttons = [
+            Button::Display1,
+            Button::Display2,
+            Button::Display3,
+            Button::Display4,
+            Button::Display5,
+            Button::Display6,
+            Button::Display7,
+            Button::Display8,
+        ];
+        Self {
+            columns: buttons
+                .iter()
+                .enumerate()
+                .map(|(idx, &button)| {
+                    let x = (idx as i32 % 4) * 64;
+                    (
+                        button,
+                        idx as u8 / 4,
+                        IntRect::new((x, 0).into(), (x + 64, 64).into()),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Layout for the Maschine Mikro Mk2, three columns under the F1 to F3 buttons
+    pub fn mikro_mk2() -> Self {
+        let edges = [0, 42, 85, 128];
+        let buttons = [Button::F1, Button::F2, Button::F3];
+        Self {
+            columns: buttons
+                .iter()
+                .enumerate()
+                .map(|(idx, &button)| {
+                    (
+                        button,
+                        0,
+                        IntRect::new((edges[idx], 0).into(), (edges[idx + 1], 64).into()),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Number of soft-key columns
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Determine if the layout has no columns
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Button above a column
+    pub fn button(&self, column: usize) -> Option<Button> {
+        self.columns.get(column).map(|&(button, _, _)| button)
+    }
+
+    /// Column belonging to a button
+    pub fn column_of(&self, button: Button) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|&(column_button, _, _)| column_button == button)
+    }
+
+    /// Display index and full height area of a column
+    pub fn column(&self, column: usize) -> Option<(u8, IntRect)> {
+        self.columns
+            .get(column)
+            .map(|&(_, display_idx, bounds)| (display_idx, bounds))
+    }
+
+    /// Display index and area of the label strip at the top of a column
+    pub fn label(&self, column: usize) -> Option<(u8, IntRect)> {
+        self.column(column).map(|(display_idx, bounds)| {
+            let max = (bounds.max.x, bounds.min.y + LABEL_HEIGHT).into();
+            (display_idx, IntRect::new(bounds.min, max))
+        })
+    }
+
+    /// Display index and area of a column below its label strip
+    pub fn body(&self, column: usize) -> Option<(u8, IntRect)> {
+        self.column(column).map(|(display_idx, bounds)| {
+            let min = (bounds.min.x, bounds.min.y + LABEL_HEIGHT).into();
+            (display_idx, IntRect::new(min, bounds.max))
+        })
+    }
+}
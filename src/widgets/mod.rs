@@ -0,0 +1,350 @@
+mod controls;
+mod layout;
+
+pub use controls::{Dialog, Knob, Label, List, Meter, Orientation, ProgressBar};
+pub use layout::SoftKeyLayout;
+
+use crate::{Device, Error};
+use raqote::{DrawTarget, IntRect};
+use std::any::Any;
+
+const PIXEL_ON: u32 = 0xFFFFFFFF;
+const PIXEL_OFF: u32 = 0xFF000000;
+
+///
+/// Element drawn on a display and managed by a `Screen`
+///
+pub trait Widget: Any {
+    ///
+    /// Area of the display occupied by the widget
+    ///
+    fn bounds(&self) -> IntRect;
+
+    ///
+    /// Draw the widget, its bounds have already been cleared
+    ///
+    /// **Arguments**
+    /// - dt - Display to draw onto
+    fn draw(&self, dt: &mut DrawTarget);
+}
+
+///
+/// Handle to a widget added to a `Screen`
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WidgetId(usize);
+
+struct Entry {
+    id: WidgetId,
+    display_idx: u8,
+    widget: Box<dyn Widget>,
+    dirty: bool,
+
+    /// Bounds before the widget was borrowed with `get_mut`, if it has been since the last render
+    modified_from: Option<IntRect>,
+
+    /// Pixels of the widget when it was last drawn, to tell whether a modification changed it
+    drawn: Vec<u32>,
+}
+
+///
+/// Retained set of widgets spread across the displays of a device
+///
+/// Widgets are drawn in the order they were added, only widgets that have changed (or that
+/// overlap a changed widget) are redrawn. Borrowing a widget with `get_mut` without changing
+/// how it looks does not redraw it.
+///
+#[derive(Default)]
+pub struct Screen {
+    entries: Vec<Entry>,
+    cleared: Vec<(u8, IntRect)>,
+    next_id: usize,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Add a widget on top of any existing widgets
+    ///
+    /// **Arguments**
+    /// - display_idx - Display the widget is drawn on
+    /// - widget - Widget to add
+    pub fn add<W: Widget>(&mut self, display_idx: u8, widget: W) -> WidgetId {
+        let id = WidgetId(self.next_id);
+        self.next_id += 1;
+        self.entries.push(Entry {
+            id,
+            display_idx,
+            drawn: snapshot(&widget),
+            widget: Box::new(widget),
+            dirty: true,
+            modified_from: None,
+        });
+        id
+    }
+
+    ///
+    /// Remove a widget, the area it occupied is cleared on the next render
+    ///
+    /// **Arguments**
+    /// - id - Widget to remove
+    pub fn remove(&mut self, id: WidgetId) {
+        if let Some(pos) = self.entries.iter().position(|entry| entry.id == id) {
+            let entry = self.entries.remove(pos);
+            let bounds = entry.widget.bounds();
+            self.mark_overlapping(entry.display_idx, bounds, 0);
+            self.cleared.push((entry.display_idx, bounds));
+        }
+    }
+
+    ///
+    /// Access a widget for reading
+    ///
+    /// **Arguments**
+    /// - id - Widget to access
+    pub fn get<W: Widget>(&self, id: WidgetId) -> Option<&W> {
+        let entry = self.entries.iter().find(|entry| entry.id == id)?;
+        (entry.widget.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    ///
+    /// Access a widget for modification, it is redrawn on the next render if it has changed
+    ///
+    /// **Arguments**
+    /// - id - Widget to modify
+    pub fn get_mut<W: Widget>(&mut self, id: WidgetId) -> Option<&mut W> {
+        let entry = self.entries.iter_mut().find(|entry| entry.id == id)?;
+        let bounds = entry.widget.bounds();
+        entry.modified_from.get_or_insert(bounds);
+        (entry.widget.as_mut() as &mut dyn Any).downcast_mut()
+    }
+
+    /// Mark every widget to be redrawn, eg after the display was drawn on directly
+    pub fn invalidate(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.dirty = true;
+        }
+    }
+
+    ///
+    /// Draw any changed widgets onto the device displays and commit them
+    ///
+    /// **Arguments**
+    /// - device - Device owning the displays
+    pub fn render<D: Device + ?Sized>(&mut self, device: &mut D) -> Result<(), Error> {
+        let mut changed: Vec<u8> = Vec::new();
+
+        // Widgets that moved leave their old area to clear, others are compared to how they
+        // were last drawn
+        for entry in self.entries.iter_mut() {
+            let Some(previous) = entry.modified_from.take() else {
+                continue;
+            };
+            if entry.widget.bounds() != previous {
+                self.cleared.push((entry.display_idx, previous));
+                entry.drawn = snapshot(entry.widget.as_ref());
+                entry.dirty = true;
+            } else {
+                let drawn = snapshot(entry.widget.as_ref());
+                if drawn != entry.drawn {
+                    entry.drawn = drawn;
+                    entry.dirty = true;
+                }
+            }
+        }
+
+        // Anything drawn over or under a changed area needs drawing again
+        for (display_idx, bounds) in std::mem::take(&mut self.cleared) {
+            let dt = device.get_display(display_idx)?;
+            fill(dt, bounds, false);
+            self.mark_overlapping(display_idx, bounds, 0);
+            if !changed.contains(&display_idx) {
+                changed.push(display_idx);
+            }
+        }
+        for pos in 0..self.entries.len() {
+            if self.entries[pos].dirty {
+                let entry = &self.entries[pos];
+                self.mark_overlapping(entry.display_idx, entry.widget.bounds(), pos + 1);
+            }
+        }
+
+        for entry in self.entries.iter_mut().filter(|entry| entry.dirty) {
+            let dt = device.get_display(entry.display_idx)?;
+            fill(dt, entry.widget.bounds(), false);
+            entry.widget.draw(dt);
+            entry.dirty = false;
+            if !changed.contains(&entry.display_idx) {
+                changed.push(entry.display_idx);
+            }
+        }
+
+        for display_idx in changed {
+            device.commit_display(display_idx)?;
+        }
+
+        Ok(())
+    }
+
+    /// Mark widgets from `start` onwards that overlap an area as needing a redraw
+    fn mark_overlapping(&mut self, display_idx: u8, bounds: IntRect, start: usize) {
+        for entry in self.entries.iter_mut().skip(start) {
+            if entry.display_idx == display_idx && entry.widget.bounds().intersects(&bounds) {
+                entry.dirty = true;
+            }
+        }
+    }
+}
+
+/// Pixels within the bounds of a widget drawn on its own
+fn snapshot(widget: &dyn Widget) -> Vec<u32> {
+    let bounds = widget.bounds();
+    let mut dt = DrawTarget::new(bounds.max.x.max(0), bounds.max.y.max(0));
+    widget.draw(&mut dt);
+
+    let width = dt.width();
+    let data = dt.get_data();
+    let mut pixels = Vec::new();
+    for y in bounds.min.y.max(0)..bounds.max.y {
+        for x in bounds.min.x.max(0)..bounds.max.x {
+            pixels.push(data[(y * width + x) as usize]);
+        }
+    }
+    pixels
+}
+
+/// Fill an area of the display, clipped to the display size
+pub(crate) fn fill(dt: &mut DrawTarget, bounds: IntRect, lit: bool) {
+    let width = dt.width();
+    let area =
+        bounds.intersection_unchecked(&IntRect::new((0, 0).into(), (width, dt.height()).into()));
+    let data = dt.get_data_mut();
+    for y in area.min.y..area.max.y {
+        for x in area.min.x..area.max.x {
+            data[(y * width + x) as usize] = if lit { PIXEL_ON } else { PIXEL_OFF };
+        }
+    }
+}
+
+/// Draw a one pixel outline just inside an area
+pub(crate) fn outline(dt: &mut DrawTarget, bounds: IntRect) {
+    let (min, max) = (bounds.min, bounds.max);
+    fill(dt, IntRect::new(min, (max.x, min.y + 1).into()), true);
+    fill(dt, IntRect::new((min.x, max.y - 1).into(), max), true);
+    fill(dt, IntRect::new(min, (min.x + 1, max.y).into()), true);
+    fill(dt, IntRect::new((max.x - 1, min.y).into(), max), true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::testing::TestDevice;
+
+    const MARK: u32 = 0xFF123456;
+
+    /// Solid block, lit or not
+    struct Block {
+        bounds: IntRect,
+        lit: bool,
+    }
+
+    impl Block {
+        fn new(x0: i32, x1: i32, lit: bool) -> Self {
+            Self {
+                bounds: IntRect::new((x0, 0).into(), (x1, 8).into()),
+                lit,
+            }
+        }
+    }
+
+    impl Widget for Block {
+        fn bounds(&self) -> IntRect {
+            self.bounds
+        }
+
+        fn draw(&self, dt: &mut DrawTarget) {
+            fill(dt, self.bounds, self.lit);
+        }
+    }
+
+    fn pixel(device: &mut TestDevice, x: i32) -> u32 {
+        device.get_display(0).unwrap().get_data()[x as usize + 256 * 4]
+    }
+
+    /// Overwrite a pixel, it is only restored if the widget there is redrawn
+    fn mark(device: &mut TestDevice, x: i32) {
+        device.get_display(0).unwrap().get_data_mut()[x as usize + 256 * 4] = MARK;
+    }
+
+    #[test]
+    fn only_changed_widgets_are_redrawn() {
+        let mut device = TestDevice::new().unwrap();
+        let mut screen = Screen::new();
+        let a = screen.add(0, Block::new(0, 10, true));
+        screen.add(0, Block::new(20, 30, true));
+        screen.render(&mut device).unwrap();
+        assert_eq!(pixel(&mut device, 5), PIXEL_ON);
+
+        mark(&mut device, 5);
+        mark(&mut device, 25);
+        screen.get_mut::<Block>(a).unwrap().lit = true;
+        screen.render(&mut device).unwrap();
+        assert_eq!(pixel(&mut device, 5), MARK);
+        assert_eq!(pixel(&mut device, 25), MARK);
+
+        screen.get_mut::<Block>(a).unwrap().lit = false;
+        screen.render(&mut device).unwrap();
+        assert_eq!(pixel(&mut device, 5), PIXEL_OFF);
+        assert_eq!(pixel(&mut device, 25), MARK);
+    }
+
+    #[test]
+    fn widgets_over_a_changed_widget_are_redrawn() {
+        let mut device = TestDevice::new().unwrap();
+        let mut screen = Screen::new();
+        let below = screen.add(0, Block::new(0, 20, true));
+        screen.add(0, Block::new(10, 30, true));
+        screen.add(0, Block::new(40, 50, true));
+        screen.render(&mut device).unwrap();
+
+        mark(&mut device, 25);
+        mark(&mut device, 45);
+        screen.get_mut::<Block>(below).unwrap().lit = false;
+        screen.render(&mut device).unwrap();
+        assert_eq!(pixel(&mut device, 5), PIXEL_OFF);
+        assert_eq!(pixel(&mut device, 15), PIXEL_ON);
+        assert_eq!(pixel(&mut device, 25), PIXEL_ON);
+        assert_eq!(pixel(&mut device, 45), MARK);
+    }
+
+    #[test]
+    fn moving_a_widget_clears_its_old_area() {
+        let mut device = TestDevice::new().unwrap();
+        let mut screen = Screen::new();
+        let block = screen.add(0, Block::new(0, 10, true));
+        screen.render(&mut device).unwrap();
+
+        screen.get_mut::<Block>(block).unwrap().bounds = Block::new(20, 30, true).bounds;
+        screen.render(&mut device).unwrap();
+        assert_eq!(pixel(&mut device, 5), PIXEL_OFF);
+        assert_eq!(pixel(&mut device, 25), PIXEL_ON);
+    }
+
+    #[test]
+    fn removing_a_widget_redraws_what_was_under_it() {
+        let mut device = TestDevice::new().unwrap();
+        let mut screen = Screen::new();
+        screen.add(0, Block::new(0, 20, true));
+        let top = screen.add(0, Block::new(10, 30, false));
+        screen.render(&mut device).unwrap();
+        assert_eq!(pixel(&mut device, 15), PIXEL_OFF);
+
+        screen.remove(top);
+        screen.render(&mut device).unwrap();
+        assert_eq!(pixel(&mut device, 15), PIXEL_ON);
+        assert_eq!(pixel(&mut device, 25), PIXEL_OFF);
+    }
+}